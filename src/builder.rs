@@ -1,12 +1,25 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use reqwest::Certificate;
 use url::Url;
 
+use crate::checkin::Feature;
+use crate::coalesce::CoalesceOptions;
+use crate::collator::CollatorOptions;
+use crate::ds_correlation::Correlation;
+use crate::dynamic_fact::DynamicFact;
+use crate::filter::EventFilter;
 use crate::identity::AnonymousDistinctId;
 use crate::storage::Storage;
+use crate::submitter::SubmitterOverflowStrategy;
+use crate::system_snapshot::{HostNamePolicy, SystemSnapshotter};
+use crate::transformer::EventTransformer;
 use crate::transport::{Transport, TransportsError};
-use crate::{DeviceId, DistinctId, Map, system_snapshot::SystemSnapshotter};
+use crate::worker::WorkerOptions;
+use crate::{DeviceId, DistinctId, Map};
 use crate::{Groups, Recorder, Worker};
 
 #[derive(Default, Clone)]
@@ -17,10 +30,33 @@ pub struct Builder {
     enable_reporting: bool,
     endpoint: Option<String>,
     facts: Option<Map>,
+    dynamic_facts: Option<HashMap<String, DynamicFact>>,
     groups: Option<Groups>,
     proxy: Option<Url>,
     certificate: Option<Certificate>,
     timeout: Option<Duration>,
+    coalesce: Option<CoalesceOptions>,
+    max_batch_size: Option<usize>,
+    max_pending_events: Option<usize>,
+    overflow_strategy: SubmitterOverflowStrategy,
+    flush_interval: Option<Duration>,
+    flush_interval_jitter: Option<Duration>,
+    snapshot_ttl: Option<Duration>,
+    host_name_policy: HostNamePolicy,
+    capture_process_stats: bool,
+    checkin_ttl: Option<Duration>,
+    configuration_refresh_interval: Option<Duration>,
+    configuration_refresh_jitter: Option<f64>,
+    anonymous_id_max_age: Option<Duration>,
+    default_features: Option<HashMap<String, Feature<serde_json::Value>>>,
+    event_filter: Option<Arc<dyn EventFilter>>,
+    event_transformer: Option<Arc<dyn EventTransformer>>,
+    correlation: Option<Correlation>,
+    storage_path: Option<PathBuf>,
+    verify_transport_on_startup: bool,
+    feature_pointer_depth: Option<usize>,
+    collect_nix_versions: bool,
+    disk_paths: Option<Vec<PathBuf>>,
 }
 
 impl Builder {
@@ -32,10 +68,33 @@ impl Builder {
             enable_reporting: true,
             endpoint: None,
             facts: None,
+            dynamic_facts: None,
             groups: None,
             proxy: None,
             certificate: None,
             timeout: None,
+            coalesce: None,
+            max_batch_size: None,
+            max_pending_events: None,
+            overflow_strategy: SubmitterOverflowStrategy::default(),
+            flush_interval: None,
+            flush_interval_jitter: None,
+            snapshot_ttl: None,
+            host_name_policy: HostNamePolicy::default(),
+            capture_process_stats: false,
+            checkin_ttl: None,
+            configuration_refresh_interval: None,
+            configuration_refresh_jitter: None,
+            anonymous_id_max_age: None,
+            default_features: None,
+            event_filter: None,
+            event_transformer: None,
+            correlation: None,
+            storage_path: None,
+            verify_transport_on_startup: false,
+            feature_pointer_depth: None,
+            collect_nix_versions: false,
+            disk_paths: None,
         }
     }
 
@@ -111,6 +170,28 @@ impl Builder {
         self
     }
 
+    /// Add a fact whose value is computed by `f` each time an event is recorded.
+    /// If `f` panics, the fact is recorded as `null`.
+    pub fn dynamic_fact(
+        mut self,
+        key: impl Into<String>,
+        f: impl Fn() -> serde_json::Value + Send + Sync + 'static,
+    ) -> Self {
+        self.set_dynamic_fact(key, f);
+        self
+    }
+
+    pub fn set_dynamic_fact(
+        &mut self,
+        key: impl Into<String>,
+        f: impl Fn() -> serde_json::Value + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.dynamic_facts
+            .get_or_insert_with(Default::default)
+            .insert(key.into(), DynamicFact::new(f));
+        self
+    }
+
     pub fn endpoint(mut self, endpoint: Option<String>) -> Self {
         self.set_endpoint(endpoint);
         self
@@ -182,29 +263,317 @@ impl Builder {
         self
     }
 
+    /// Merge identical events recorded in rapid succession into a single event with a `$count` property.
+    /// Coalescing is disabled by default.
+    pub fn coalesce_events(mut self, coalesce: Option<CoalesceOptions>) -> Self {
+        self.set_coalesce_events(coalesce);
+        self
+    }
+
+    pub fn set_coalesce_events(&mut self, coalesce: Option<CoalesceOptions>) -> &mut Self {
+        self.coalesce = coalesce;
+        self
+    }
+
+    /// The maximum number of events submitted in a single request.
+    /// Larger batches are split into multiple requests. Unlimited by default.
+    /// The server may ask for smaller batches, but not larger ones.
+    pub fn max_batch_size(mut self, max_batch_size: Option<usize>) -> Self {
+        self.set_max_batch_size(max_batch_size);
+        self
+    }
+
+    pub fn set_max_batch_size(&mut self, max_batch_size: Option<usize>) -> &mut Self {
+        self.max_batch_size = max_batch_size;
+        self
+    }
+
+    /// The maximum number of events waiting to be submitted, beyond which the oldest are dropped.
+    /// Identity changes like `$identify` are kept even beyond the limit.
+    /// Unlimited by default, or as many as the Submitter's queue holds with `SubmitterOverflowStrategy::DropOldest`.
+    /// The server may ask for a lower limit, but not a higher one.
+    pub fn max_pending_events(mut self, max_pending_events: Option<usize>) -> Self {
+        self.set_max_pending_events(max_pending_events);
+        self
+    }
+
+    pub fn set_max_pending_events(&mut self, max_pending_events: Option<usize>) -> &mut Self {
+        self.max_pending_events = max_pending_events;
+        self
+    }
+
+    /// What to do with new events when the Submitter can't keep up.
+    /// Defaults to `SubmitterOverflowStrategy::Block`.
+    pub fn overflow_strategy(mut self, overflow_strategy: SubmitterOverflowStrategy) -> Self {
+        self.set_overflow_strategy(overflow_strategy);
+        self
+    }
+
+    pub fn set_overflow_strategy(
+        &mut self,
+        overflow_strategy: SubmitterOverflowStrategy,
+    ) -> &mut Self {
+        self.overflow_strategy = overflow_strategy;
+        self
+    }
+
+    /// How often to submit recorded events when the server doesn't say.
+    /// Defaults to 30 seconds, and can't be shorter than a second.
+    pub fn flush_interval(mut self, interval: Option<Duration>) -> Self {
+        self.set_flush_interval(interval);
+        self
+    }
+
+    pub fn set_flush_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Delay each flush by a random amount up to `jitter`, so tools started together don't submit together.
+    /// No jitter by default.
+    pub fn flush_interval_jitter(mut self, jitter: Option<Duration>) -> Self {
+        self.set_flush_interval_jitter(jitter);
+        self
+    }
+
+    pub fn set_flush_interval_jitter(&mut self, jitter: Option<Duration>) -> &mut Self {
+        self.flush_interval_jitter = jitter;
+        self
+    }
+
+    /// How long a SystemSnapshot is reused across events before taking a new one.
+    /// Defaults to five seconds. `$identify` events and check-ins always take a fresh snapshot.
+    pub fn snapshot_ttl(mut self, snapshot_ttl: Option<Duration>) -> Self {
+        self.set_snapshot_ttl(snapshot_ttl);
+        self
+    }
+
+    pub fn set_snapshot_ttl(&mut self, snapshot_ttl: Option<Duration>) -> &mut Self {
+        self.snapshot_ttl = snapshot_ttl;
+        self
+    }
+
+    /// How the system snapshot reports the host name, in events and session properties alike.
+    /// Defaults to `HostNamePolicy::Hashed`.
+    pub fn host_name_policy(mut self, policy: HostNamePolicy) -> Self {
+        self.set_host_name_policy(policy);
+        self
+    }
+
+    pub fn set_host_name_policy(&mut self, policy: HostNamePolicy) -> &mut Self {
+        self.host_name_policy = policy;
+        self
+    }
+
+    /// How long a stored check-in is trusted to bootstrap feature flags before a fresh one arrives.
+    /// Defaults to seven days.
+    pub fn checkin_ttl(mut self, checkin_ttl: Option<Duration>) -> Self {
+        self.set_checkin_ttl(checkin_ttl);
+        self
+    }
+
+    pub fn set_checkin_ttl(&mut self, checkin_ttl: Option<Duration>) -> &mut Self {
+        self.checkin_ttl = checkin_ttl;
+        self
+    }
+
+    /// How often to refresh the feature configuration when the server doesn't say.
+    /// Defaults to two hours, and can't be shorter than a minute.
+    pub fn configuration_refresh_interval(mut self, interval: Option<Duration>) -> Self {
+        self.set_configuration_refresh_interval(interval);
+        self
+    }
+
+    pub fn set_configuration_refresh_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.configuration_refresh_interval = interval;
+        self
+    }
+
+    /// How far each refresh may randomly move, as a fraction of the refresh interval, so clients started together don't check in together.
+    /// The first refresh after startup is also delayed by up to this fraction. Defaults to 0.1, and can't be more than 0.5.
+    pub fn configuration_refresh_jitter(mut self, fraction: Option<f64>) -> Self {
+        self.set_configuration_refresh_jitter(fraction);
+        self
+    }
+
+    pub fn set_configuration_refresh_jitter(&mut self, fraction: Option<f64>) -> &mut Self {
+        self.configuration_refresh_jitter = fraction;
+        self
+    }
+
+    /// Replace a stored anonymous distinct ID with a fresh one once it is older than `max_age`.
+    /// The device ID and distinct ID are unaffected. Rotation is off by default.
+    pub fn anonymous_id_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.set_anonymous_id_max_age(max_age);
+        self
+    }
+
+    pub fn set_anonymous_id_max_age(&mut self, max_age: Option<Duration>) -> &mut Self {
+        self.anonymous_id_max_age = max_age;
+        self
+    }
+
+    /// Features served until a check-in provides them, such as before the first check-in or on an offline machine.
+    pub fn default_features(
+        mut self,
+        default_features: Option<HashMap<String, Feature<serde_json::Value>>>,
+    ) -> Self {
+        self.set_default_features(default_features);
+        self
+    }
+
+    pub fn set_default_features(
+        &mut self,
+        default_features: Option<HashMap<String, Feature<serde_json::Value>>>,
+    ) -> &mut Self {
+        self.default_features = default_features;
+        self
+    }
+
+    pub fn default_feature(
+        mut self,
+        key: impl Into<String>,
+        variant: impl Into<serde_json::Value>,
+        payload: Option<serde_json::Value>,
+    ) -> Self {
+        self.set_default_feature(key, variant, payload);
+        self
+    }
+
+    pub fn set_default_feature(
+        &mut self,
+        key: impl Into<String>,
+        variant: impl Into<serde_json::Value>,
+        payload: Option<serde_json::Value>,
+    ) -> &mut Self {
+        self.default_features
+            .get_or_insert_with(Default::default)
+            .insert(key.into(), Feature::new(variant, payload));
+        self
+    }
+
+    /// Drop recorded events which the filter excludes, before they are submitted.
+    pub fn with_event_filter(mut self, filter: impl EventFilter) -> Self {
+        self.set_event_filter(filter);
+        self
+    }
+
+    pub fn set_event_filter(&mut self, filter: impl EventFilter) -> &mut Self {
+        self.event_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Rewrite recorded events before they are collated, for example to scrub PII with [`crate::PiiScrubber`].
+    pub fn with_event_transformer(mut self, transformer: impl EventTransformer) -> Self {
+        self.set_event_transformer(transformer);
+        self
+    }
+
+    pub fn set_event_transformer(&mut self, transformer: impl EventTransformer) -> &mut Self {
+        self.event_transformer = Some(Arc::new(transformer));
+        self
+    }
+
+    /// Correlate with a parent process using this data, instead of only what `Correlation::import` finds.
+    /// It takes precedence over imported data, which fills in what it leaves out. See `Correlation::merge`.
+    pub fn with_correlation(mut self, correlation: Correlation) -> Self {
+        self.set_correlation(correlation);
+        self
+    }
+
+    pub fn set_correlation(&mut self, correlation: Correlation) -> &mut Self {
+        self.correlation = Some(correlation);
+        self
+    }
+
+    /// Where the default storage is kept, instead of the platform's state directory.
+    /// The `DETSYS_IDS_STORAGE_PATH` environment variable takes precedence.
+    pub fn storage_path(mut self, storage_path: Option<PathBuf>) -> Self {
+        self.set_storage_path(storage_path);
+        self
+    }
+
+    pub fn set_storage_path(&mut self, storage_path: Option<PathBuf>) -> &mut Self {
+        self.storage_path = storage_path;
+        self
+    }
+
+    /// Check that the transport can reach its backend before the worker starts, logging a warning if it can't.
+    /// Off by default. Events are still recorded and submitted either way.
+    pub fn verify_transport_on_startup(mut self, verify: bool) -> Self {
+        self.set_verify_transport_on_startup(verify);
+        self
+    }
+
+    pub fn set_verify_transport_on_startup(&mut self, verify: bool) -> &mut Self {
+        self.verify_transport_on_startup = verify;
+        self
+    }
+
+    /// How many pointers `Recorder::get_feature_ptr` and `Recorder::get_feature_resolved` follow before giving up.
+    /// Defaults to five.
+    pub fn feature_pointer_depth(mut self, depth: Option<usize>) -> Self {
+        self.set_feature_pointer_depth(depth);
+        self
+    }
+
+    pub fn set_feature_pointer_depth(&mut self, depth: Option<usize>) -> &mut Self {
+        self.feature_pointer_depth = depth;
+        self
+    }
+
+    /// Add `nix_version`, `nix_flavor`, and `determinate_nixd_version` to the system snapshot, by running `nix --version` and `determinate-nixd version`.
+    /// Off by default. The commands run once per process, and versions that can't be found are left out.
+    pub fn collect_nix_versions(mut self, collect: bool) -> Self {
+        self.set_collect_nix_versions(collect);
+        self
+    }
+
+    pub fn set_collect_nix_versions(&mut self, collect: bool) -> &mut Self {
+        self.collect_nix_versions = collect;
+        self
+    }
+
+    /// Add the process's `process_rss_bytes`, `process_cpu_seconds`, and `open_fd_count` to each event.
+    /// Off by default. They're read at most once per second.
+    pub fn capture_process_stats(mut self, capture: bool) -> Self {
+        self.set_capture_process_stats(capture);
+        self
+    }
+
+    pub fn set_capture_process_stats(&mut self, capture: bool) -> &mut Self {
+        self.capture_process_stats = capture;
+        self
+    }
+
+    /// Report the size and free space of the disks holding these paths in the system snapshot.
+    /// Defaults to `/nix` if it exists, or else `/`. Paths that don't exist are skipped.
+    pub fn disk_paths(mut self, disk_paths: Option<Vec<PathBuf>>) -> Self {
+        self.set_disk_paths(disk_paths);
+        self
+    }
+
+    pub fn set_disk_paths(&mut self, disk_paths: Option<Vec<PathBuf>>) -> &mut Self {
+        self.disk_paths = disk_paths;
+        self
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn try_build(mut self) -> Result<(Recorder, Worker), TransportsError> {
         let transport = self.transport().await?;
+        let storage = crate::storage::DefaultStorageChain::new(self.storage_path.take()).await;
+        let snapshotter = crate::system_snapshot::Generic::with_disk_paths(self.disk_paths.take());
 
-        Ok(self
-            .build_with(
-                transport,
-                crate::system_snapshot::Generic::default(),
-                crate::storage::DefaultStorageChain::new().await,
-            )
-            .await)
+        Ok(self.build_with(transport, snapshotter, storage).await)
     }
 
     #[tracing::instrument(skip(self))]
     pub async fn build_or_default(mut self) -> (Recorder, Worker) {
         let transport = self.transport_or_default().await;
+        let storage = crate::storage::DefaultStorageChain::new(self.storage_path.take()).await;
+        let snapshotter = crate::system_snapshot::Generic::with_disk_paths(self.disk_paths.take());
 
-        self.build_with(
-            transport,
-            crate::system_snapshot::Generic::default(),
-            crate::storage::DefaultStorageChain::new().await,
-        )
-        .await
+        self.build_with(transport, snapshotter, storage).await
     }
 
     #[tracing::instrument(skip(self, snapshotter, storage))]
@@ -237,18 +606,49 @@ impl Builder {
         storage: P,
     ) -> (Recorder, Worker) {
         Worker::new(
-            self.anonymous_distinct_id.take(),
-            self.distinct_id.take(),
-            self.device_id.take(),
-            self.facts.take(),
-            self.groups.take(),
-            snapshotter,
+            self.worker_options(),
+            crate::system_snapshot::NixVersions::new(snapshotter, self.collect_nix_versions),
             storage,
             transport,
         )
         .await
     }
 
+    fn worker_options(&mut self) -> WorkerOptions {
+        WorkerOptions {
+            collator: CollatorOptions {
+                anonymous_distinct_id: self.anonymous_distinct_id.take(),
+                distinct_id: self.distinct_id.take(),
+                device_id: self.device_id.take(),
+                facts: self.facts.take(),
+                dynamic_facts: self.dynamic_facts.take(),
+                groups: self.groups.take(),
+                correlation: Correlation::supplied_or_imported(
+                    self.correlation.take(),
+                    Correlation::import,
+                ),
+                coalesce: self.coalesce.take(),
+                event_filter: self.event_filter.take(),
+                event_transformer: self.event_transformer.take(),
+                snapshot_ttl: self.snapshot_ttl,
+                host_name_policy: self.host_name_policy,
+                capture_process_stats: self.capture_process_stats,
+                checkin_ttl: self.checkin_ttl,
+                anonymous_id_max_age: self.anonymous_id_max_age,
+                overflow_strategy: self.overflow_strategy,
+            },
+            max_batch_size: self.max_batch_size,
+            max_pending_events: self.max_pending_events,
+            flush_interval: self.flush_interval,
+            flush_interval_jitter: self.flush_interval_jitter,
+            configuration_refresh_interval: self.configuration_refresh_interval,
+            configuration_refresh_jitter: self.configuration_refresh_jitter,
+            default_features: self.default_features.take(),
+            verify_transport_on_startup: self.verify_transport_on_startup,
+            feature_pointer_depth: self.feature_pointer_depth,
+        }
+    }
+
     async fn transport_or_default(&mut self) -> crate::transport::Transports {
         match self.transport().await {
             Ok(t) => {
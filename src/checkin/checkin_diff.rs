@@ -1,36 +1,170 @@
-use super::{Checkin, coherent_feature_flag_diff::CoherentFlagDiff};
+use super::{Checkin, FlagChange, coherent_feature_flag_diff::CoherentFlagDiff};
 
-pub(crate) trait CheckinDiff {
-    fn diff(&self, prev: Option<&Checkin>) -> Option<String>;
+/// What changed between two check-ins.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CheckinChangeset {
+    /// A description of each server option that changed.
+    pub server_options_changes: Vec<String>,
+
+    /// Every feature added, removed, or modified, ordered by key.
+    pub flag_changes: Vec<FlagChange>,
 }
 
-impl CheckinDiff for Checkin {
-    fn diff(&self, prev: Option<&Checkin>) -> Option<String> {
-        let Some(prev) = prev else {
-            return Some(String::from(
-                "Current is populated, but the previous was None.",
-            ));
-        };
+impl std::fmt::Display for CheckinChangeset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flag_changes = self.flag_changes.iter().map(FlagChange::to_string);
+        let lines: Vec<String> = self
+            .server_options_changes
+            .iter()
+            .cloned()
+            .chain(flag_changes)
+            .collect();
 
-        if prev == self {
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl Checkin {
+    /// What changed since `prev`, or None if nothing did.
+    /// The first check-in is always a change, with every feature added and server options compared to their defaults.
+    pub(crate) fn changeset(&self, prev: Option<&Checkin>) -> Option<CheckinChangeset> {
+        if prev == Some(self) {
             return None;
         }
 
-        let mut delta: Vec<String> = vec![];
+        let empty = Checkin::default();
+        let prev = prev.unwrap_or(&empty);
+
+        Some(CheckinChangeset {
+            server_options_changes: self.server_options.diff(&prev.server_options),
+            flag_changes: self.options.structured_diff(&prev.options),
+        })
+    }
+
+    /// Properties of the `$feature_flags_changed` event for a change from `prev` to this check-in.
+    /// The first check-in is marked `initial` instead of listing every feature as added.
+    pub(crate) fn change_properties(&self, prev: Option<&Checkin>) -> crate::Map {
+        let Some(prev) = prev else {
+            return crate::Map::from_iter([("initial".into(), true.into())]);
+        };
 
-        delta.extend(self.server_options.diff(&prev.server_options));
-        delta.extend(self.options.diff(&prev.options));
+        let changed = self.options.changed_keys(&prev.options);
 
-        Some(delta.join("\n"))
+        crate::Map::from_iter([
+            ("initial".into(), false.into()),
+            ("added".into(), changed.added.into()),
+            ("removed".into(), changed.removed.into()),
+            ("modified".into(), changed.modified.into()),
+            (
+                "server_options".into(),
+                self.server_options.diff(&prev.server_options).into(),
+            ),
+        ])
     }
 }
 
-impl CheckinDiff for Option<Checkin> {
-    fn diff(&self, prev: Option<&Checkin>) -> Option<String> {
-        match (self, prev) {
-            (None, None) => None,
-            (None, Some(_)) => Some(String::from("Current is none, but previous was not.")),
-            (Some(s), prev) => s.diff(prev),
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::super::{Feature, FlagChange, ServerOptions};
+    use super::{Checkin, CheckinChangeset};
+
+    fn feat(variant: serde_json::Value) -> Arc<Feature<serde_json::Value>> {
+        Arc::new(Feature {
+            variant,
+            payload: None,
+            experiment_id: None,
+        })
+    }
+
+    fn checkin(
+        refresh_interval_seconds: Option<u64>,
+        features: &[(&str, Arc<Feature<serde_json::Value>>)],
+    ) -> Checkin {
+        Checkin {
+            server_options: ServerOptions {
+                refresh_interval_seconds,
+                ..ServerOptions::default()
+            },
+            options: features
+                .iter()
+                .map(|(key, feature)| (key.to_string(), feature.clone()))
+                .collect(),
         }
     }
+
+    #[test]
+    fn unchanged_is_none() {
+        let current = checkin(Some(600), &[("a", feat(true.into()))]);
+
+        assert_eq!(current.changeset(Some(&current.clone())), None);
+    }
+
+    #[test]
+    fn server_options_and_flags_change_together() {
+        let prev = checkin(
+            None,
+            &[("kept", feat(true.into())), ("dropped", feat(true.into()))],
+        );
+        let current = checkin(
+            Some(600),
+            &[("kept", feat(false.into())), ("new", feat("b".into()))],
+        );
+
+        assert_eq!(
+            current.changeset(Some(&prev)),
+            Some(CheckinChangeset {
+                server_options_changes: vec!["Refresh interval: None -> Some(600s)".into()],
+                flag_changes: vec![
+                    FlagChange::Removed {
+                        key: "dropped".into(),
+                        feature: feat(true.into()),
+                    },
+                    FlagChange::Modified {
+                        key: "kept".into(),
+                        previous: feat(true.into()),
+                        current: feat(false.into()),
+                    },
+                    FlagChange::Added {
+                        key: "new".into(),
+                        feature: feat("b".into()),
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn first_checkin_adds_everything() {
+        let current = checkin(None, &[("a", feat(true.into()))]);
+
+        assert_eq!(
+            current.changeset(None),
+            Some(CheckinChangeset {
+                server_options_changes: vec![],
+                flag_changes: vec![FlagChange::Added {
+                    key: "a".into(),
+                    feature: feat(true.into()),
+                }],
+            })
+        );
+        assert_eq!(
+            Checkin::default().changeset(None),
+            Some(CheckinChangeset::default())
+        );
+    }
+
+    #[test]
+    fn display_lists_server_options_before_flags() {
+        let prev = checkin(None, &[]);
+        let current = checkin(Some(600), &[("a", feat(true.into()))]);
+
+        let display = current.changeset(Some(&prev)).unwrap().to_string();
+        let lines: Vec<&str> = display.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "Refresh interval: None -> Some(600s)");
+        assert!(lines[1].starts_with("+feature:a:"), "{display}");
+    }
 }
@@ -1,37 +1,117 @@
 use std::collections::BTreeSet;
+use std::sync::Arc;
 
-use super::CoherentFeatureFlags;
+use super::{CoherentFeatureFlags, Feature};
 
 pub(crate) trait CoherentFlagDiff {
+    #[cfg(test)]
     fn diff(&self, prev: &CoherentFeatureFlags) -> Vec<String>;
+    fn structured_diff(&self, prev: &CoherentFeatureFlags) -> Vec<FlagChange>;
+    fn changed_keys(&self, prev: &CoherentFeatureFlags) -> ChangedKeys;
+}
+
+/// The names of features which changed between two check-ins, each in sorted order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ChangedKeys {
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) modified: Vec<String>,
+}
+
+/// How one feature changed between two check-ins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FlagChange {
+    Added {
+        key: String,
+        feature: Arc<Feature<serde_json::Value>>,
+    },
+    Removed {
+        key: String,
+        feature: Arc<Feature<serde_json::Value>>,
+    },
+    Modified {
+        key: String,
+        previous: Arc<Feature<serde_json::Value>>,
+        current: Arc<Feature<serde_json::Value>>,
+    },
+}
+
+impl FlagChange {
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Added { key, .. } | Self::Removed { key, .. } | Self::Modified { key, .. } => key,
+        }
+    }
+}
+
+impl std::fmt::Display for FlagChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added { key, feature } => write!(f, "+feature:{key}:{feature:?}"),
+            Self::Removed { key, feature } => write!(f, "-feature:{key}:{feature:?}"),
+            Self::Modified {
+                key,
+                previous,
+                current,
+            } => write!(f, "~feature:{key}:{}", current.diff(previous)),
+        }
+    }
 }
 
 impl CoherentFlagDiff for CoherentFeatureFlags {
+    #[cfg(test)]
     fn diff(&self, prev: &CoherentFeatureFlags) -> Vec<String> {
+        self.structured_diff(prev)
+            .iter()
+            .map(FlagChange::to_string)
+            .collect()
+    }
+
+    /// Every feature added, removed, or modified since `prev`, ordered by key.
+    fn structured_diff(&self, prev: &CoherentFeatureFlags) -> Vec<FlagChange> {
         if self == prev {
             return vec![];
         }
 
-        let mut changes: Vec<String> = vec![];
+        let mut changes: Vec<FlagChange> = vec![];
 
-        let all_names: BTreeSet<String> =
-            BTreeSet::from_iter(self.keys().chain(prev.keys()).cloned());
+        let all_names: BTreeSet<&String> = BTreeSet::from_iter(self.keys().chain(prev.keys()));
         for key in all_names {
-            let current = self.get(&key);
-            let previous = prev.get(&key);
+            let key = key.clone();
 
-            if current == previous {
-                continue;
+            match (self.get(&key), prev.get(&key)) {
+                (None, None) => continue,
+                (None, Some(feature)) => changes.push(FlagChange::Removed {
+                    key,
+                    feature: feature.clone(),
+                }),
+                (Some(feature), None) => changes.push(FlagChange::Added {
+                    key,
+                    feature: feature.clone(),
+                }),
+                (Some(current), Some(previous)) if current == previous => continue,
+                (Some(current), Some(previous)) => changes.push(FlagChange::Modified {
+                    key,
+                    previous: previous.clone(),
+                    current: current.clone(),
+                }),
             }
+        }
+
+        changes
+    }
+
+    fn changed_keys(&self, prev: &CoherentFeatureFlags) -> ChangedKeys {
+        let mut changes = ChangedKeys::default();
 
-            match (current, previous) {
+        let all_names: BTreeSet<&String> = BTreeSet::from_iter(self.keys().chain(prev.keys()));
+        for key in all_names {
+            match (self.get(key), prev.get(key)) {
                 (None, None) => continue,
-                (None, Some(feature)) => changes.push(format!("-feature:{key}:{feature:?}")),
-                (Some(feature), None) => changes.push(format!("+feature:{key}:{feature:?}")),
+                (None, Some(_)) => changes.removed.push(key.clone()),
+                (Some(_), None) => changes.added.push(key.clone()),
                 (Some(current), Some(previous)) if current == previous => continue,
-                (Some(current), Some(previous)) => {
-                    changes.push(format!("~feature:{key}:{}", current.diff(previous)))
-                }
+                (Some(_), Some(_)) => changes.modified.push(key.clone()),
             }
         }
 
@@ -52,7 +132,11 @@ mod tests {
         variant: serde_json::Value,
         payload: Option<serde_json::Value>,
     ) -> Arc<Feature<serde_json::Value>> {
-        Arc::new(Feature { variant, payload })
+        Arc::new(Feature {
+            variant,
+            payload,
+            experiment_id: None,
+        })
     }
 
     fn flags(pairs: &[(&str, Arc<Feature<serde_json::Value>>)]) -> CoherentFeatureFlags {
@@ -94,7 +178,7 @@ mod tests {
         assert_eq!(
             curr.diff(&prev),
             vec![String::from(
-                r#"+feature:flag_a:Feature { variant: String("on"), payload: None }"#
+                r#"+feature:flag_a:Feature { variant: String("on"), payload: None, experiment_id: None }"#
             )]
         );
     }
@@ -107,8 +191,12 @@ mod tests {
         let curr = flags(&[("alpha", fa.clone()), ("beta", fb.clone())]);
 
         let expected = vec![
-            String::from(r#"+feature:alpha:Feature { variant: String("on"), payload: None }"#),
-            String::from(r#"+feature:beta:Feature { variant: String("off"), payload: None }"#),
+            String::from(
+                r#"+feature:alpha:Feature { variant: String("on"), payload: None, experiment_id: None }"#,
+            ),
+            String::from(
+                r#"+feature:beta:Feature { variant: String("off"), payload: None, experiment_id: None }"#,
+            ),
         ];
         assert_eq!(curr.diff(&prev), expected);
     }
@@ -122,7 +210,7 @@ mod tests {
         assert_eq!(
             curr.diff(&prev),
             vec![String::from(
-                r#"-feature:flag_a:Feature { variant: String("on"), payload: None }"#
+                r#"-feature:flag_a:Feature { variant: String("on"), payload: None, experiment_id: None }"#
             ),]
         );
     }
@@ -162,13 +250,137 @@ mod tests {
         ]);
 
         let expected = vec![
-            String::from(r#"+feature:added:Feature { variant: String("new"), payload: None }"#),
+            String::from(
+                r#"+feature:added:Feature { variant: String("new"), payload: None, experiment_id: None }"#,
+            ),
             String::from(
                 r#"~feature:mutated:variant: String("v1") -> String("v2"), payload: Some(Number(1)) -> Some(Number(2))"#,
             ),
-            String::from(r#"-feature:removed:Feature { variant: String("old"), payload: None }"#),
+            String::from(
+                r#"-feature:removed:Feature { variant: String("old"), payload: None, experiment_id: None }"#,
+            ),
         ];
 
         assert_eq!(curr.diff(&prev), expected);
     }
+
+    #[test]
+    fn structured_diff_is_empty_without_changes() {
+        let f = flags(&[
+            ("a", feat(json!("on"), None)),
+            ("b", feat(json!("off"), Some(json!(42)))),
+        ]);
+
+        assert!(empty().structured_diff(&empty()).is_empty());
+        assert!(f.structured_diff(&f.clone()).is_empty());
+    }
+
+    #[test]
+    fn structured_additions() {
+        let fa = feat(json!("on"), None);
+        let fb = feat(json!("off"), None);
+        let curr = flags(&[("beta", fb.clone()), ("alpha", fa.clone())]);
+
+        assert_eq!(
+            curr.structured_diff(&empty()),
+            vec![
+                FlagChange::Added {
+                    key: "alpha".into(),
+                    feature: fa,
+                },
+                FlagChange::Added {
+                    key: "beta".into(),
+                    feature: fb,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn structured_removal() {
+        let feature = feat(json!("on"), None);
+        let prev = flags(&[("flag_a", feature.clone())]);
+
+        assert_eq!(
+            empty().structured_diff(&prev),
+            vec![FlagChange::Removed {
+                key: "flag_a".into(),
+                feature,
+            }]
+        );
+    }
+
+    #[test]
+    fn structured_add_remove_and_mutate() {
+        let stable_feat = feat(json!("stable"), None);
+        let removed_feat = feat(json!("old"), None);
+        let prev_mut = feat(json!("v1"), Some(json!(1)));
+        let curr_mut = feat(json!("v2"), Some(json!(2)));
+        let added_feat = feat(json!("new"), None);
+
+        let prev = flags(&[
+            ("stable", stable_feat.clone()),
+            ("removed", removed_feat.clone()),
+            ("mutated", prev_mut.clone()),
+        ]);
+        let curr = flags(&[
+            ("stable", stable_feat.clone()),
+            ("added", added_feat.clone()),
+            ("mutated", curr_mut.clone()),
+        ]);
+
+        let changes = curr.structured_diff(&prev);
+        assert_eq!(
+            changes,
+            vec![
+                FlagChange::Added {
+                    key: "added".into(),
+                    feature: added_feat,
+                },
+                FlagChange::Modified {
+                    key: "mutated".into(),
+                    previous: prev_mut,
+                    current: curr_mut,
+                },
+                FlagChange::Removed {
+                    key: "removed".into(),
+                    feature: removed_feat,
+                },
+            ]
+        );
+        assert_eq!(
+            changes.iter().map(FlagChange::key).collect::<Vec<_>>(),
+            vec!["added", "mutated", "removed"]
+        );
+        assert_eq!(
+            changes
+                .iter()
+                .map(FlagChange::to_string)
+                .collect::<Vec<_>>(),
+            curr.diff(&prev)
+        );
+    }
+
+    #[test]
+    fn changed_keys_are_sorted_by_kind() {
+        let prev = flags(&[
+            ("kept", feat(json!("on"), None)),
+            ("modified", feat(json!("a"), None)),
+            ("removed", feat(json!(true), None)),
+        ]);
+        let curr = flags(&[
+            ("kept", feat(json!("on"), None)),
+            ("modified", feat(json!("b"), None)),
+            ("added", feat(json!(true), None)),
+        ]);
+
+        assert_eq!(
+            curr.changed_keys(&prev),
+            ChangedKeys {
+                added: vec!["added".into()],
+                removed: vec!["removed".into()],
+                modified: vec!["modified".into()],
+            }
+        );
+    }
 }
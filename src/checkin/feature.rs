@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+/// A feature flag's served value. Build one with [`Feature::new`].
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Feature<T: serde::ser::Serialize + serde::de::DeserializeOwned> {
     pub variant: serde_json::Value,
     #[serde(
@@ -9,4 +11,23 @@ pub struct Feature<T: serde::ser::Serialize + serde::de::DeserializeOwned> {
         default
     )]
     pub payload: Option<T>,
+
+    /// The A/B test this feature is part of, recorded as `$experiment_id` on `$feature_flag_called`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub experiment_id: Option<String>,
+}
+
+impl<T: serde::ser::Serialize + serde::de::DeserializeOwned> Feature<T> {
+    pub fn new(variant: impl Into<serde_json::Value>, payload: Option<T>) -> Self {
+        Self {
+            variant: variant.into(),
+            payload,
+            experiment_id: None,
+        }
+    }
+
+    pub fn with_experiment_id(mut self, experiment_id: impl Into<String>) -> Self {
+        self.experiment_id = Some(experiment_id.into());
+        self
+    }
 }
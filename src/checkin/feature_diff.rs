@@ -23,6 +23,13 @@ impl<T: serde::ser::Serialize + serde::de::DeserializeOwned + std::cmp::PartialE
             ));
         }
 
+        if self.experiment_id != previous.experiment_id {
+            diff.push(format!(
+                "experiment: {:?} -> {:?}",
+                previous.experiment_id, self.experiment_id
+            ));
+        }
+
         diff.join(", ")
     }
 }
@@ -33,7 +40,11 @@ mod tests {
     use serde_json::json;
 
     fn make_feature(variant: serde_json::Value, payload: Option<i32>) -> Feature<i32> {
-        Feature { variant, payload }
+        Feature {
+            variant,
+            payload,
+            experiment_id: None,
+        }
     }
 
     #[test]
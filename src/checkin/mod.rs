@@ -5,11 +5,14 @@ mod data;
 mod feature;
 mod feature_diff;
 mod server_options;
-pub(crate) use checkin_diff::CheckinDiff;
+pub use checkin_diff::CheckinChangeset;
+pub use coherent_feature_flag_diff::FlagChange;
 pub(crate) use coherent_feature_flags::CoherentFeatureFlags;
 pub(crate) use data::Checkin;
-pub(crate) use feature::Feature;
-pub(crate) use server_options::ServerOptions;
+pub use feature::Feature;
+pub(crate) use server_options::{
+    DEFAULT_REFRESH_INTERVAL, MIN_FLUSH_INTERVAL, MIN_REFRESH_INTERVAL, ServerOptions,
+};
 
 #[cfg(test)]
 mod test {
@@ -35,4 +38,54 @@ mod test {
 
         let _: super::Checkin = serde_json::from_str(json).unwrap();
     }
+
+    #[test]
+    fn test_parse_payload_encodings() {
+        let json = r#"
+        {
+            "options": {
+                "double-encoded": {
+                    "variant": "a",
+                    "payload": "{\"greeting\": \"hello\"}"
+                },
+                "plain-object": {
+                    "variant": "a",
+                    "payload": {"greeting": "hello"}
+                },
+                "plain-number": {
+                    "variant": true,
+                    "payload": 42
+                },
+                "plain-string": {
+                    "variant": true,
+                    "payload": "hello"
+                },
+                "null-payload": {
+                    "variant": false,
+                    "payload": null
+                }
+            }
+        }"#;
+
+        let checkin: super::Checkin = serde_json::from_str(json).unwrap();
+        let payload = |name: &str| checkin.options[name].payload.clone();
+
+        assert_eq!(
+            payload("double-encoded"),
+            Some(serde_json::json!({ "greeting": "hello" }))
+        );
+        assert_eq!(payload("plain-object"), payload("double-encoded"));
+        assert_eq!(payload("plain-number"), Some(serde_json::json!(42)));
+        assert_eq!(payload("plain-string"), Some(serde_json::json!("hello")));
+        assert_eq!(payload("null-payload"), None);
+
+        // Payloads are written double-encoded, and read back the same
+        let reparsed: super::Checkin =
+            serde_json::from_str(&serde_json::to_string(&checkin).unwrap()).unwrap();
+        assert_eq!(reparsed, checkin);
+        assert_eq!(
+            serde_json::to_value(&checkin).unwrap()["options"]["plain-object"]["payload"],
+            r#"{"greeting":"hello"}"#
+        );
+    }
 }
@@ -1,20 +1,126 @@
+use std::time::Duration;
+
 use serde::Deserialize;
 
+/// How often to check in when neither the server nor the Builder says.
+pub(crate) const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60 * 2);
+pub(crate) const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+pub(crate) const MAX_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+pub(crate) const MIN_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Clone, Debug, Deserialize, Default, PartialEq, Eq)]
 pub(crate) struct ServerOptions {
     pub(crate) compression_algorithms: crate::compression_set::CompressionSet,
+
+    #[serde(default)]
+    pub(crate) refresh_interval_seconds: Option<u64>,
+
+    /// Set to false to stop sending events, until `pause_seconds` elapse or a later check-in lifts it.
+    #[serde(default)]
+    pub(crate) telemetry_enabled: Option<bool>,
+
+    /// Stop sending events for this long.
+    #[serde(default)]
+    pub(crate) pause_seconds: Option<u64>,
+
+    #[serde(default)]
+    pub(crate) flush_interval_seconds: Option<u64>,
+
+    #[serde(default)]
+    pub(crate) max_batch_size: Option<usize>,
+
+    #[serde(default)]
+    pub(crate) max_pending_events: Option<usize>,
+
+    /// When the server wants clients to check in again.
+    #[serde(default)]
+    pub(crate) valid_until: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl ServerOptions {
+    /// How often the server asked clients to check in, if it did, clamped to between a minute and a day.
+    pub(crate) fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh_interval_seconds.map(|seconds| {
+            Duration::from_secs(seconds).clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL)
+        })
+    }
+
+    /// How long until `valid_until`, if the server set it, clamped to between a minute and a day.
+    pub(crate) fn valid_for(&self) -> Option<Duration> {
+        self.valid_until.map(|valid_until| {
+            (valid_until - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default()
+                .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL)
+        })
+    }
+
+    /// Whether the server asked clients to stop sending events, and for how long.
+    pub(crate) fn pause(&self) -> Option<crate::submitter::Pause> {
+        if self.telemetry_enabled == Some(true) {
+            return None;
+        }
+
+        match (self.telemetry_enabled, self.pause_seconds) {
+            (_, Some(seconds)) if seconds > 0 => Some(crate::submitter::Pause::Until(
+                tokio::time::Instant::now() + Duration::from_secs(seconds),
+            )),
+            (Some(false), _) => Some(crate::submitter::Pause::Indefinitely),
+            _ => None,
+        }
+    }
+
+    /// How the server asked clients to batch events, with the flush interval clamped to between a second and an hour.
+    pub(crate) fn batching(&self) -> crate::submitter::BatchingRequest {
+        crate::submitter::BatchingRequest {
+            flush_interval: self.flush_interval_seconds.map(|seconds| {
+                Duration::from_secs(seconds).clamp(MIN_FLUSH_INTERVAL, MAX_FLUSH_INTERVAL)
+            }),
+            max_batch_size: self.max_batch_size.map(|size| size.max(1)),
+            max_pending_events: self.max_pending_events.map(|size| size.max(1)),
+        }
+    }
+
     pub(crate) fn diff(&self, prev: &Self) -> Vec<String> {
-        if self == prev {
-            return vec![];
+        let mut delta = vec![];
+
+        if self.compression_algorithms != prev.compression_algorithms {
+            delta.push(format!(
+                "Compression algorithms: {:?} -> {:?}",
+                prev.compression_algorithms, self.compression_algorithms
+            ));
+        }
+
+        if self.refresh_interval_seconds != prev.refresh_interval_seconds {
+            delta.push(format!(
+                "Refresh interval: {:?} -> {:?}",
+                prev.refresh_interval(),
+                self.refresh_interval()
+            ));
         }
 
-        vec![format!(
-            "Compression algorithms: {:?} -> {:?}",
-            prev.compression_algorithms, self.compression_algorithms
-        )]
+        if self.telemetry_enabled != prev.telemetry_enabled
+            || self.pause_seconds != prev.pause_seconds
+        {
+            delta.push(format!(
+                "Telemetry enabled: {:?} -> {:?}, paused for {:?} -> {:?} seconds",
+                prev.telemetry_enabled,
+                self.telemetry_enabled,
+                prev.pause_seconds,
+                self.pause_seconds
+            ));
+        }
+
+        if self.batching() != prev.batching() {
+            delta.push(format!(
+                "Batching: {:?} -> {:?}",
+                prev.batching(),
+                self.batching()
+            ));
+        }
+
+        delta
     }
 }
 
@@ -26,7 +132,14 @@ mod tests {
 
     fn server_options(zstd: bool) -> ServerOptions {
         ServerOptions {
-            compression_algorithms: CompressionSet { zstd },
+            compression_algorithms: CompressionSet { lz4: false, zstd },
+            refresh_interval_seconds: None,
+            telemetry_enabled: None,
+            pause_seconds: None,
+            flush_interval_seconds: None,
+            max_batch_size: None,
+            max_pending_events: None,
+            valid_until: None,
         }
     }
 
@@ -46,8 +159,97 @@ mod tests {
         assert_eq!(
             next.diff(&prev),
             vec![String::from(
-                "Compression algorithms: CompressionSet { zstd: false } -> CompressionSet { zstd: true }"
+                "Compression algorithms: CompressionSet { lz4: false, zstd: false } -> CompressionSet { lz4: false, zstd: true }"
             )]
         )
     }
+
+    #[test]
+    fn diff_mentions_refresh_interval() {
+        let prev = server_options(false);
+        let next = ServerOptions {
+            refresh_interval_seconds: Some(600),
+            ..server_options(false)
+        };
+
+        assert_eq!(
+            next.diff(&prev),
+            vec![String::from("Refresh interval: None -> Some(600s)")]
+        )
+    }
+
+    #[test]
+    fn refresh_interval_is_clamped() {
+        let interval = |seconds| {
+            ServerOptions {
+                refresh_interval_seconds: seconds,
+                ..server_options(false)
+            }
+            .refresh_interval()
+        };
+
+        assert_eq!(interval(None), None);
+        assert_eq!(interval(Some(600)), Some(Duration::from_secs(600)));
+        assert_eq!(interval(Some(0)), Some(MIN_REFRESH_INTERVAL));
+        assert_eq!(interval(Some(u64::MAX)), Some(MAX_REFRESH_INTERVAL));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pauses() {
+        let pause = |telemetry_enabled, pause_seconds| {
+            ServerOptions {
+                telemetry_enabled,
+                pause_seconds,
+                ..server_options(false)
+            }
+            .pause()
+        };
+        let in_an_hour = tokio::time::Instant::now() + Duration::from_secs(3600);
+
+        assert_eq!(pause(None, None), None);
+        assert_eq!(pause(Some(true), Some(3600)), None);
+        assert_eq!(pause(None, Some(0)), None);
+        assert_eq!(
+            pause(Some(false), None),
+            Some(crate::submitter::Pause::Indefinitely)
+        );
+        assert_eq!(
+            pause(None, Some(3600)),
+            Some(crate::submitter::Pause::Until(in_an_hour))
+        );
+        assert_eq!(
+            pause(Some(false), Some(3600)),
+            Some(crate::submitter::Pause::Until(in_an_hour))
+        );
+    }
+
+    #[test]
+    fn batching_is_bounded() {
+        let batching = |flush_interval_seconds, max_batch_size| {
+            ServerOptions {
+                flush_interval_seconds,
+                max_batch_size,
+                max_pending_events: Some(0),
+                ..server_options(false)
+            }
+            .batching()
+        };
+
+        assert_eq!(batching(None, None).flush_interval, None);
+        assert_eq!(
+            batching(Some(10), None).flush_interval,
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            batching(Some(0), None).flush_interval,
+            Some(MIN_FLUSH_INTERVAL)
+        );
+        assert_eq!(
+            batching(Some(u64::MAX), None).flush_interval,
+            Some(MAX_FLUSH_INTERVAL)
+        );
+        assert_eq!(batching(None, Some(0)).max_batch_size, Some(1));
+        assert_eq!(batching(None, Some(50)).max_batch_size, Some(50));
+        assert_eq!(batching(None, None).max_pending_events, Some(1));
+    }
 }
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::collator::Event;
+use crate::{Groups, Map};
+
+/// Events which are never coalesced, since each one changes identity state on the backend.
+const NEVER_COALESCE: &[&str] = &["$identify", "$create_alias"];
+
+/// Merge identical events (same name and properties) recorded within `window` of each other into a single event.
+///
+/// The merged event carries a `$count` property, and `$first_timestamp` / `$last_timestamp` properties.
+#[derive(Clone, Debug)]
+pub struct CoalesceOptions {
+    pub window: Duration,
+    pub event_names: EventNameFilter,
+}
+
+impl CoalesceOptions {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            event_names: EventNameFilter::All,
+        }
+    }
+
+    pub fn event_names(mut self, event_names: EventNameFilter) -> Self {
+        self.event_names = event_names;
+        self
+    }
+}
+
+/// Select which event names are eligible for coalescing.
+#[derive(Clone, Debug, Default)]
+pub enum EventNameFilter {
+    #[default]
+    All,
+    Allow(HashSet<String>),
+    Deny(HashSet<String>),
+}
+
+impl EventNameFilter {
+    fn matches(&self, event_name: &str) -> bool {
+        match self {
+            EventNameFilter::All => true,
+            EventNameFilter::Allow(names) => names.contains(event_name),
+            EventNameFilter::Deny(names) => !names.contains(event_name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CoalesceKey {
+    event_name: String,
+    properties_hash: u64,
+}
+
+struct Pending {
+    event: Event,
+    count: u64,
+    deadline: Instant,
+    last_timestamp: String,
+}
+
+pub(crate) struct Coalescer {
+    options: CoalesceOptions,
+    pending: HashMap<CoalesceKey, Pending>,
+}
+
+impl Coalescer {
+    pub(crate) fn new(options: CoalesceOptions) -> Self {
+        Self {
+            options,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn applies_to(&self, event_name: &str) -> bool {
+        !NEVER_COALESCE.contains(&event_name) && self.options.event_names.matches(event_name)
+    }
+
+    pub(crate) fn key(
+        event_name: &str,
+        properties: Option<&Map>,
+        groups: Option<&Groups>,
+    ) -> CoalesceKey {
+        let mut hasher = std::hash::DefaultHasher::new();
+
+        // serde_json's Map is ordered, so the serialization is stable for identical properties
+        serde_json::to_string(&properties)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        groups
+            .map(|groups| groups.iter().collect::<BTreeMap<_, _>>())
+            .hash(&mut hasher);
+
+        CoalesceKey {
+            event_name: event_name.to_string(),
+            properties_hash: hasher.finish(),
+        }
+    }
+
+    /// Merge an occurrence into a pending event, returning false if there is no pending event to merge into.
+    pub(crate) fn absorb(&mut self, key: &CoalesceKey) -> bool {
+        let Some(pending) = self.pending.get_mut(key) else {
+            return false;
+        };
+
+        pending.count += 1;
+        pending.last_timestamp = now_rfc3339();
+
+        true
+    }
+
+    pub(crate) fn hold(&mut self, key: CoalesceKey, event: Box<Event>) {
+        let last_timestamp = event.timestamp().to_string();
+
+        self.pending.insert(
+            key,
+            Pending {
+                event: *event,
+                count: 1,
+                deadline: Instant::now() + self.options.window,
+                last_timestamp,
+            },
+        );
+    }
+
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|pending| pending.deadline).min()
+    }
+
+    pub(crate) fn take_expired(&mut self) -> Vec<Event> {
+        let now = Instant::now();
+        self.take_where(|pending| pending.deadline <= now)
+    }
+
+    pub(crate) fn take_all(&mut self) -> Vec<Event> {
+        self.take_where(|_| true)
+    }
+
+    fn take_where(&mut self, predicate: impl Fn(&Pending) -> bool) -> Vec<Event> {
+        let keys: Vec<CoalesceKey> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| predicate(pending))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut taken: Vec<Pending> = keys
+            .iter()
+            .filter_map(|key| self.pending.remove(key))
+            .collect();
+        taken.sort_by_key(|pending| pending.deadline);
+
+        taken.into_iter().map(Pending::into_event).collect()
+    }
+}
+
+impl Pending {
+    fn into_event(mut self) -> Event {
+        if self.count > 1 {
+            let first_timestamp = self.event.timestamp().to_string();
+
+            self.event.insert_property("$count", self.count.into());
+            self.event
+                .insert_property("$first_timestamp", first_timestamp.into());
+            self.event
+                .insert_property("$last_timestamp", self.last_timestamp.into());
+        }
+
+        self.event
+    }
+}
+
+fn now_rfc3339() -> String {
+    let now: chrono::DateTime<chrono::Utc> = std::time::SystemTime::now().into();
+    now.to_rfc3339()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CoalesceOptions, Coalescer, EventNameFilter};
+
+    #[test]
+    fn never_coalesces_identity_events() {
+        let coalescer = Coalescer::new(CoalesceOptions::new(std::time::Duration::from_secs(1)));
+
+        assert!(coalescer.applies_to("fetch_failed"));
+        assert!(!coalescer.applies_to("$identify"));
+        assert!(!coalescer.applies_to("$create_alias"));
+    }
+
+    #[test]
+    fn allow_and_deny_lists() {
+        let allow = Coalescer::new(
+            CoalesceOptions::new(std::time::Duration::from_secs(1))
+                .event_names(EventNameFilter::Allow(["fetch_failed".to_string()].into())),
+        );
+        assert!(allow.applies_to("fetch_failed"));
+        assert!(!allow.applies_to("other"));
+
+        let deny = Coalescer::new(
+            CoalesceOptions::new(std::time::Duration::from_secs(1))
+                .event_names(EventNameFilter::Deny(["fetch_failed".to_string()].into())),
+        );
+        assert!(!deny.applies_to("fetch_failed"));
+        assert!(deny.applies_to("other"));
+    }
+
+    #[test]
+    fn key_depends_on_properties() {
+        let a = crate::Map::from_iter([("a".to_string(), 1.into())]);
+        let b = crate::Map::from_iter([("a".to_string(), 2.into())]);
+
+        assert_eq!(
+            Coalescer::key("ev", Some(&a), None),
+            Coalescer::key("ev", Some(&a.clone()), None)
+        );
+        assert_ne!(
+            Coalescer::key("ev", Some(&a), None),
+            Coalescer::key("ev", Some(&b), None)
+        );
+        assert_ne!(
+            Coalescer::key("ev", Some(&a), None),
+            Coalescer::key("ev2", Some(&a), None)
+        );
+        assert_ne!(
+            Coalescer::key("ev", Some(&a), None),
+            Coalescer::key("ev", None, None)
+        );
+
+        let groups = crate::Groups::from_iter([("org".to_string(), "acme".to_string())]);
+        assert_ne!(
+            Coalescer::key("ev", Some(&a), None),
+            Coalescer::key("ev", Some(&a), Some(&groups))
+        );
+    }
+}
@@ -1,20 +1,44 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
 use thiserror::Error;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::oneshot::Sender as OneshotSender;
 use tracing::Instrument;
 
 use crate::checkin::Checkin;
+use crate::coalesce::{CoalesceOptions, Coalescer};
 use crate::ds_correlation::Correlation;
-use crate::identity::{AnonymousDistinctId, DeviceId, DistinctId};
-use crate::recorder::{IdentifyProperties, RawSignal};
-use crate::{Groups, Map};
+use crate::dynamic_fact::DynamicFact;
+use crate::filter::EventFilter;
+use crate::identity::{AnonymousDistinctId, DeviceId, DistinctId, DistinctIdChange};
+use crate::process_stats::{ProcessStats, ProcessStatsSampler};
+use crate::recorder::{IdentifyProperties, RawSignal, ResetOptions};
+use crate::submitter::{SubmitterMetrics, SubmitterOverflowStrategy};
+use crate::system_snapshot::HostNamePolicy;
+use crate::transformer::EventTransformer;
+use crate::{Groups, Map, PersonProperties};
 
 #[derive(serde::Serialize, Debug)]
 pub(crate) enum CollatedSignal {
     Event(Box<Event>),
+    /// An event the Submitter flushes as soon as it arrives, without waiting for the flush interval.
+    PriorityEvent(Box<Event>),
     FlushNow,
 }
 
+/// Events which change who later events are attributed to, so they're never dropped to make room.
+const IDENTITY_EVENTS: &[&str] = &["$identify", "$set", "$create_alias", "$identity_imported"];
+
+/// How the Collator hands events to the Submitter when its queue is full.
+pub(crate) struct Overflow {
+    pub(crate) strategy: SubmitterOverflowStrategy,
+    pub(crate) metrics: SubmitterMetrics,
+}
+
 #[derive(serde::Serialize, Debug)]
 pub(crate) struct Event {
     name: String,
@@ -43,12 +67,30 @@ struct EventProperties {
     #[serde(rename = "$session_id")]
     session_id: String,
 
+    #[serde(rename = "$insert_id")]
+    insert_id: uuid::Uuid,
+
+    /// Counts up from zero in each process, so it orders events per `$seq_instance` rather than per session.
+    /// Processes sharing a `$session_id` through correlation each start their own count.
+    #[serde(rename = "$seq")]
+    seq: u64,
+
+    /// Identifies the process counting `$seq`.
+    #[serde(rename = "$seq_instance")]
+    seq_instance: uuid::Uuid,
+
+    #[serde(rename = "$idempotency_key", skip_serializing_if = "Option::is_none")]
+    idempotency_key: Option<String>,
+
     #[serde(rename = "$groups")]
     groups: Groups,
 
     #[serde(flatten)]
     snapshot: crate::system_snapshot::SystemSnapshot,
 
+    #[serde(flatten)]
+    process_stats: Option<ProcessStats>,
+
     #[serde(flatten)]
     facts: Map,
 
@@ -59,9 +101,89 @@ struct EventProperties {
     properties: Option<Map>,
 }
 
+impl Event {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn is_identity_change(&self) -> bool {
+        IDENTITY_EVENTS.contains(&self.name.as_str())
+    }
+
+    pub(crate) fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    pub(crate) fn insert_property(&mut self, key: impl Into<String>, value: serde_json::Value) {
+        self.properties
+            .properties
+            .get_or_insert_with(Map::new)
+            .insert(key.into(), value);
+    }
+}
+
+#[cfg(test)]
+impl Event {
+    pub(crate) fn for_test(name: impl Into<String>) -> Self {
+        let uuid = uuid::Uuid::now_v7();
+
+        Self {
+            name: name.into(),
+            distinct_id: "distinct".into(),
+            uuid,
+            timestamp: "2024-01-01T00:00:00+00:00".into(),
+            properties: EventProperties {
+                anon_distinct_id: "anon".into(),
+                device_id: "device".into(),
+                lib: env!("CARGO_PKG_NAME"),
+                lib_version: env!("CARGO_PKG_VERSION"),
+                session_id: "session".into(),
+                insert_id: uuid,
+                seq: 0,
+                seq_instance: uuid::Uuid::nil(),
+                idempotency_key: None,
+                groups: Groups::new(),
+                snapshot: crate::system_snapshot::SystemSnapshot::default(),
+                process_stats: None,
+                facts: Map::new(),
+                featurefacts: FeatureFacts::default(),
+                properties: None,
+            },
+        }
+    }
+}
+
 #[derive(serde::Serialize, Debug, Clone, Default)]
 pub(crate) struct FeatureFacts(pub(crate) Map);
 
+/// How long a SystemSnapshot is reused for subsequent events.
+pub(crate) const DEFAULT_SNAPSHOT_TTL: Duration = Duration::from_secs(5);
+
+/// How long a stored Checkin is used to bootstrap feature flags.
+pub(crate) const DEFAULT_CHECKIN_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The Builder's settings for the Collator: the identity it starts from, what it adds to each event, and how it hands events to the Submitter.
+#[derive(Default)]
+pub(crate) struct CollatorOptions {
+    pub(crate) anonymous_distinct_id: Option<AnonymousDistinctId>,
+    pub(crate) distinct_id: Option<DistinctId>,
+    pub(crate) device_id: Option<DeviceId>,
+    pub(crate) facts: Option<Map>,
+    pub(crate) dynamic_facts: Option<HashMap<String, DynamicFact>>,
+    pub(crate) groups: Option<Groups>,
+    /// Identity and properties inherited from a parent process, merged beneath the Builder's own.
+    pub(crate) correlation: Correlation,
+    pub(crate) coalesce: Option<CoalesceOptions>,
+    pub(crate) event_filter: Option<Arc<dyn EventFilter>>,
+    pub(crate) event_transformer: Option<Arc<dyn EventTransformer>>,
+    pub(crate) snapshot_ttl: Option<Duration>,
+    pub(crate) host_name_policy: HostNamePolicy,
+    pub(crate) capture_process_stats: bool,
+    pub(crate) checkin_ttl: Option<Duration>,
+    pub(crate) anonymous_id_max_age: Option<Duration>,
+    pub(crate) overflow_strategy: SubmitterOverflowStrategy,
+}
+
 #[derive(Error, Debug)]
 pub(crate) enum SnapshotError {
     #[error("Forwarding a collated message failed: {0}")]
@@ -74,57 +196,120 @@ pub(crate) enum SnapshotError {
 pub(crate) struct Collator<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage>
 {
     system_snapshotter: F,
+    snapshot_ttl: Duration,
+    host_name_policy: HostNamePolicy,
+    process_stats: Option<ProcessStatsSampler>,
+    cached_snapshot: Option<(tokio::time::Instant, crate::system_snapshot::SystemSnapshot)>,
     storage: P,
     incoming: Receiver<RawSignal>,
     outgoing: Sender<CollatedSignal>,
+    overflow: Overflow,
+    shutdown: tokio::sync::watch::Receiver<bool>,
     session_id: String,
+    next_seq: AtomicU64,
+    seq_instance: uuid::Uuid,
     anon_distinct_id: AnonymousDistinctId,
+    anon_distinct_id_created_at: chrono::DateTime<chrono::Utc>,
     distinct_id: Option<DistinctId>,
     device_id: DeviceId,
     facts: Map,
+    initial_facts: Map,
+    dynamic_facts: HashMap<String, DynamicFact>,
+    initial_dynamic_facts: HashMap<String, DynamicFact>,
     featurefacts: FeatureFacts,
     checkin: Option<Checkin>,
+    checkin_fetched_at: Option<chrono::DateTime<chrono::Utc>>,
     groups: Groups,
+    sent_set_once: Vec<String>,
+    identity_notifier: tokio::sync::broadcast::Sender<DistinctIdChange>,
+    coalescer: Option<Coalescer>,
+    event_filter: Option<Arc<dyn EventFilter>>,
+    event_transformer: Option<Arc<dyn EventTransformer>>,
 }
 impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> Collator<F, P> {
-    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new(
+        options: CollatorOptions,
         system_snapshotter: F,
         storage: P,
         incoming: Receiver<RawSignal>,
         outgoing: Sender<CollatedSignal>,
-        anonymous_distinct_id: Option<AnonymousDistinctId>,
-        distinct_id: Option<DistinctId>,
-        device_id: Option<DeviceId>,
-        mut facts: Map,
-        mut groups: Groups,
-        mut correlation_data: Correlation,
+        submitter_metrics: SubmitterMetrics,
+        shutdown: tokio::sync::watch::Receiver<bool>,
     ) -> Self {
+        let CollatorOptions {
+            anonymous_distinct_id,
+            distinct_id,
+            device_id,
+            facts,
+            dynamic_facts,
+            groups,
+            correlation: mut correlation_data,
+            coalesce,
+            event_filter,
+            event_transformer,
+            snapshot_ttl,
+            host_name_policy,
+            capture_process_stats,
+            checkin_ttl,
+            anonymous_id_max_age,
+            overflow_strategy,
+        } = options;
+
+        let mut facts = facts.unwrap_or_default();
+        let dynamic_facts = dynamic_facts.unwrap_or_default();
+        let mut groups = groups.unwrap_or_default();
         facts.append(&mut correlation_data.properties);
         groups.extend(correlation_data.groups_as_hashmap());
 
         let stored_ident = storage.load().await.ok().flatten();
+        let stored_checkin = stored_ident
+            .as_ref()
+            .and_then(|props| fresh_checkin(props, checkin_ttl.unwrap_or(DEFAULT_CHECKIN_TTL)));
+        let stored_anon = stored_ident
+            .as_ref()
+            .filter(|props| !anonymous_id_expired(props, anonymous_id_max_age))
+            .map(|props| {
+                (
+                    props.anonymous_distinct_id.clone(),
+                    props.anonymous_id_created_at,
+                )
+            });
+
+        let anon_distinct_id = anonymous_distinct_id
+            .or_else(|| stored_anon.as_ref().map(|(id, _)| id.clone()))
+            .or_else(|| {
+                correlation_data
+                    .anon_distinct_id
+                    .map(AnonymousDistinctId::from)
+            })
+            .unwrap_or_else(|| AnonymousDistinctId::from(uuid::Uuid::now_v7().to_string()));
+        let anon_distinct_id_created_at = stored_anon
+            .and_then(|(id, created_at)| created_at.filter(|_| id == anon_distinct_id))
+            .unwrap_or_else(chrono::Utc::now);
 
         let mut ret = Self {
             system_snapshotter,
+            snapshot_ttl: snapshot_ttl.unwrap_or(DEFAULT_SNAPSHOT_TTL),
+            host_name_policy,
+            process_stats: capture_process_stats
+                .then(ProcessStatsSampler::new)
+                .flatten(),
+            cached_snapshot: None,
             storage,
             incoming,
             outgoing,
+            overflow: Overflow {
+                strategy: overflow_strategy,
+                metrics: submitter_metrics,
+            },
+            shutdown,
             session_id: correlation_data
                 .session_id
                 .unwrap_or_else(|| uuid::Uuid::now_v7().to_string()),
-            anon_distinct_id: anonymous_distinct_id
-                .or_else(|| {
-                    stored_ident
-                        .as_ref()
-                        .map(|props| props.anonymous_distinct_id.clone())
-                })
-                .or_else(|| {
-                    correlation_data
-                        .anon_distinct_id
-                        .map(AnonymousDistinctId::from)
-                })
-                .unwrap_or_else(|| AnonymousDistinctId::from(uuid::Uuid::now_v7().to_string())),
+            next_seq: AtomicU64::new(0),
+            seq_instance: uuid::Uuid::now_v7(),
+            anon_distinct_id,
+            anon_distinct_id_created_at,
             distinct_id: distinct_id
                 .or_else(|| {
                     stored_ident
@@ -136,12 +321,30 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
                 .or_else(|| stored_ident.as_ref().map(|props| props.device_id.clone()))
                 .or(correlation_data.device_id)
                 .unwrap_or_default(),
+            initial_facts: facts.clone(),
             facts,
-            checkin: stored_ident.as_ref().map(|props| props.checkin.clone()),
-            featurefacts: FeatureFacts::default(),
+            initial_dynamic_facts: dynamic_facts.clone(),
+            dynamic_facts,
+            checkin_fetched_at: stored_checkin.as_ref().map(|(_, fetched_at)| *fetched_at),
+            featurefacts: stored_checkin
+                .as_ref()
+                .map(|(checkin, _)| checkin.as_feature_facts())
+                .unwrap_or_default(),
+            checkin: stored_checkin.map(|(checkin, _)| checkin),
             groups,
+            sent_set_once: Vec::new(),
+            identity_notifier: tokio::sync::broadcast::Sender::new(16),
+            coalescer: coalesce.map(Coalescer::new),
+            event_filter,
+            event_transformer,
         };
 
+        if let Some(stored) = &stored_ident
+            && stored.distinct_id == ret.distinct_id
+        {
+            ret.sent_set_once = stored.sent_set_once.clone();
+        }
+
         if stored_ident != Some(ret.properties_to_store()) {
             ret.persist_storage().await;
         }
@@ -150,7 +353,68 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
     }
 }
 
+/// Whether the stored anonymous distinct ID is older than `max_age`.
+/// IDs stored without a creation time start aging when they are next stored, rather than rotating immediately.
+fn anonymous_id_expired(
+    props: &crate::storage::StoredProperties,
+    max_age: Option<Duration>,
+) -> bool {
+    let (Some(max_age), Some(created_at)) = (max_age, props.anonymous_id_created_at) else {
+        return false;
+    };
+
+    let age = chrono::Utc::now()
+        .signed_duration_since(created_at)
+        .to_std()
+        .unwrap_or_default();
+
+    if age > max_age {
+        tracing::debug!(%created_at, "Rotating the anonymous distinct ID since it is too old");
+        return true;
+    }
+
+    false
+}
+
+/// The stored Checkin and when it was fetched, unless it is older than `ttl` or its age is unknown.
+fn fresh_checkin(
+    props: &crate::storage::StoredProperties,
+    ttl: Duration,
+) -> Option<(Checkin, chrono::DateTime<chrono::Utc>)> {
+    let fetched_at = props.checkin_fetched_at?;
+    let age = chrono::Utc::now()
+        .signed_duration_since(fetched_at)
+        .to_std()
+        .unwrap_or_default();
+
+    if age > ttl {
+        tracing::debug!(%fetched_at, "Ignoring the stored checkin since it is stale");
+        return None;
+    }
+
+    Some((props.checkin.clone(), fetched_at))
+}
+
 impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> Collator<F, P> {
+    /// A recent snapshot, reused for `snapshot_ttl` to avoid snapshotting the system for every event.
+    async fn snapshot(&mut self) -> crate::system_snapshot::SystemSnapshot {
+        if let Some((taken_at, ref snapshot)) = self.cached_snapshot
+            && taken_at.elapsed() < self.snapshot_ttl
+        {
+            return snapshot.clone();
+        }
+
+        self.fresh_snapshot().await
+    }
+
+    async fn fresh_snapshot(&mut self) -> crate::system_snapshot::SystemSnapshot {
+        let mut snapshot = self.system_snapshotter.snapshot().await;
+        self.host_name_policy
+            .apply(&mut snapshot, &self.device_id.to_string());
+        self.cached_snapshot = Some((tokio::time::Instant::now(), snapshot.clone()));
+        snapshot
+    }
+
     fn distinct_id(&self) -> String {
         if let Some(ref distinct_id) = self.distinct_id {
             distinct_id.to_string()
@@ -159,25 +423,80 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
         }
     }
 
+    /// Tell subscribers the distinct ID changed from `from` to the current one.
+    fn notify_identity_change(&self, from: Option<DistinctId>) {
+        let change = DistinctIdChange {
+            from: from.map(|id| id.to_string()),
+            to: self.distinct_id(),
+        };
+
+        if let Err(e) = self.identity_notifier.send(change) {
+            tracing::trace!(%e, "No subscribers to identity changes");
+        }
+    }
+
+    fn rotate_anon_distinct_id(&mut self) {
+        self.anon_distinct_id = AnonymousDistinctId::new();
+        self.anon_distinct_id_created_at = chrono::Utc::now();
+    }
+
     pub(crate) fn get_checkin(&self) -> Option<&Checkin> {
         self.checkin.as_ref()
     }
 
+    pub(crate) fn get_checkin_fetched_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.checkin_fetched_at
+    }
+
     #[tracing::instrument(skip(self))]
     pub(crate) async fn execute(mut self) -> Result<(), SnapshotError> {
-        while let Some(signal) = self
-            .incoming
-            .recv()
-            .instrument(tracing::trace_span!("waiting for RawSignal messages"))
-            .await
-        {
+        loop {
+            let coalesce_deadline = self.coalescer.as_ref().and_then(Coalescer::next_deadline);
+
+            let draining = self.incoming.is_closed();
+
+            let signal = tokio::select! {
+                signal = self
+                    .incoming
+                    .recv()
+                    .instrument(tracing::trace_span!("waiting for RawSignal messages")) => signal,
+                _ = sleep_until(coalesce_deadline) => {
+                    self.forward_coalesced(false).await?;
+                    continue;
+                }
+                Ok(()) = shutdown_requested(&mut self.shutdown), if !draining => {
+                    tracing::debug!("Shutting down, handling the remaining messages");
+                    self.incoming.close();
+                    continue;
+                }
+            };
+
+            let Some(signal) = signal else {
+                break;
+            };
+
             match signal {
                 RawSignal::GetSessionProperties { tx } => {
                     self.handle_message_get_session_properties(tx).await?;
                 }
+                RawSignal::GetSessionIds { tx } => {
+                    self.handle_message_get_session_ids(tx)?;
+                }
+                RawSignal::ExportIdentity { tx } => {
+                    self.handle_message_export_identity(tx)?;
+                }
+                RawSignal::ImportIdentity(properties) => {
+                    self.handle_message_import_identity(*properties).await?;
+                }
+                RawSignal::SubscribeToIdentityChanges(tx) => {
+                    self.handle_message_subscribe_to_identity_changes(tx)?;
+                }
                 RawSignal::Fact { key, value } => {
                     self.handle_message_fact(key, value);
                 }
+                RawSignal::DynamicFact { key, fact } => {
+                    self.handle_message_dynamic_fact(key, fact);
+                }
                 RawSignal::UpdateFeatureConfiguration(checkin, featurefacts) => {
                     self.handle_message_update_feature_configuration(checkin, featurefacts)
                         .await;
@@ -185,8 +504,28 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
                 RawSignal::Event {
                     event_name,
                     properties,
+                    groups,
                 } => {
-                    self.handle_message_event(event_name, properties).await?;
+                    self.handle_message_event(event_name, properties, groups, None)
+                        .await?;
+                }
+                RawSignal::PriorityEvent {
+                    event_name,
+                    properties,
+                } => {
+                    self.handle_message_priority_event(event_name, properties)
+                        .await?;
+                }
+                RawSignal::EventWithKey {
+                    event_name,
+                    properties,
+                    key,
+                } => {
+                    self.handle_message_event(event_name, properties, None, Some(key))
+                        .await?;
+                }
+                RawSignal::EventBatch(events) => {
+                    self.handle_message_event_batch(events).await?;
                 }
                 RawSignal::Identify(new, properties) => {
                     self.handle_message_identify(new, properties).await?;
@@ -195,6 +534,9 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
                     self.handle_message_set_person_properties(properties)
                         .await?;
                 }
+                RawSignal::UnsetPersonProperty(key) => {
+                    self.handle_message_unset_person_property(key).await?;
+                }
                 RawSignal::AddGroup {
                     group_name,
                     group_member_id,
@@ -205,8 +547,8 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
                 RawSignal::Alias(alias) => {
                     self.handle_message_alias(alias).await?;
                 }
-                RawSignal::Reset => {
-                    self.handle_message_reset().await?;
+                RawSignal::Reset(options) => {
+                    self.handle_message_reset(options).await?;
                 }
                 RawSignal::FlushNow => {
                     self.handle_message_flush_now().await?;
@@ -214,6 +556,8 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
             }
         }
 
+        self.forward_coalesced(true).await?;
+
         self.outgoing
             .send(CollatedSignal::FlushNow)
             .instrument(tracing::trace_span!("final FlushNow"))
@@ -232,7 +576,21 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
         snapshot: crate::system_snapshot::SystemSnapshot,
         event: String,
         properties: Option<Map>,
+        group_overrides: Option<Groups>,
     ) -> Box<Event> {
+        let mut groups = self.groups.clone();
+        groups.extend(group_overrides.unwrap_or_default());
+
+        // The uuid doubles as $insert_id, so retrying a batch resubmits the same identifiers
+        let uuid = uuid::Uuid::now_v7();
+
+        let mut facts = self.facts.clone();
+        facts.extend(
+            self.dynamic_facts
+                .iter()
+                .map(|(key, fact)| (key.clone(), fact.evaluate(key))),
+        );
+
         Box::new(Event {
             distinct_id: self.distinct_id(),
             name: event,
@@ -240,21 +598,29 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
             properties: EventProperties {
                 anon_distinct_id: self.anon_distinct_id.to_string(),
                 session_id: self.session_id.to_string(),
+                insert_id: uuid,
+                seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+                seq_instance: self.seq_instance,
+                idempotency_key: None,
                 device_id: self.device_id.to_string(),
                 snapshot,
-                facts: self.facts.clone(),
+                process_stats: self
+                    .process_stats
+                    .as_ref()
+                    .and_then(ProcessStatsSampler::current),
+                facts,
                 featurefacts: self.featurefacts.clone(),
                 lib: env!("CARGO_PKG_NAME"),
                 lib_version: env!("CARGO_PKG_VERSION"),
                 properties,
-                groups: self.groups.clone(),
+                groups,
             },
 
             timestamp: {
                 let now: chrono::DateTime<chrono::Utc> = std::time::SystemTime::now().into();
                 now.to_rfc3339()
             },
-            uuid: uuid::Uuid::new_v4(),
+            uuid,
         })
     }
 
@@ -262,29 +628,73 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
         crate::storage::StoredProperties {
             distinct_id: self.distinct_id.clone(),
             anonymous_distinct_id: self.anon_distinct_id.clone(),
+            anonymous_id_created_at: Some(self.anon_distinct_id_created_at),
             device_id: self.device_id.clone(),
             groups: self.groups.clone(),
             checkin: self.checkin.as_ref().cloned().unwrap_or_default(),
+            checkin_fetched_at: self.checkin_fetched_at,
+            sent_set_once: self.sent_set_once.clone(),
         }
     }
 
+    /// Drop `$set_once` properties which were already sent, returning the keys left to send.
+    fn filter_set_once(&self, properties: &mut IdentifyProperties) -> Vec<String> {
+        let set_once = std::mem::take(&mut properties.set_once)
+            .into_iter()
+            .filter(|(key, _)| !self.sent_set_once.contains(key))
+            .collect::<Map>();
+        let keys = set_once.keys().cloned().collect();
+
+        properties.set_once = PersonProperties::from_map(set_once);
+        keys
+    }
+
+    async fn remember_set_once(&mut self, keys: Vec<String>) {
+        if keys.is_empty() {
+            return;
+        }
+
+        self.sent_set_once.extend(keys);
+        self.persist_storage().await;
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
     async fn persist_storage(&mut self) {
-        if let Err(e) = self.storage.store(self.properties_to_store()).await {
-            tracing::debug!(%e, "Storage error");
+        match self.storage.store_merged(self.properties_to_store()).await {
+            Ok(stored) => self.adopt_stored_identity(stored),
+            Err(e) => tracing::debug!(%e, "Storage error"),
+        }
+    }
+
+    /// Use the IDs storage kept, which are another process's if it stored them first.
+    fn adopt_stored_identity(&mut self, stored: crate::storage::StoredProperties) {
+        if stored.anonymous_distinct_id != self.anon_distinct_id
+            || stored.device_id != self.device_id
+        {
+            tracing::debug!("Adopting the identity another process stored first");
+        }
+
+        self.anon_distinct_id = stored.anonymous_distinct_id;
+        if let Some(created_at) = stored.anonymous_id_created_at {
+            self.anon_distinct_id_created_at = created_at;
+        }
+        self.device_id = stored.device_id;
+        self.sent_set_once = stored.sent_set_once;
+
+        if stored.distinct_id != self.distinct_id {
+            let from = std::mem::replace(&mut self.distinct_id, stored.distinct_id);
+            self.notify_identity_change(from);
         }
     }
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
     async fn handle_message_get_session_properties(
-        &self,
+        &mut self,
         tx: OneshotSender<Map>,
     ) -> Result<(), SnapshotError> {
         let mut props = Map::new();
 
-        if let Ok(person_properties) =
-            serde_json::to_value(self.system_snapshotter.snapshot().await)
-        {
+        if let Ok(person_properties) = serde_json::to_value(self.fresh_snapshot().await) {
             props.insert("person_properties".into(), person_properties);
         }
 
@@ -293,6 +703,8 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
             "$anon_distinct_id".into(),
             self.anon_distinct_id.to_string().into(),
         );
+        props.insert("$session_id".into(), self.session_id.to_string().into());
+        props.insert("$device_id".into(), self.device_id.to_string().into());
         props.insert(
             "groups".into(),
             serde_json::Value::from_iter(self.groups.clone()),
@@ -304,11 +716,51 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
+    fn handle_message_get_session_ids(
+        &self,
+        tx: OneshotSender<crate::recorder::SessionIds>,
+    ) -> Result<(), SnapshotError> {
+        let ids = crate::recorder::SessionIds {
+            session_id: self.session_id.to_string(),
+            distinct_id: self.distinct_id(),
+        };
+
+        tx.send(ids)
+            .map_err(|e| SnapshotError::Reply(format!("{e:?}")))?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
+    fn handle_message_export_identity(
+        &mut self,
+        tx: OneshotSender<crate::storage::StoredProperties>,
+    ) -> Result<(), SnapshotError> {
+        let properties = crate::storage::StoredProperties {
+            checkin: Checkin::default(),
+            checkin_fetched_at: None,
+            ..self.properties_to_store()
+        };
+
+        tx.send(properties)
+            .map_err(|e| SnapshotError::Reply(format!("{e:?}")))?;
+
+        Ok(())
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     fn handle_message_fact(&mut self, key: String, value: serde_json::Value) {
+        self.dynamic_facts.remove(&key);
         self.facts.insert(key, value);
     }
 
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    fn handle_message_dynamic_fact(&mut self, key: String, fact: DynamicFact) {
+        self.facts.remove(&key);
+        self.dynamic_facts.insert(key, fact);
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     async fn handle_message_update_feature_configuration(
         &mut self,
@@ -317,24 +769,140 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
     ) {
         if let Some(checkin) = checkin {
             self.checkin = Some(checkin);
+            self.checkin_fetched_at = Some(chrono::Utc::now());
         }
         self.featurefacts = facts;
         self.persist_storage().await;
     }
 
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    async fn handle_message_event_batch(
+        &mut self,
+        events: Vec<(String, Option<Map>)>,
+    ) -> Result<(), SnapshotError> {
+        for (event_name, properties) in events {
+            self.handle_message_event(event_name, properties, None, None)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     async fn handle_message_event(
-        &self,
+        &mut self,
         event_name: String,
         properties: Option<Map>,
+        groups: Option<Groups>,
+        idempotency_key: Option<String>,
     ) -> Result<(), SnapshotError> {
-        let snapshot = self.system_snapshotter.snapshot().await;
+        let Some((event_name, properties)) = self.filter_and_transform(event_name, properties)
+        else {
+            return Ok(());
+        };
+
+        // Merging would drop all but the first idempotency key, so keyed events are never coalesced
+        let coalesce_key = self
+            .coalescer
+            .as_ref()
+            .filter(|coalescer| idempotency_key.is_none() && coalescer.applies_to(&event_name))
+            .map(|_| Coalescer::key(&event_name, properties.as_ref(), groups.as_ref()));
+
+        if let Some(ref key) = coalesce_key
+            && let Some(ref mut coalescer) = self.coalescer
+            && coalescer.absorb(key)
+        {
+            return Ok(());
+        }
+
+        let snapshot = self.snapshot().await;
+        let mut event = self.msg_to_event(snapshot, event_name, properties, groups);
+        event.properties.idempotency_key = idempotency_key;
+
+        if let Some(key) = coalesce_key
+            && let Some(ref mut coalescer) = self.coalescer
+        {
+            coalescer.hold(key, event);
+            return Ok(());
+        }
+
+        self.forward(CollatedSignal::Event(event)).await
+    }
+
+    /// Priority events are never coalesced, and are submitted as soon as the Submitter receives them.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    async fn handle_message_priority_event(
+        &mut self,
+        event_name: String,
+        properties: Option<Map>,
+    ) -> Result<(), SnapshotError> {
+        let Some((event_name, properties)) = self.filter_and_transform(event_name, properties)
+        else {
+            return Ok(());
+        };
+
+        let snapshot = self.snapshot().await;
+        let event = self.msg_to_event(snapshot, event_name, properties, None);
+
+        self.forward(CollatedSignal::PriorityEvent(event)).await
+    }
+
+    /// Apply the event filter and transformer, returning None if the event is excluded.
+    fn filter_and_transform(
+        &self,
+        event_name: String,
+        properties: Option<Map>,
+    ) -> Option<(String, Option<Map>)> {
+        if let Some(ref filter) = self.event_filter
+            && !filter.should_include(&event_name, properties.as_ref())
+        {
+            tracing::trace!(event_name, "Dropping an event excluded by the event filter");
+            return None;
+        }
+
+        Some(match self.event_transformer {
+            Some(ref transformer) => transformer.transform(event_name, properties),
+            None => (event_name, properties),
+        })
+    }
+
+    /// Hand an event to the Submitter, dropping it if its queue is full and the overflow strategy says to.
+    ///
+    /// `DropOldest` waits like `Block`, since the Submitter makes room by dropping from its pending events.
+    async fn forward(&self, signal: CollatedSignal) -> Result<(), SnapshotError> {
+        if self.overflow.strategy == SubmitterOverflowStrategy::DropNewest {
+            return match self.outgoing.try_send(signal) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    tracing::debug!("Dropping an event since the Submitter's queue is full");
+                    self.overflow.metrics.record_dropped_events(1);
+                    Ok(())
+                }
+                Err(e @ TrySendError::Closed(_)) => Err(SnapshotError::Forward(format!("{e:?}"))),
+            };
+        }
+
         self.outgoing
-            .send(CollatedSignal::Event(
-                self.msg_to_event(snapshot, event_name, properties),
-            ))
+            .send(signal)
             .await
-            .map_err(|e| SnapshotError::Forward(format!("{e:?}")))?;
+            .map_err(|e| SnapshotError::Forward(format!("{e:?}")))
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    async fn forward_coalesced(&mut self, all: bool) -> Result<(), SnapshotError> {
+        let Some(ref mut coalescer) = self.coalescer else {
+            return Ok(());
+        };
+
+        let events = if all {
+            coalescer.take_all()
+        } else {
+            coalescer.take_expired()
+        };
+
+        for event in events {
+            self.forward(CollatedSignal::Event(Box::new(event))).await?;
+        }
 
         Ok(())
     }
@@ -343,45 +911,87 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
     async fn handle_message_identify(
         &mut self,
         new: DistinctId,
-        properties: IdentifyProperties,
+        mut properties: IdentifyProperties,
     ) -> Result<(), SnapshotError> {
         let old = self.distinct_id.replace(new);
+        let changed = old != self.distinct_id;
+
+        if changed {
+            // $set_once properties were sent for someone else
+            self.sent_set_once.clear();
+        }
 
         if old.is_some() {
             // Reset our anon distinct ID so we don't link the old id to the new id
-            self.anon_distinct_id = AnonymousDistinctId::from(uuid::Uuid::now_v7().to_string());
+            self.rotate_anon_distinct_id();
             // Reset our groups since they probably don't carry over
             self.groups = Groups::new();
         }
 
         self.persist_storage().await;
 
-        let snapshot = self.system_snapshotter.snapshot().await;
+        if changed {
+            self.notify_identity_change(old);
+        }
+
+        let set_once_keys = self.filter_set_once(&mut properties);
+        let snapshot = self.fresh_snapshot().await;
 
         self.outgoing
             .send(CollatedSignal::Event(self.msg_to_event(
                 snapshot,
                 "$identify".to_string(),
                 Some(properties.as_map()),
+                None,
             )))
             .await
             .map_err(|e| SnapshotError::Forward(format!("{e:?}")))?;
 
+        self.remember_set_once(set_once_keys).await;
+
         Ok(())
     }
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     async fn handle_message_set_person_properties(
         &mut self,
-        properties: IdentifyProperties,
+        mut properties: IdentifyProperties,
     ) -> Result<(), SnapshotError> {
-        let snapshot = self.system_snapshotter.snapshot().await;
+        let set_once_keys = self.filter_set_once(&mut properties);
+        let snapshot = self.snapshot().await;
 
         self.outgoing
             .send(CollatedSignal::Event(self.msg_to_event(
                 snapshot,
                 "$set".to_string(),
                 Some(properties.as_map()),
+                None,
+            )))
+            .await
+            .map_err(|e| SnapshotError::Forward(format!("{e:?}")))?;
+
+        self.remember_set_once(set_once_keys).await;
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    async fn handle_message_unset_person_property(
+        &mut self,
+        key: String,
+    ) -> Result<(), SnapshotError> {
+        let properties = IdentifyProperties {
+            unset: vec![key],
+            ..Default::default()
+        };
+        let snapshot = self.snapshot().await;
+
+        self.outgoing
+            .send(CollatedSignal::Event(self.msg_to_event(
+                snapshot,
+                "$identify".to_string(),
+                Some(properties.as_map()),
+                None,
             )))
             .await
             .map_err(|e| SnapshotError::Forward(format!("{e:?}")))?;
@@ -403,18 +1013,19 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
     }
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
-    async fn handle_message_alias(&self, alias: String) -> Result<(), SnapshotError> {
+    async fn handle_message_alias(&mut self, alias: String) -> Result<(), SnapshotError> {
         let mut properties = Map::new();
 
         properties.insert("alias".to_string(), alias.into());
 
-        let snapshot = self.system_snapshotter.snapshot().await;
+        let snapshot = self.snapshot().await;
 
         self.outgoing
             .send(CollatedSignal::Event(self.msg_to_event(
                 snapshot,
                 "$create_alias".to_string(),
                 Some(properties),
+                None,
             )))
             .await
             .map_err(|e| SnapshotError::Forward(format!("{e:?}")))?;
@@ -422,19 +1033,86 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
         Ok(())
     }
 
+    fn handle_message_subscribe_to_identity_changes(
+        &mut self,
+        tx: OneshotSender<tokio::sync::broadcast::Receiver<DistinctIdChange>>,
+    ) -> Result<(), SnapshotError> {
+        tx.send(self.identity_notifier.subscribe())
+            .map_err(|e| SnapshotError::Reply(format!("{e:?}")))?;
+
+        Ok(())
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
-    async fn handle_message_reset(&mut self) -> Result<(), SnapshotError> {
-        self.distinct_id = None;
-        self.anon_distinct_id = AnonymousDistinctId::new();
-        self.groups = Groups::new();
+    async fn handle_message_import_identity(
+        &mut self,
+        properties: crate::storage::StoredProperties,
+    ) -> Result<(), SnapshotError> {
+        let old = self.distinct_id.clone();
+        let previous_distinct_id = self.distinct_id();
+
+        self.anon_distinct_id = properties.anonymous_distinct_id;
+        self.anon_distinct_id_created_at = properties
+            .anonymous_id_created_at
+            .unwrap_or_else(chrono::Utc::now);
+        self.distinct_id = properties.distinct_id;
+        self.device_id = properties.device_id;
+        self.groups = properties.groups;
+        self.sent_set_once = properties.sent_set_once;
 
         self.persist_storage().await;
 
+        if self.distinct_id() != previous_distinct_id {
+            self.notify_identity_change(old);
+        }
+
+        let snapshot = self.fresh_snapshot().await;
+
+        self.outgoing
+            .send(CollatedSignal::Event(self.msg_to_event(
+                snapshot,
+                "$identity_imported".to_string(),
+                None,
+                None,
+            )))
+            .await
+            .map_err(|e| SnapshotError::Forward(format!("{e:?}")))?;
+
         Ok(())
     }
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
-    async fn handle_message_flush_now(&self) -> Result<(), SnapshotError> {
+    async fn handle_message_reset(&mut self, options: ResetOptions) -> Result<(), SnapshotError> {
+        let old = self.distinct_id.take();
+        self.rotate_anon_distinct_id();
+        self.sent_set_once.clear();
+
+        if options.groups {
+            self.groups = Groups::new();
+        }
+
+        if options.facts {
+            self.facts = self.initial_facts.clone();
+            self.dynamic_facts = self.initial_dynamic_facts.clone();
+        }
+
+        if options.device_id {
+            self.device_id = DeviceId::new();
+
+            // Hashed host names are salted with the device ID
+            self.cached_snapshot = None;
+        }
+
+        self.persist_storage().await;
+        self.notify_identity_change(old);
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    async fn handle_message_flush_now(&mut self) -> Result<(), SnapshotError> {
+        self.forward_coalesced(true).await?;
+
         self.outgoing
             .send(CollatedSignal::FlushNow)
             .await
@@ -442,3 +1120,554 @@ impl<F: crate::system_snapshot::SystemSnapshotter, P: crate::storage::Storage> C
         Ok(())
     }
 }
+
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves once shutdown is requested, or fails if the Worker is gone without requesting it.
+async fn shutdown_requested(
+    shutdown: &mut tokio::sync::watch::Receiver<bool>,
+) -> Result<(), tokio::sync::watch::error::RecvError> {
+    shutdown.wait_for(|stop| *stop).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use tokio::sync::mpsc::{Receiver, channel};
+
+    use super::{CollatedSignal, Collator, CollatorOptions, Overflow};
+    use crate::recorder::ResetOptions;
+    use crate::storage::Storage;
+    use crate::submitter::{SubmitterMetrics, SubmitterOverflowStrategy};
+    use crate::system_snapshot::{SystemSnapshot, SystemSnapshotter};
+    use crate::{DistinctId, Groups, Map};
+
+    #[derive(Clone, Default)]
+    struct CountingSnapshotter {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SystemSnapshotter for CountingSnapshotter {
+        async fn snapshot(&self) -> SystemSnapshot {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            SystemSnapshot {
+                host_name: None,
+                operating_system: None,
+                operating_system_version: None,
+                locale: None,
+                timezone: None,
+                target_triple: String::new(),
+                stdin_is_terminal: false,
+                is_ci: false,
+                ci_provider: None,
+                ci_runner_os_image: None,
+                processor_count: None,
+                cpu_brand: None,
+                cpu_arch: String::new(),
+                rosetta_translated: None,
+                native_arch: None,
+                logical_core_count: None,
+                cpu_features: None,
+                physical_memory_bytes: 0,
+                boot_time: 0,
+                process_name: None,
+                container_runtime: None,
+                in_container: false,
+                wsl_version: None,
+                virtualization: None,
+                libc: None,
+                init_system: None,
+                disks: Vec::new(),
+                extra_fields: None,
+            }
+        }
+    }
+
+    async fn collator_with<F: SystemSnapshotter>(
+        snapshotter: F,
+        snapshot_ttl: Duration,
+    ) -> Collator<F, crate::storage::Generic> {
+        collator_with_storage(
+            snapshotter,
+            snapshot_ttl,
+            crate::storage::Generic::default(),
+            None,
+        )
+        .await
+    }
+
+    async fn collator_with_storage<F: SystemSnapshotter, P: Storage>(
+        snapshotter: F,
+        snapshot_ttl: Duration,
+        storage: P,
+        anonymous_id_max_age: Option<Duration>,
+    ) -> Collator<F, P> {
+        let (_, incoming) = channel(1);
+        let (outgoing, _) = channel(1);
+
+        Collator::new(
+            CollatorOptions {
+                distinct_id: Some(DistinctId::from("alice".to_string())),
+                facts: Some(Map::from_iter([("built".to_string(), true.into())])),
+                groups: Some(Groups::from_iter([("org".to_string(), "acme".to_string())])),
+                snapshot_ttl: Some(snapshot_ttl),
+                host_name_policy: crate::HostNamePolicy::Raw,
+                anonymous_id_max_age,
+                ..Default::default()
+            },
+            snapshotter,
+            storage,
+            incoming,
+            outgoing,
+            SubmitterMetrics::default(),
+            tokio::sync::watch::channel(false).1,
+        )
+        .await
+    }
+
+    fn overflow(strategy: SubmitterOverflowStrategy) -> Overflow {
+        Overflow {
+            strategy,
+            metrics: SubmitterMetrics::default(),
+        }
+    }
+
+    /// A collator whose Submitter queue holds two signals, with its receiving end.
+    async fn collator_with_full_queue(
+        strategy: SubmitterOverflowStrategy,
+    ) -> (
+        Collator<crate::system_snapshot::Generic, crate::storage::Generic>,
+        Receiver<CollatedSignal>,
+    ) {
+        let mut collator = collator().await;
+
+        let (outgoing, queue) = channel(2);
+        collator.outgoing = outgoing;
+        collator.overflow = overflow(strategy);
+
+        for name in ["first", "second"] {
+            collator
+                .handle_message_event(name.to_string(), None, None, None)
+                .await
+                .unwrap();
+        }
+
+        (collator, queue)
+    }
+
+    fn queued_event_names(queue: &mut Receiver<CollatedSignal>) -> Vec<String> {
+        let mut names = Vec::new();
+        while let Ok(signal) = queue.try_recv() {
+            if let CollatedSignal::Event(event) = signal {
+                names.push(event.name);
+            }
+        }
+        names
+    }
+
+    async fn collator() -> Collator<crate::system_snapshot::Generic, crate::storage::Generic> {
+        collator_with(
+            crate::system_snapshot::Generic::default(),
+            super::DEFAULT_SNAPSHOT_TTL,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn overflow_drop_newest_keeps_queued_events() {
+        let (mut collator, mut queue) =
+            collator_with_full_queue(SubmitterOverflowStrategy::DropNewest).await;
+
+        collator
+            .handle_message_event("third".to_string(), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(collator.overflow.metrics.dropped_event_count(), 1);
+        assert_eq!(queued_event_names(&mut queue), ["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn overflow_block_and_drop_oldest_wait_for_room() {
+        // DropOldest makes room in the Submitter's pending events, so the Collator waits for it to receive
+        for strategy in [
+            SubmitterOverflowStrategy::Block,
+            SubmitterOverflowStrategy::DropOldest,
+        ] {
+            let (mut collator, mut queue) = collator_with_full_queue(strategy).await;
+            let metrics = collator.overflow.metrics.clone();
+
+            let send = collator.handle_message_event("third".to_string(), None, None, None);
+            tokio::pin!(send);
+
+            assert!(
+                tokio::time::timeout(Duration::from_millis(50), &mut send)
+                    .await
+                    .is_err(),
+                "{strategy:?}"
+            );
+
+            let Ok(CollatedSignal::Event(first)) = queue.try_recv() else {
+                panic!("Expected the first event");
+            };
+            assert_eq!(first.name, "first");
+
+            send.await.unwrap();
+
+            assert_eq!(metrics.dropped_event_count(), 0);
+            assert_eq!(queued_event_names(&mut queue), ["second", "third"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshot_is_reused_within_ttl() {
+        let snapshotter = CountingSnapshotter::default();
+        let mut collator = collator_with(snapshotter.clone(), Duration::from_secs(60)).await;
+
+        let (outgoing, mut rx) = channel(1000);
+        collator.outgoing = outgoing;
+
+        for _ in 0..100 {
+            collator
+                .handle_message_event("burst".to_string(), None, None, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(snapshotter.calls.load(Ordering::SeqCst), 1);
+
+        collator
+            .handle_message_identify(DistinctId::from("bob".to_string()), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(snapshotter.calls.load(Ordering::SeqCst), 2);
+
+        let (tx, session_rx) = tokio::sync::oneshot::channel();
+        collator
+            .handle_message_get_session_properties(tx)
+            .await
+            .unwrap();
+        session_rx.await.unwrap();
+        assert_eq!(snapshotter.calls.load(Ordering::SeqCst), 3);
+
+        collator
+            .handle_message_event("after".to_string(), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(snapshotter.calls.load(Ordering::SeqCst), 3);
+
+        rx.close();
+        let mut received = 0;
+        while rx.recv().await.is_some() {
+            received += 1;
+        }
+        assert_eq!(received, 102);
+    }
+
+    #[tokio::test]
+    async fn session_ids_are_read_without_a_snapshot() {
+        let snapshotter = CountingSnapshotter::default();
+        let collator = collator_with(snapshotter.clone(), Duration::ZERO).await;
+        let calls = snapshotter.calls.load(Ordering::SeqCst);
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        collator.handle_message_get_session_ids(tx).unwrap();
+        let ids = rx.await.unwrap();
+
+        assert_eq!(ids.distinct_id, "alice");
+        assert_eq!(ids.session_id, collator.session_id.to_string());
+        assert_eq!(snapshotter.calls.load(Ordering::SeqCst), calls);
+    }
+
+    #[tokio::test]
+    async fn snapshot_is_refreshed_after_ttl() {
+        let snapshotter = CountingSnapshotter::default();
+        let mut collator = collator_with(snapshotter.clone(), Duration::ZERO).await;
+
+        let (outgoing, _rx) = channel(1000);
+        collator.outgoing = outgoing;
+
+        for _ in 0..10 {
+            collator
+                .handle_message_event("burst".to_string(), None, None, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(snapshotter.calls.load(Ordering::SeqCst), 10);
+    }
+
+    #[tokio::test]
+    async fn reset_option_combinations() {
+        for groups in [false, true] {
+            for facts in [false, true] {
+                for device_id in [false, true] {
+                    let options = ResetOptions {
+                        groups,
+                        facts,
+                        device_id,
+                    };
+
+                    let mut collator = collator().await;
+                    collator.handle_message_fact("runtime".to_string(), 1.into());
+
+                    let anon_before = collator.anon_distinct_id.clone();
+                    let device_before = collator.device_id.clone();
+
+                    collator.handle_message_reset(options).await.unwrap();
+
+                    assert_eq!(collator.distinct_id, None, "{options:?}");
+                    assert_ne!(collator.anon_distinct_id, anon_before, "{options:?}");
+                    assert_eq!(collator.groups.is_empty(), groups, "{options:?}");
+                    assert_eq!(
+                        collator.facts.contains_key("runtime"),
+                        !facts,
+                        "{options:?}"
+                    );
+                    assert!(collator.facts.contains_key("built"), "{options:?}");
+                    assert_eq!(
+                        collator.device_id != device_before,
+                        device_id,
+                        "{options:?}"
+                    );
+
+                    let stored = collator.storage.load().await.unwrap().unwrap();
+                    assert_eq!(stored, collator.properties_to_store(), "{options:?}");
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn group_overrides_do_not_modify_session_groups() {
+        let mut collator = collator().await;
+        let stored_before = collator.storage.load().await.unwrap();
+        let groups_before = collator.groups.clone();
+
+        let event = collator.msg_to_event(
+            crate::system_snapshot::SystemSnapshot::default(),
+            "ev".to_string(),
+            None,
+            Some(Groups::from_iter([(
+                "org".to_string(),
+                "other".to_string(),
+            )])),
+        );
+
+        assert_eq!(event.properties.groups["org"], "other");
+        assert_eq!(collator.groups, groups_before);
+        assert_eq!(collator.storage.load().await.unwrap(), stored_before);
+
+        let (outgoing, mut rx) = channel(1);
+        collator.outgoing = outgoing;
+        collator
+            .handle_message_event(
+                "ev".to_string(),
+                None,
+                Some(Groups::from_iter([(
+                    "org".to_string(),
+                    "other".to_string(),
+                )])),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(rx.recv().await.is_some());
+        assert_eq!(collator.groups, groups_before);
+        assert_eq!(collator.storage.load().await.unwrap(), stored_before);
+    }
+
+    #[test]
+    fn default_reset_clears_groups() {
+        assert_eq!(
+            ResetOptions::default(),
+            ResetOptions {
+                groups: true,
+                facts: false,
+                device_id: false,
+            }
+        );
+    }
+
+    async fn collator_with_stored_checkin(
+        fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Collator<crate::system_snapshot::Generic, crate::storage::Generic> {
+        let checkin: crate::checkin::Checkin = serde_json::from_value(
+            serde_json::json!({ "options": { "fast-path": { "variant": true } } }),
+        )
+        .unwrap();
+
+        let mut storage = crate::storage::Generic::default();
+        storage
+            .store(crate::storage::StoredProperties {
+                checkin,
+                checkin_fetched_at: fetched_at,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        collator_with_storage(
+            crate::system_snapshot::Generic::default(),
+            super::DEFAULT_SNAPSHOT_TTL,
+            storage,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn fresh_stored_checkin_is_used() {
+        let fetched_at = chrono::Utc::now() - chrono::Duration::days(1);
+        let collator = collator_with_stored_checkin(Some(fetched_at)).await;
+
+        assert!(
+            collator
+                .get_checkin()
+                .unwrap()
+                .options
+                .contains_key("fast-path")
+        );
+        assert_eq!(
+            collator
+                .storage
+                .load()
+                .await
+                .unwrap()
+                .unwrap()
+                .checkin_fetched_at,
+            Some(fetched_at)
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_stored_checkin_is_ignored() {
+        let collator =
+            collator_with_stored_checkin(Some(chrono::Utc::now() - chrono::Duration::days(30)))
+                .await;
+
+        assert!(collator.get_checkin().is_none());
+    }
+
+    #[tokio::test]
+    async fn stored_checkin_without_a_timestamp_is_ignored() {
+        let collator = collator_with_stored_checkin(None).await;
+
+        assert!(collator.get_checkin().is_none());
+    }
+
+    #[tokio::test]
+    async fn updating_the_checkin_records_when_it_was_fetched() {
+        let mut collator = collator_with_stored_checkin(None).await;
+        let before = chrono::Utc::now();
+
+        collator
+            .handle_message_update_feature_configuration(
+                Some(crate::checkin::Checkin::default()),
+                super::FeatureFacts::default(),
+            )
+            .await;
+
+        let stored = collator.storage.load().await.unwrap().unwrap();
+        assert!(stored.checkin_fetched_at.unwrap() >= before);
+    }
+
+    async fn collator_with_stored_anonymous_id(
+        created_at: Option<chrono::DateTime<chrono::Utc>>,
+        max_age: Option<Duration>,
+    ) -> Collator<crate::system_snapshot::Generic, crate::storage::Generic> {
+        let mut storage = crate::storage::Generic::default();
+        storage
+            .store(crate::storage::StoredProperties {
+                anonymous_distinct_id: crate::AnonymousDistinctId::from("old-anon".to_string()),
+                anonymous_id_created_at: created_at,
+                distinct_id: Some(DistinctId::from("alice".to_string())),
+                device_id: crate::DeviceId::from("the-device".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        collator_with_storage(
+            crate::system_snapshot::Generic::default(),
+            super::DEFAULT_SNAPSHOT_TTL,
+            storage,
+            max_age,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn old_anonymous_id_is_rotated() {
+        let created_at = chrono::Utc::now() - chrono::Duration::days(60);
+        let mut collator = collator_with_stored_anonymous_id(
+            Some(created_at),
+            Some(Duration::from_secs(86400 * 30)),
+        )
+        .await;
+
+        let stored = collator.storage.load().await.unwrap().unwrap();
+        assert_ne!(stored.anonymous_distinct_id.to_string(), "old-anon");
+        assert_eq!(stored.anonymous_distinct_id, collator.anon_distinct_id);
+        assert!(stored.anonymous_id_created_at.unwrap() > created_at);
+        assert_eq!(stored.device_id.to_string(), "the-device");
+        assert_eq!(stored.distinct_id.unwrap().to_string(), "alice");
+
+        let (outgoing, mut rx) = channel(1);
+        collator.outgoing = outgoing;
+        collator
+            .handle_message_event("ev".to_string(), None, None, None)
+            .await
+            .unwrap();
+
+        let Some(super::CollatedSignal::Event(event)) = rx.recv().await else {
+            panic!("expected an event");
+        };
+        assert_eq!(
+            event.properties.anon_distinct_id,
+            stored.anonymous_distinct_id.to_string()
+        );
+        assert_eq!(event.properties.device_id, "the-device");
+    }
+
+    #[tokio::test]
+    async fn young_anonymous_id_is_kept() {
+        let created_at = chrono::Utc::now() - chrono::Duration::days(1);
+        let collator = collator_with_stored_anonymous_id(
+            Some(created_at),
+            Some(Duration::from_secs(86400 * 30)),
+        )
+        .await;
+
+        let stored = collator.storage.load().await.unwrap().unwrap();
+        assert_eq!(stored.anonymous_distinct_id.to_string(), "old-anon");
+        assert_eq!(stored.anonymous_id_created_at, Some(created_at));
+    }
+
+    #[tokio::test]
+    async fn anonymous_id_is_not_rotated_by_default() {
+        let created_at = chrono::Utc::now() - chrono::Duration::days(365);
+        let collator = collator_with_stored_anonymous_id(Some(created_at), None).await;
+
+        assert_eq!(collator.anon_distinct_id.to_string(), "old-anon");
+        assert_eq!(collator.anon_distinct_id_created_at, created_at);
+    }
+
+    #[tokio::test]
+    async fn anonymous_id_without_a_timestamp_starts_aging() {
+        let before = chrono::Utc::now();
+        let collator = collator_with_stored_anonymous_id(None, Some(Duration::from_secs(60))).await;
+
+        let stored = collator.storage.load().await.unwrap().unwrap();
+        assert_eq!(stored.anonymous_distinct_id.to_string(), "old-anon");
+        assert!(stored.anonymous_id_created_at.unwrap() >= before);
+    }
+}
@@ -3,6 +3,7 @@ use tokio::io::AsyncWriteExt;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) struct CompressionSet {
+    pub(crate) lz4: bool,
     pub(crate) zstd: bool,
 }
 
@@ -12,14 +13,37 @@ impl CompressionSet {
             CompressionAlgorithm::Identity => {
                 // noop
             }
+            CompressionAlgorithm::Lz4 => {
+                self.lz4 = false;
+            }
             CompressionAlgorithm::Zstd => {
                 self.zstd = false;
             }
         }
     }
 
+    /// The highest-priority enabled algorithm, without consuming the set.
+    pub(crate) const fn preferred_algorithm(&self) -> CompressionAlgorithm {
+        if self.lz4 {
+            CompressionAlgorithm::Lz4
+        } else if self.zstd {
+            CompressionAlgorithm::Zstd
+        } else {
+            CompressionAlgorithm::Identity
+        }
+    }
+
+    /// Whether any algorithm besides `Identity` is enabled.
+    pub(crate) const fn has_any_compression(&self) -> bool {
+        self.lz4 || self.zstd
+    }
+
     pub(crate) fn into_iter(self) -> std::vec::IntoIter<CompressionAlgorithm> {
-        let mut algos = Vec::with_capacity(2);
+        let mut algos = Vec::with_capacity(3);
+        if self.lz4 {
+            algos.push(CompressionAlgorithm::Lz4);
+        }
+
         if self.zstd {
             algos.push(CompressionAlgorithm::Zstd);
         }
@@ -31,8 +55,12 @@ impl CompressionSet {
 }
 
 impl std::default::Default for CompressionSet {
+    /// zstd only, since requests sent before the first check-in can't know whether the server accepts lz4.
     fn default() -> Self {
-        Self { zstd: true }
+        Self {
+            lz4: false,
+            zstd: true,
+        }
     }
 }
 
@@ -55,13 +83,22 @@ impl<'de> Deserialize<'de> for CompressionSet {
             .collect();
 
         if algos.is_empty() {
-            return Ok(CompressionSet { zstd: false });
+            return Ok(CompressionSet {
+                lz4: false,
+                zstd: false,
+            });
         }
 
-        let mut set = CompressionSet { zstd: false };
+        let mut set = CompressionSet {
+            lz4: false,
+            zstd: false,
+        };
 
         for algo in algos.into_iter() {
             match algo {
+                CompressionAlgorithm::Lz4 => {
+                    set.lz4 = true;
+                }
                 CompressionAlgorithm::Zstd => {
                     set.zstd = true;
                 }
@@ -79,6 +116,7 @@ impl<'de> Deserialize<'de> for CompressionSet {
 #[serde(rename_all = "kebab-case")]
 pub(crate) enum CompressionAlgorithm {
     Identity,
+    Lz4,
     Zstd,
 }
 
@@ -86,6 +124,7 @@ impl CompressionAlgorithm {
     pub(crate) fn content_encoding(&self) -> Option<String> {
         match self {
             CompressionAlgorithm::Identity => None,
+            CompressionAlgorithm::Lz4 => Some("lz4".to_string()),
             CompressionAlgorithm::Zstd => Some("zstd".to_string()),
         }
     }
@@ -93,6 +132,14 @@ impl CompressionAlgorithm {
     pub(crate) async fn compress(&self, r: &[u8]) -> Result<Vec<u8>, std::io::Error> {
         match self {
             CompressionAlgorithm::Identity => Ok(r.into()),
+            CompressionAlgorithm::Lz4 => {
+                let mut output: Vec<u8> = vec![];
+                let mut encoder = async_compression::tokio::write::Lz4Encoder::new(&mut output);
+                encoder.write_all(r).await?;
+                encoder.shutdown().await?;
+
+                Ok(output)
+            }
             CompressionAlgorithm::Zstd => {
                 let mut output: Vec<u8> = vec![];
                 let mut encoder = async_compression::tokio::write::ZstdEncoder::new(&mut output);
@@ -107,7 +154,9 @@ impl CompressionAlgorithm {
 
 #[cfg(test)]
 mod test {
-    use super::CompressionSet;
+    use tokio::io::AsyncWriteExt;
+
+    use super::{CompressionAlgorithm, CompressionSet};
 
     #[test]
     fn test_parse_compression_empty_defaults_to_identity() {
@@ -118,7 +167,10 @@ mod test {
 
         assert_eq!(
             serde_json::from_str::<CompressionSet>(json).unwrap(),
-            CompressionSet { zstd: false }
+            CompressionSet {
+                lz4: false,
+                zstd: false
+            }
         );
     }
 
@@ -133,7 +185,10 @@ mod test {
 
         assert_eq!(
             serde_json::from_str::<CompressionSet>(json).unwrap(),
-            CompressionSet { zstd: true }
+            CompressionSet {
+                lz4: false,
+                zstd: true
+            }
         );
     }
 
@@ -147,7 +202,10 @@ mod test {
 
         assert_eq!(
             serde_json::from_str::<CompressionSet>(json).unwrap(),
-            CompressionSet { zstd: true }
+            CompressionSet {
+                lz4: false,
+                zstd: true
+            }
         );
     }
 
@@ -162,7 +220,106 @@ mod test {
 
         assert_eq!(
             serde_json::from_str::<CompressionSet>(json).unwrap(),
-            CompressionSet { zstd: true }
+            CompressionSet {
+                lz4: false,
+                zstd: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_compression_lz4() {
+        let json = r#"
+        [
+          "lz4",
+          "zstd",
+          "identity"
+        ]
+        "#;
+
+        assert_eq!(
+            serde_json::from_str::<CompressionSet>(json).unwrap(),
+            CompressionSet {
+                lz4: true,
+                zstd: true
+            }
         );
     }
+
+    #[test]
+    fn test_default_is_zstd_only() {
+        assert_eq!(
+            CompressionSet::default().into_iter().collect::<Vec<_>>(),
+            vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Identity]
+        );
+    }
+
+    #[test]
+    fn test_preference_order() {
+        let set = CompressionSet {
+            lz4: true,
+            zstd: true,
+        };
+
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![
+                CompressionAlgorithm::Lz4,
+                CompressionAlgorithm::Zstd,
+                CompressionAlgorithm::Identity
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lz4_rejected_falls_back_to_zstd() {
+        let mut set = CompressionSet {
+            lz4: true,
+            zstd: true,
+        };
+        set.delete(&CompressionAlgorithm::Lz4);
+
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Identity]
+        );
+
+        set.delete(&CompressionAlgorithm::Zstd);
+
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![CompressionAlgorithm::Identity]
+        );
+    }
+
+    #[test]
+    fn test_preferred_algorithm() {
+        for (lz4, zstd, preferred) in [
+            (false, false, CompressionAlgorithm::Identity),
+            (false, true, CompressionAlgorithm::Zstd),
+            (true, false, CompressionAlgorithm::Lz4),
+            (true, true, CompressionAlgorithm::Lz4),
+        ] {
+            let set = CompressionSet { lz4, zstd };
+
+            assert_eq!(set.preferred_algorithm(), preferred);
+            assert_eq!(set.into_iter().next(), Some(preferred));
+            assert_eq!(set.has_any_compression(), lz4 || zstd);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lz4_round_trip() {
+        let input = br#"{"sent_at":"2024-01-01T00:00:00Z","batch":[]}"#.repeat(10);
+
+        let compressed = CompressionAlgorithm::Lz4.compress(&input).await.unwrap();
+        assert_ne!(compressed, input);
+
+        let mut output: Vec<u8> = vec![];
+        let mut decoder = async_compression::tokio::write::Lz4Decoder::new(&mut output);
+        decoder.write_all(&compressed).await.unwrap();
+        decoder.shutdown().await.unwrap();
+
+        assert_eq!(output, input);
+    }
 }
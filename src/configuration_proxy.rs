@@ -8,8 +8,9 @@ use tokio::sync::oneshot;
 use tokio::sync::oneshot::Sender as OneshotSender;
 use tracing::Instrument;
 
-use crate::checkin::CheckinDiff;
+use crate::checkin::CoherentFeatureFlags;
 use crate::recorder::RawSignal;
+use crate::submitter::{BatchingRequest, PauseSwitch};
 use crate::{
     Map,
     checkin::{Checkin, Feature},
@@ -19,21 +20,186 @@ use crate::{
 #[derive(Debug)]
 pub(crate) enum ConfigurationProxySignal {
     QueryIfCheckedIn(OneshotSender<CheckinStatus>),
-    GetFeature(
-        String,
-        OneshotSender<Option<Arc<Feature<serde_json::Value>>>>,
-    ),
+    GetFeature(String, OneshotSender<Option<ServedFeature>>),
     CheckInNow(Map, OneshotSender<(Option<Checkin>, FeatureFacts)>),
+    RefreshConfiguration(Map, OneshotSender<Result<CheckinSummary, String>>),
+    QueryCheckinStatus(OneshotSender<CheckinStatusReport>),
     Subscribe(OneshotSender<broadcast::Receiver<()>>),
 }
 
+/// The shortest refresh interval while the server has paused sending events.
+const PAUSED_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// How far each refresh may move, as a fraction of the refresh interval.
+pub(crate) const DEFAULT_REFRESH_JITTER: f64 = 0.1;
+const MAX_REFRESH_JITTER: f64 = 0.5;
+
+/// Randomly spreads refreshes out, so clients started at the same time don't keep checking in together.
+#[derive(Clone)]
+pub(crate) struct Jitter {
+    fraction: f64,
+    source: Arc<dyn Fn() -> f64 + Send + Sync>,
+}
+
+impl Jitter {
+    pub(crate) fn new(fraction: f64) -> Self {
+        Self::with_source(fraction, Arc::new(random_unit))
+    }
+
+    /// Jitter drawing from `source`, which returns values between 0 and 1.
+    pub(crate) fn with_source(fraction: f64, source: Arc<dyn Fn() -> f64 + Send + Sync>) -> Self {
+        if !(0.0..=MAX_REFRESH_JITTER).contains(&fraction) {
+            tracing::warn!(
+                fraction,
+                maximum = MAX_REFRESH_JITTER,
+                "The configuration refresh jitter is out of range, clamping it"
+            );
+        }
+
+        Self {
+            // NaN fails the range check too, and means no jitter
+            fraction: fraction.clamp(0.0, MAX_REFRESH_JITTER).max(0.0),
+            source,
+        }
+    }
+
+    /// `period`, moved earlier or later by up to the jitter fraction.
+    fn apply(&self, period: std::time::Duration) -> std::time::Duration {
+        period.mul_f64(1.0 + self.fraction * (2.0 * self.sample() - 1.0))
+    }
+
+    /// `deadline`, moved earlier by up to the jitter fraction of the time left until it, so refreshes spread out without passing it.
+    fn before(&self, deadline: tokio::time::Instant) -> tokio::time::Instant {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        deadline - remaining.mul_f64(self.fraction * self.sample())
+    }
+
+    /// A delay of up to the jitter fraction of `period`, offsetting the first refresh after startup.
+    fn phase(&self, period: std::time::Duration) -> std::time::Duration {
+        period.mul_f64(self.fraction * self.sample())
+    }
+
+    fn sample(&self) -> f64 {
+        if self.fraction == 0.0 {
+            return 0.5;
+        }
+
+        let sample = (self.source)();
+        if sample.is_nan() {
+            0.5
+        } else {
+            sample.clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// A random number between 0 and 1, from the random bits of a v4 UUID.
+pub(crate) fn random_unit() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() >> 80) as f64 / (1u64 << 48) as f64
+}
+
+/// The parts of `session_properties` which say who a check-in is for, to tell whether a cached check-in still applies.
+///
+/// The system snapshot is left out, since measurements like free disk space change between otherwise identical requests.
+fn checkin_session(session_properties: &Map) -> Map {
+    let mut session = session_properties.clone();
+    session.remove("person_properties");
+    session
+}
+
+/// How many feature configuration changes a subscriber can fall behind by before it misses some.
+const CHANGE_NOTIFIER_CAPACITY: usize = 16;
+
+/// The longest refresh interval while check-ins keep failing.
+const MAX_BACKOFF_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Consecutive check-in failures, and when triggered check-ins may reach the transport again.
+#[derive(Debug, Default)]
+struct Backoff {
+    failures: u32,
+    retry_at: Option<tokio::time::Instant>,
+}
+
+impl Backoff {
+    fn is_waiting(&self) -> bool {
+        self.retry_at
+            .is_some_and(|retry_at| tokio::time::Instant::now() < retry_at)
+    }
+}
+
+/// Double `period` for each consecutive failure after the first, up to `MAX_BACKOFF_INTERVAL`.
+fn backoff_interval(period: std::time::Duration, failures: u32) -> std::time::Duration {
+    period
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_BACKOFF_INTERVAL.max(period))
+}
+
+#[derive(Debug)]
+pub(crate) struct ServedFeature {
+    pub(crate) feature: Arc<Feature<serde_json::Value>>,
+
+    /// Whether the feature came from the defaults given to the Builder, rather than a check-in.
+    pub(crate) is_default: bool,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum CheckinStatus {
     CheckedIn,
     NotYet,
 }
 
-type CheckInPropsWithReply = (Map, OneshotSender<(Option<Checkin>, FeatureFacts)>);
+/// Where the feature configuration currently being served came from.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum CheckinSource {
+    /// No check-in has succeeded, and nothing usable was stored.
+    #[default]
+    None,
+
+    /// A check-in stored by an earlier run.
+    Cache,
+
+    /// A check-in fetched by this run.
+    Network,
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct CheckinStatusReport {
+    /// When the configuration being served was fetched.
+    pub last_success: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// The error from the most recent check-in, if it failed.
+    pub last_error: Option<String>,
+
+    pub source: CheckinSource,
+}
+
+/// The outcome of a successful `Recorder::refresh_configuration`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CheckinSummary {
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+
+    /// Whether the configuration differs from what was served before.
+    pub changed: bool,
+
+    /// The number of features in the configuration.
+    pub feature_count: usize,
+}
+
+#[derive(Debug)]
+pub(crate) enum CheckinReply {
+    Facts(OneshotSender<(Option<Checkin>, FeatureFacts)>),
+    Summary(OneshotSender<Result<CheckinSummary, String>>),
+}
+
+type CheckInPropsWithReply = (Map, CheckinReply);
+
+/// A check-in which reached the server.
+struct CheckedIn {
+    summary: CheckinSummary,
+
+    /// The properties of the `$feature_flags_changed` event, if the configuration changed.
+    change_properties: Option<Map>,
+}
 
 #[derive(Error, Debug)]
 pub(crate) enum ConfigurationProxyError {
@@ -52,40 +218,98 @@ pub(crate) enum ConfigurationProxyError {
 
 pub(crate) struct ConfigurationProxy<T: crate::transport::Transport> {
     checkin: RwLock<Option<Checkin>>,
+    status: RwLock<CheckinStatusReport>,
+    backoff: RwLock<Backoff>,
+    /// When the current check-in expires, if the server said.
+    valid_until: RwLock<Option<tokio::time::Instant>>,
+    /// The session the current check-in was fetched for, as from [`checkin_session`].
+    checked_in_session: RwLock<Option<Map>>,
+    defaults: CoherentFeatureFlags,
+    pause: PauseSwitch,
+    batching: tokio::sync::watch::Sender<BatchingRequest>,
+    refresh_interval: std::time::Duration,
+    jitter: Jitter,
     transport: T,
     incoming: Option<mpsc::Receiver<ConfigurationProxySignal>>,
     collator: mpsc::Sender<crate::recorder::RawSignal>,
+    shutdown: tokio::sync::watch::Receiver<bool>,
     change_notifier: broadcast::Sender<()>,
 }
 
 impl<T: crate::transport::Transport> ConfigurationProxy<T> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         transport: T,
         incoming: mpsc::Receiver<ConfigurationProxySignal>,
         collator: mpsc::Sender<crate::recorder::RawSignal>,
+        pause: PauseSwitch,
+        batching: tokio::sync::watch::Sender<BatchingRequest>,
+        refresh_interval: std::time::Duration,
+        jitter: Jitter,
+        shutdown: tokio::sync::watch::Receiver<bool>,
     ) -> Self {
+        if refresh_interval < crate::checkin::MIN_REFRESH_INTERVAL {
+            tracing::warn!(
+                ?refresh_interval,
+                minimum = ?crate::checkin::MIN_REFRESH_INTERVAL,
+                "The configuration refresh interval is too short, using the minimum"
+            );
+        }
+
         Self {
             checkin: None.into(),
+            status: CheckinStatusReport::default().into(),
+            backoff: Backoff::default().into(),
+            valid_until: None.into(),
+            checked_in_session: None.into(),
+            defaults: CoherentFeatureFlags::default(),
+            pause,
+            batching,
+            refresh_interval: refresh_interval.max(crate::checkin::MIN_REFRESH_INTERVAL),
+            jitter,
             transport,
             incoming: Some(incoming),
             collator,
-            change_notifier: broadcast::Sender::new(1),
+            shutdown,
+            change_notifier: broadcast::Sender::new(CHANGE_NOTIFIER_CAPACITY),
         }
     }
 
-    pub(crate) async fn bootstrap_checkin(&mut self, checkin: Option<Checkin>) {
+    pub(crate) async fn bootstrap_checkin(
+        &mut self,
+        checkin: Option<Checkin>,
+        fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        if checkin.is_some() {
+            *self.status.write().await = CheckinStatusReport {
+                last_success: fetched_at,
+                last_error: None,
+                source: CheckinSource::Cache,
+            };
+        }
+
         let mut c = self.checkin.write().await;
         *c = checkin;
     }
 
+    /// Serve `defaults` for features which the check-in doesn't have.
+    pub(crate) fn bootstrap_defaults(&mut self, defaults: CoherentFeatureFlags) {
+        self.defaults = defaults;
+    }
+
     #[tracing::instrument(skip(self))]
     pub(crate) async fn execute(mut self) -> Result<(), ConfigurationProxyError> {
         let incoming = self.incoming.take().expect("Incoming stream is None");
 
         let (checkin_trigger, checkin_rx) = mpsc::channel::<CheckInPropsWithReply>(100);
+        let mut shutdown = self.shutdown.clone();
 
         tokio::select! {
             biased;
+            Ok(_) = shutdown.wait_for(|stop| *stop) => {
+                tracing::debug!("Shutting down the configuration proxy");
+                return Ok(());
+            },
             e = self.execute_incoming_worker(incoming, checkin_trigger) => {
                 return Ok(e);
             },
@@ -121,10 +345,26 @@ impl<T: crate::transport::Transport> ConfigurationProxy<T> {
                     }
                 }
                 ConfigurationProxySignal::CheckInNow(session_properties, reply) => {
-                    if let Err(e) = checkin_trigger.send((session_properties, reply)).await {
+                    if let Err(e) = checkin_trigger
+                        .send((session_properties, CheckinReply::Facts(reply)))
+                        .await
+                    {
                         tracing::debug!(%e, "Failure while handling a CheckInNow message");
                     }
                 }
+                ConfigurationProxySignal::RefreshConfiguration(session_properties, reply) => {
+                    if let Err(e) = checkin_trigger
+                        .send((session_properties, CheckinReply::Summary(reply)))
+                        .await
+                    {
+                        tracing::debug!(%e, "Failure while handling a RefreshConfiguration message");
+                    }
+                }
+                ConfigurationProxySignal::QueryCheckinStatus(reply) => {
+                    if let Err(e) = self.handle_message_query_checkin_status(reply).await {
+                        tracing::debug!(%e, "Failure while handling a QueryCheckinStatus message");
+                    }
+                }
                 ConfigurationProxySignal::Subscribe(reply) => {
                     if let Err(e) = self.handle_message_subscribe(reply).await {
                         tracing::debug!(%e, "Failure while handling a Subscribe message");
@@ -138,35 +378,105 @@ impl<T: crate::transport::Transport> ConfigurationProxy<T> {
         &self,
         mut checkin_rx: mpsc::Receiver<CheckInPropsWithReply>,
     ) -> () {
-        let mut refresh_interval =
-            tokio::time::interval(std::time::Duration::from_secs(60 * 60 * 2));
-        refresh_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut period = self.requested_refresh_interval().await;
+
+        // Check in right away, then offset the refreshes so clients started together spread out
+        let mut next_refresh = tokio::time::Instant::now();
+        let mut phase = Some(self.jitter.phase(period));
 
         loop {
-            tokio::select! {
+            let mut reschedule = tokio::select! {
                 biased;
                 event = checkin_rx.recv() => {
-                    let Some((session_properties, reply)) = event else {
+                    let Some(request) = event else {
                         tracing::debug!("Incoming worker hung up, shutting down");
 
                         return;
                     };
 
-                    if let Err(e) = self.handle_message_check_in_now(session_properties, reply).await {
-                        tracing::debug!(%e, "Failure while handling a CheckInNow message");
+                    // Requests which queued up during the previous check-in share one transport call
+                    let mut requests = vec![request];
+                    while let Ok(request) = checkin_rx.try_recv() {
+                        requests.push(request);
+                    }
+
+                    // Requests served from the cache leave the next refresh where it is
+                    match self.handle_checkin_requests(requests, false).await {
+                        Ok(reached_transport) => reached_transport,
+                        Err(e) => {
+                            tracing::debug!(%e, "Failure while handling check-in requests");
+                            true
+                        }
                     }
-                    refresh_interval.reset();
                 }
-                _ = refresh_interval.tick() => {
-                    tracing::debug!("Checking in after the refresh interval ticked");
+                _ = tokio::time::sleep_until(next_refresh) => {
+                    tracing::debug!("Checking in after the refresh interval elapsed");
                     if let Err(e) = self.check_in_now().await {
                         tracing::debug!(%e, "Failure processing a refresh tick");
                     }
+
+                    true
                 }
+            };
+
+            let requested = self.requested_refresh_interval().await;
+            if requested != period {
+                tracing::debug!(?period, ?requested, "Changing the refresh interval");
+
+                period = requested;
+                reschedule = true;
+            }
+
+            if reschedule {
+                let phase = phase.take().unwrap_or_default();
+
+                next_refresh = match *self.valid_until.read().await {
+                    Some(valid_until) => {
+                        let refresh_at = self.jitter.before(valid_until);
+                        tracing::trace!(
+                            ?valid_until,
+                            ?refresh_at,
+                            "Refreshing before the check-in expires"
+                        );
+
+                        refresh_at
+                    }
+                    None => {
+                        let delay = self.jitter.apply(period) + phase;
+                        tracing::trace!(?delay, "Scheduling the next refresh");
+
+                        tokio::time::Instant::now() + delay
+                    }
+                };
             }
         }
     }
 
+    async fn requested_refresh_interval(&self) -> std::time::Duration {
+        backoff_interval(
+            self.base_refresh_interval().await,
+            self.backoff.read().await.failures,
+        )
+    }
+
+    /// The refresh interval before backing off failed check-ins.
+    async fn base_refresh_interval(&self) -> std::time::Duration {
+        let requested = self
+            .checkin
+            .read()
+            .await
+            .as_ref()
+            .and_then(|checkin| checkin.server_options.refresh_interval())
+            .unwrap_or(self.refresh_interval);
+
+        if self.pause.is_paused() {
+            // Keep checking in, but slowly, so the server can lift the pause
+            requested.max(PAUSED_REFRESH_INTERVAL)
+        } else {
+            requested
+        }
+    }
+
     async fn handle_message_query_if_checked_in(
         &self,
         reply: OneshotSender<CheckinStatus>,
@@ -186,10 +496,21 @@ impl<T: crate::transport::Transport> ConfigurationProxy<T> {
         Ok(())
     }
 
+    async fn handle_message_query_checkin_status(
+        &self,
+        reply: OneshotSender<CheckinStatusReport>,
+    ) -> Result<(), ConfigurationProxyError> {
+        reply
+            .send(self.status.read().await.clone())
+            .map_err(|e| ConfigurationProxyError::Reply(format!("{e:?}")))?;
+
+        Ok(())
+    }
+
     async fn handle_message_get_feature(
         &self,
         name: String,
-        reply: OneshotSender<Option<Arc<Feature<serde_json::Value>>>>,
+        reply: OneshotSender<Option<ServedFeature>>,
     ) -> Result<(), ConfigurationProxyError> {
         let feat = self
             .checkin
@@ -199,7 +520,16 @@ impl<T: crate::transport::Transport> ConfigurationProxy<T> {
             .map(|c| &c.options)
             .as_ref()
             .and_then(|o| o.get(&name))
-            .cloned();
+            .map(|feature| ServedFeature {
+                feature: feature.clone(),
+                is_default: false,
+            })
+            .or_else(|| {
+                Some(ServedFeature {
+                    feature: self.defaults.get(&name)?.clone(),
+                    is_default: true,
+                })
+            });
 
         reply
             .send(feat)
@@ -226,59 +556,258 @@ impl<T: crate::transport::Transport> ConfigurationProxy<T> {
 
         let (sender, receiver) = oneshot::channel();
 
-        self.handle_message_check_in_now(session_properties, sender)
+        // The refresh interval is already backed off, so a periodic check-in always goes ahead
+        self.handle_checkin_requests(
+            vec![(session_properties, CheckinReply::Facts(sender))],
+            true,
+        )
+        .await?;
+
+        let (checkin, feature_facts) = receiver.await?;
+        tracing::debug!(?checkin, "Checked in after timeout");
+
+        self.collator
+            .send(RawSignal::UpdateFeatureConfiguration(
+                checkin,
+                feature_facts,
+            ))
+            .instrument(tracing::trace_span!(
+                "sending the UpdateFeatureConfiguration message"
+            ))
             .await?;
 
-        let reply = receiver.await?;
-        tracing::debug!(?reply, "Checked in after timeout");
-
         Ok(())
     }
 
-    async fn handle_message_check_in_now(
+    /// Check in once for every request in `requests`, using the most recent session properties.
+    ///
+    /// Unless a request is forced, or asks for a summary, requests made while backing off, or for the same session before the check-in expires, are served the current configuration instead.
+    /// Returns whether the transport was used.
+    async fn handle_checkin_requests(
         &self,
-        session_properties: Map,
-        reply: OneshotSender<(Option<Checkin>, FeatureFacts)>,
-    ) -> Result<(), ConfigurationProxyError> {
-        let fresh_checkin: Option<Checkin> = self
-            .transport
-            .checkin(session_properties)
-            .await
-            .inspect_err(|e| tracing::debug!(%e, "Error refreshing checkin configuration"))
-            .ok();
+        requests: Vec<CheckInPropsWithReply>,
+        force: bool,
+    ) -> Result<bool, ConfigurationProxyError> {
+        let wants_summary = requests
+            .iter()
+            .any(|(_, reply)| matches!(reply, CheckinReply::Summary(_)));
+        let Some(session_properties) = requests.last().map(|(props, _)| props.clone()) else {
+            return Ok(false);
+        };
 
-        let mut current_checkin = self.checkin.write().await;
+        if requests.len() > 1 {
+            tracing::debug!(count = requests.len(), "Coalescing check-in requests");
+        }
 
-        let changed = fresh_checkin.is_some() && fresh_checkin != *current_checkin;
-        let diff = fresh_checkin
-            .diff(current_checkin.as_ref())
-            .unwrap_or_else(|| "No change".into());
+        if !force && !wants_summary && self.serves_cached_checkins(&session_properties).await {
+            let (_, feature_facts) = self.current_checkin().await;
+            for (_, reply) in requests {
+                if let CheckinReply::Facts(reply) = reply
+                    && let Err(e) = reply.send((None, feature_facts.clone()))
+                {
+                    tracing::debug!(?e, "Failure replying to a check-in request");
+                }
+            }
 
-        tracing::trace!(changed, diff, "Checked in");
+            return Ok(false);
+        }
 
-        if changed {
-            if let Some(fresh) = fresh_checkin {
-                current_checkin.replace(fresh);
+        let (summary, change_properties) = match self.check_in(session_properties).await {
+            Ok(checked_in) => (Ok(checked_in.summary), checked_in.change_properties),
+            Err(e) => (Err(e), None),
+        };
+
+        let (current_checkin, feature_facts) = self.current_checkin().await;
+
+        // Callers asking for facts update the collator themselves, but summaries don't carry the configuration
+        if wants_summary && summary.is_ok() {
+            self.collator
+                .send(RawSignal::UpdateFeatureConfiguration(
+                    current_checkin.clone(),
+                    feature_facts.clone(),
+                ))
+                .await?;
+        }
+
+        for (_, reply) in requests {
+            let sent = match reply {
+                // Only a check-in from the network is worth storing, so a cached one doesn't look fresh
+                CheckinReply::Facts(reply) => reply
+                    .send((
+                        current_checkin.clone().filter(|_| summary.is_ok()),
+                        feature_facts.clone(),
+                    ))
+                    .map_err(|e| format!("{e:?}")),
+                CheckinReply::Summary(reply) => {
+                    reply.send(summary.clone()).map_err(|e| format!("{e:?}"))
+                }
+            };
+
+            if let Err(e) = sent {
+                tracing::debug!(%e, "Failure replying to a check-in request");
             }
         }
 
-        let current_checkin = current_checkin.downgrade().clone();
+        self.announce_change(change_properties).await?;
 
+        Ok(true)
+    }
+
+    /// Whether check-in requests for `session_properties` should get the current configuration instead of reaching the transport.
+    async fn serves_cached_checkins(&self, session_properties: &Map) -> bool {
+        if self.backoff.read().await.is_waiting() {
+            tracing::trace!("Serving the current configuration while backing off failed check-ins");
+            return true;
+        }
+
+        if let Some(valid_until) = *self.valid_until.read().await
+            && tokio::time::Instant::now() < valid_until
+        {
+            if self.checked_in_session.read().await.as_ref()
+                != Some(&checkin_session(session_properties))
+            {
+                tracing::trace!("The session changed since the last check-in, checking in again");
+                return false;
+            }
+
+            tracing::trace!(
+                ?valid_until,
+                "Serving the current configuration until it expires"
+            );
+            return true;
+        }
+
+        false
+    }
+
+    async fn current_checkin(&self) -> (Option<Checkin>, FeatureFacts) {
+        let current_checkin = self.checkin.read().await.clone();
         let feature_facts = current_checkin
             .as_ref()
             .map(|f| f.as_feature_facts())
             .unwrap_or_default();
 
-        reply
-            .send((current_checkin.clone(), feature_facts))
-            .map_err(|e| ConfigurationProxyError::Reply(format!("{e:?}")))?;
+        (current_checkin, feature_facts)
+    }
 
-        if changed {
-            if let Err(e) = self.change_notifier.send(()) {
-                tracing::debug!(%e, "Error notifying subscribers to changed feature configuration");
+    /// Fetch a check-in from the transport and start serving it, recording the outcome in the status report.
+    async fn check_in(&self, session_properties: Map) -> Result<CheckedIn, String> {
+        let session = checkin_session(&session_properties);
+        let fetched = self
+            .transport
+            .checkin(session_properties)
+            .await
+            .map_err(|e| {
+                tracing::debug!(%e, "Error refreshing checkin configuration");
+                e.to_string()
+            });
+
+        let mut fresh = match fetched {
+            Ok(fresh) => fresh,
+            Err(e) => {
+                self.status.write().await.last_error = Some(e.clone());
+                self.back_off().await;
+
+                return Err(e);
             }
+        };
+
+        *self.backoff.write().await = Backoff::default();
+        *self.checked_in_session.write().await = Some(session);
+
+        // Taken out, so an otherwise identical check-in doesn't count as a change
+        *self.valid_until.write().await = fresh
+            .server_options
+            .valid_for()
+            .map(|valid_for| tokio::time::Instant::now() + valid_for);
+        fresh.server_options.valid_until = None;
+
+        let fetched_at = chrono::Utc::now();
+        *self.status.write().await = CheckinStatusReport {
+            last_success: Some(fetched_at),
+            last_error: None,
+            source: CheckinSource::Network,
+        };
+
+        let mut current_checkin = self.checkin.write().await;
+
+        let changeset = fresh.changeset(current_checkin.as_ref());
+        let changed = changeset.is_some();
+
+        match changeset {
+            Some(ref changeset) => tracing::trace!(changed, diff = %changeset, "Checked in"),
+            None => tracing::trace!(changed, diff = "No change", "Checked in"),
         }
 
+        self.pause.set(fresh.server_options.pause());
+        self.batching.send_if_modified(|current| {
+            let requested = fresh.server_options.batching();
+            std::mem::replace(current, requested) != requested
+        });
+
+        let summary = CheckinSummary {
+            fetched_at,
+            changed,
+            feature_count: fresh.options.len(),
+        };
+
+        let change_properties = changed.then(|| fresh.change_properties(current_checkin.as_ref()));
+        if changed {
+            current_checkin.replace(fresh);
+        }
+
+        Ok(CheckedIn {
+            summary,
+            change_properties,
+        })
+    }
+
+    /// Count a failed check-in, and push the next periodic check-in back accordingly.
+    ///
+    /// A single failure may be a blip, so triggered check-ins only start using the cache once check-ins fail repeatedly.
+    /// Even then they wait at most the base refresh interval, rather than the backed off one.
+    async fn back_off(&self) {
+        let failures = {
+            let mut backoff = self.backoff.write().await;
+            backoff.failures = backoff.failures.saturating_add(1);
+            backoff.failures
+        };
+
+        // Retry on the backed off interval, rather than when the last check-in expires
+        *self.valid_until.write().await = None;
+
+        let retry_in = self.requested_refresh_interval().await;
+        tracing::debug!(?retry_in, failures, "Backing off after a failed check-in");
+
+        let retry_at = if failures > 1 {
+            Some(tokio::time::Instant::now() + self.base_refresh_interval().await)
+        } else {
+            None
+        };
+        self.backoff.write().await.retry_at = retry_at;
+    }
+
+    /// Notify subscribers and record `$feature_flags_changed`, if the configuration changed.
+    async fn announce_change(
+        &self,
+        change_properties: Option<Map>,
+    ) -> Result<(), ConfigurationProxyError> {
+        let Some(properties) = change_properties else {
+            return Ok(());
+        };
+
+        if let Err(e) = self.change_notifier.send(()) {
+            tracing::debug!(%e, "Error notifying subscribers to changed feature configuration");
+        }
+
+        self.collator
+            .send(RawSignal::Event {
+                event_name: "$feature_flags_changed".into(),
+                properties: Some(properties),
+                groups: None,
+            })
+            .await?;
+
         Ok(())
     }
 
@@ -293,3 +822,91 @@ impl<T: crate::transport::Transport> ConfigurationProxy<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{Jitter, MAX_BACKOFF_INTERVAL, backoff_interval};
+
+    /// Jitter which draws each of `samples` in turn, then starts over.
+    fn scripted(fraction: f64, samples: &'static [f64]) -> Jitter {
+        let next = AtomicUsize::new(0);
+        Jitter::with_source(
+            fraction,
+            Arc::new(move || samples[next.fetch_add(1, Ordering::Relaxed) % samples.len()]),
+        )
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_day() {
+        let period = Duration::from_secs(600);
+
+        assert_eq!(backoff_interval(period, 0), period);
+        assert_eq!(backoff_interval(period, 1), period);
+        assert_eq!(backoff_interval(period, 2), Duration::from_secs(1200));
+        assert_eq!(backoff_interval(period, 4), Duration::from_secs(4800));
+        assert_eq!(backoff_interval(period, 10), MAX_BACKOFF_INTERVAL);
+        assert_eq!(backoff_interval(period, u32::MAX), MAX_BACKOFF_INTERVAL);
+    }
+
+    #[test]
+    fn jitter_moves_within_the_band() {
+        let period = Duration::from_secs(1000);
+        let jitter = scripted(0.1, &[0.0, 1.0, 0.5, 0.25]);
+
+        assert_eq!(jitter.apply(period), Duration::from_secs(900));
+        assert_eq!(jitter.apply(period), Duration::from_secs(1100));
+        assert_eq!(jitter.apply(period), period);
+        assert_eq!(jitter.apply(period), Duration::from_secs(950));
+    }
+
+    #[test]
+    fn phase_delays_up_to_the_fraction() {
+        let period = Duration::from_secs(1000);
+        let jitter = scripted(0.1, &[0.0, 1.0, 0.5]);
+
+        assert_eq!(jitter.phase(period), Duration::ZERO);
+        assert_eq!(jitter.phase(period), Duration::from_secs(100));
+        assert_eq!(jitter.phase(period), Duration::from_secs(50));
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        let period = Duration::from_secs(1000);
+
+        let jitter = scripted(2.0, &[0.0]);
+        assert_eq!(jitter.apply(period), Duration::from_secs(500));
+
+        let jitter = scripted(f64::NAN, &[0.0]);
+        assert_eq!(jitter.apply(period), period);
+
+        let jitter = scripted(0.1, &[-3.0, 7.0, f64::NAN]);
+        assert_eq!(jitter.apply(period), Duration::from_secs(900));
+        assert_eq!(jitter.apply(period), Duration::from_secs(1100));
+        assert_eq!(jitter.apply(period), period);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deadlines_move_earlier_within_the_band() {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(1000);
+        let jitter = scripted(0.1, &[0.0, 1.0, 0.5]);
+
+        assert_eq!(jitter.before(deadline), deadline);
+        assert_eq!(jitter.before(deadline), deadline - Duration::from_secs(100));
+        assert_eq!(jitter.before(deadline), deadline - Duration::from_secs(50));
+
+        let passed = tokio::time::Instant::now() - Duration::from_secs(1);
+        assert_eq!(jitter.before(passed), passed);
+    }
+
+    #[test]
+    fn random_samples_are_between_zero_and_one() {
+        for _ in 0..1000 {
+            assert!((0.0..1.0).contains(&super::random_unit()));
+        }
+    }
+}
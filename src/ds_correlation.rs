@@ -16,18 +16,91 @@ enum CorrelationInputs {
 
 type OptionalGroups = HashMap<String, Option<String>>;
 
+/// Which correlation wins in `Correlation::merge` when both set a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Prefer the correlation `merge` is called on, like `Correlation::import` prefers `DETSYS_CORRELATION`.
+    SelfPriority,
+    /// Prefer the correlation passed to `merge`.
+    OtherPriority,
+}
+
 impl Correlation {
+    /// Read correlation data from the `DETSYS_CORRELATION` environment variable, or else the identity file.
+    /// The Builder always does this, so calling it directly is only needed to inspect the data.
     #[tracing::instrument]
-    pub(crate) fn import() -> Correlation {
-        Self::import_from_env()
+    pub fn import() -> Correlation {
+        Self::import_with(|name| std::env::var_os(name))
+    }
+
+    /// Like `import`, looking up environment variables with `var`.
+    pub(crate) fn import_with(var: impl Fn(&str) -> Option<std::ffi::OsString>) -> Correlation {
+        Self::import_from_env(var)
             .or_else(Self::import_from_file)
             .unwrap_or_default()
     }
 
-    #[tracing::instrument]
-    fn import_from_env() -> Option<Correlation> {
+    pub fn with_distinct_id(mut self, distinct_id: DistinctId) -> Self {
+        self.distinct_id = Some(distinct_id);
+        self
+    }
+
+    pub fn with_anon_distinct_id(mut self, anon_distinct_id: impl Into<String>) -> Self {
+        self.anon_distinct_id = Some(anon_distinct_id.into());
+        self
+    }
+
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn with_window_id(mut self, window_id: impl Into<String>) -> Self {
+        self.window_id = Some(window_id.into());
+        self
+    }
+
+    pub fn with_device_id(mut self, device_id: DeviceId) -> Self {
+        self.device_id = Some(device_id);
+        self
+    }
+
+    /// Add the process to `group_member_id` of the `group_name` group.
+    pub fn with_group(
+        mut self,
+        group_name: impl Into<String>,
+        group_member_id: impl Into<String>,
+    ) -> Self {
+        self.groups
+            .insert(group_name.into(), Some(group_member_id.into()));
+        self
+    }
+
+    /// Add a property to every event.
+    pub fn with_property(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    /// The correlation given to the Builder, if any, filled in from `import`.
+    pub(crate) fn supplied_or_imported(
+        supplied: Option<Correlation>,
+        import: impl FnOnce() -> Correlation,
+    ) -> Correlation {
+        match supplied {
+            Some(supplied) => supplied.merge(import(), MergeStrategy::SelfPriority),
+            None => import(),
+        }
+    }
+
+    #[tracing::instrument(skip(var))]
+    fn import_from_env(var: impl Fn(&str) -> Option<std::ffi::OsString>) -> Option<Correlation> {
         let correlation = serde_json::from_slice(
-            std::env::var_os("DETSYS_CORRELATION")?.as_bytes(),
+            var("DETSYS_CORRELATION")?.as_bytes(),
         )
         .inspect_err(
             |e| tracing::trace!(%e, %IDENTITY_FILE, "DETSYS_CORRELATION contained a malformed document"),
@@ -56,17 +129,57 @@ impl Correlation {
         }
     }
 
-    pub(crate) fn groups_as_hashmap(&self) -> crate::Groups {
+    /// Combine two correlations, such as one read from `DETSYS_CORRELATION` and one from the identity file.
+    ///
+    /// IDs come from the priority correlation when it has them, and from the other otherwise.
+    /// Groups from both are kept, preferring the priority correlation's member IDs. Properties come from the priority correlation alone.
+    pub fn merge(self, other: Correlation, strategy: MergeStrategy) -> Correlation {
+        let (primary, secondary) = match strategy {
+            MergeStrategy::SelfPriority => (self, other),
+            MergeStrategy::OtherPriority => (other, self),
+        };
+
+        let mut groups = secondary.groups;
+        for (name, member) in primary.groups {
+            if member.is_some() || !groups.contains_key(&name) {
+                groups.insert(name, member);
+            }
+        }
+
+        Correlation {
+            distinct_id: primary.distinct_id.or(secondary.distinct_id),
+            anon_distinct_id: primary.anon_distinct_id.or(secondary.anon_distinct_id),
+            session_id: primary.session_id.or(secondary.session_id),
+            window_id: primary.window_id.or(secondary.window_id),
+            device_id: primary.device_id.or(secondary.device_id),
+            groups,
+            properties: primary.properties,
+        }
+    }
+
+    /// The groups with a member ID, as `Groups`, ready to extend and pass to `Builder::groups`.
+    pub fn groups_as_hashmap(&self) -> crate::Groups {
         self.groups
             .clone()
             .into_iter()
             .filter_map(|(k, v)| Some((k, v?)))
             .collect()
     }
+
+    /// The groups with a member ID, as a JSON `Map` of strings, for use as event properties.
+    pub fn groups_as_map(&self) -> Map {
+        self.groups_as_hashmap()
+            .into_iter()
+            .map(|(k, v)| (k, v.into()))
+            .collect()
+    }
 }
 
+/// Identity and grouping data inherited from a parent process, like a GitHub Action.
+///
+/// To supply it with `Builder::with_correlation`, start from `Correlation::default()` and fill it in with the `with_*` methods.
 #[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
-pub(crate) struct Correlation {
+pub struct Correlation {
     pub(crate) distinct_id: Option<DistinctId>,
 
     #[serde(rename = "$anon_distinct_id")]
@@ -147,7 +260,9 @@ impl DetsysTsGitHubAction {
 
 #[cfg(test)]
 mod tests {
-    use crate::ds_correlation::{Correlation, CorrelationInputs, DetsysTsGitHubAction};
+    use crate::ds_correlation::{
+        Correlation, CorrelationInputs, DetsysTsGitHubAction, MergeStrategy,
+    };
 
     // In https://github.com/DeterminateSystems/detsys-ts/pull/104 we stopped doing the wacky transformations.
     // In that PR, we also changed the structure of the correlation we write to the identity.json to be more straightforward.
@@ -269,4 +384,147 @@ mod tests {
             })
         );
     }
+
+    fn correlation_with_groups() -> Correlation {
+        Correlation {
+            groups: std::collections::HashMap::from_iter([
+                ("ci".to_string(), Some("github-actions".to_string())),
+                ("project".to_string(), None),
+            ]),
+            ..Correlation::default()
+        }
+    }
+
+    #[test]
+    fn groups_as_hashmap_skips_missing_members() {
+        assert_eq!(
+            correlation_with_groups().groups_as_hashmap(),
+            crate::Groups::from_iter([("ci".to_string(), "github-actions".to_string())])
+        );
+        assert!(Correlation::default().groups_as_hashmap().is_empty());
+    }
+
+    #[test]
+    fn groups_as_map_skips_missing_members() {
+        assert_eq!(
+            serde_json::Value::Object(correlation_with_groups().groups_as_map()),
+            serde_json::json!({ "ci": "github-actions" })
+        );
+        assert!(Correlation::default().groups_as_map().is_empty());
+    }
+
+    fn env_correlation() -> Correlation {
+        Correlation {
+            distinct_id: Some(crate::DistinctId::from("env-distinct".to_string())),
+            anon_distinct_id: None,
+            session_id: Some("env-session".into()),
+            window_id: None,
+            device_id: Some(crate::DeviceId::from("env-device".to_string())),
+            groups: std::collections::HashMap::from_iter([
+                ("ci".to_string(), Some("github-actions".to_string())),
+                ("project".to_string(), None),
+                ("shared".to_string(), Some("from-env".to_string())),
+            ]),
+            properties: super::Map::from_iter([("source".to_string(), "env".into())]),
+        }
+    }
+
+    fn file_correlation() -> Correlation {
+        Correlation {
+            distinct_id: Some(crate::DistinctId::from("file-distinct".to_string())),
+            anon_distinct_id: Some("file-anon".into()),
+            session_id: None,
+            window_id: None,
+            device_id: Some(crate::DeviceId::from("file-device".to_string())),
+            groups: std::collections::HashMap::from_iter([
+                ("project".to_string(), Some("nix-installer".to_string())),
+                ("shared".to_string(), Some("from-file".to_string())),
+                ("org".to_string(), None),
+            ]),
+            properties: super::Map::from_iter([("source".to_string(), "file".into())]),
+        }
+    }
+
+    #[test]
+    fn merge_prefers_self() {
+        let merged = env_correlation().merge(file_correlation(), MergeStrategy::SelfPriority);
+
+        assert_eq!(
+            merged,
+            Correlation {
+                // Set by both
+                distinct_id: Some(crate::DistinctId::from("env-distinct".to_string())),
+                device_id: Some(crate::DeviceId::from("env-device".to_string())),
+                // Set by one
+                anon_distinct_id: Some("file-anon".into()),
+                session_id: Some("env-session".into()),
+                // Set by neither
+                window_id: None,
+                groups: std::collections::HashMap::from_iter([
+                    ("ci".to_string(), Some("github-actions".to_string())),
+                    ("project".to_string(), Some("nix-installer".to_string())),
+                    ("shared".to_string(), Some("from-env".to_string())),
+                    ("org".to_string(), None),
+                ]),
+                properties: super::Map::from_iter([("source".to_string(), "env".into())]),
+            }
+        );
+    }
+
+    #[test]
+    fn merge_prefers_other() {
+        let merged = env_correlation().merge(file_correlation(), MergeStrategy::OtherPriority);
+
+        assert_eq!(
+            merged,
+            Correlation {
+                distinct_id: Some(crate::DistinctId::from("file-distinct".to_string())),
+                device_id: Some(crate::DeviceId::from("file-device".to_string())),
+                anon_distinct_id: Some("file-anon".into()),
+                session_id: Some("env-session".into()),
+                window_id: None,
+                groups: std::collections::HashMap::from_iter([
+                    ("ci".to_string(), Some("github-actions".to_string())),
+                    ("project".to_string(), Some("nix-installer".to_string())),
+                    ("shared".to_string(), Some("from-file".to_string())),
+                    ("org".to_string(), None),
+                ]),
+                properties: super::Map::from_iter([("source".to_string(), "file".into())]),
+            }
+        );
+    }
+
+    #[test]
+    fn merge_with_an_empty_correlation() {
+        for strategy in [MergeStrategy::SelfPriority, MergeStrategy::OtherPriority] {
+            assert_eq!(
+                env_correlation().merge(Correlation::default(), strategy),
+                Correlation {
+                    properties: match strategy {
+                        MergeStrategy::SelfPriority => env_correlation().properties,
+                        MergeStrategy::OtherPriority => super::Map::new(),
+                    },
+                    ..env_correlation()
+                }
+            );
+            assert_eq!(
+                Correlation::default().merge(Correlation::default(), strategy),
+                Correlation::default()
+            );
+        }
+    }
+
+    #[test]
+    fn supplied_correlation_overrides_the_import() {
+        let imported = || file_correlation();
+
+        assert_eq!(
+            Correlation::supplied_or_imported(Some(env_correlation()), imported),
+            env_correlation().merge(file_correlation(), MergeStrategy::SelfPriority)
+        );
+        assert_eq!(
+            Correlation::supplied_or_imported(None, imported),
+            file_correlation()
+        );
+    }
 }
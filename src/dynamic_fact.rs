@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+/// A fact whose value is computed each time an event is recorded.
+#[derive(Clone)]
+pub(crate) struct DynamicFact(Arc<dyn Fn() -> serde_json::Value + Send + Sync>);
+
+impl DynamicFact {
+    pub(crate) fn new(f: impl Fn() -> serde_json::Value + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Compute the fact's value, turning a panic into `null`.
+    pub(crate) fn evaluate(&self, key: &str) -> serde_json::Value {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| (self.0)())).unwrap_or_else(|_| {
+            tracing::warn!(key, "Computing a dynamic fact panicked, using null");
+            serde_json::Value::Null
+        })
+    }
+}
+
+impl std::fmt::Debug for DynamicFact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicFact").finish()
+    }
+}
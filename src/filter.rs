@@ -0,0 +1,42 @@
+use crate::Map;
+
+/// Decide whether a recorded event is submitted.
+///
+/// Filters are checked by the Collator for every event recorded with [`crate::Recorder::record`] and friends.
+/// Identity events like `$identify` and `$create_alias` are never filtered.
+pub trait EventFilter: Send + Sync + 'static {
+    fn should_include(&self, event_name: &str, properties: Option<&Map>) -> bool;
+}
+
+/// Drop events whose name starts with any of the given prefixes.
+#[derive(Clone, Debug, Default)]
+pub struct PrefixFilter(pub Vec<String>);
+
+impl EventFilter for PrefixFilter {
+    fn should_include(&self, event_name: &str, _properties: Option<&Map>) -> bool {
+        !self
+            .0
+            .iter()
+            .any(|prefix| event_name.starts_with(prefix.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EventFilter, PrefixFilter};
+
+    #[test]
+    fn prefix_filter() {
+        let filter = PrefixFilter(vec!["debug.".into(), "trace_".into()]);
+
+        assert!(!filter.should_include("debug.cache_hit", None));
+        assert!(!filter.should_include("trace_step", None));
+        assert!(filter.should_include("build_started", None));
+        assert!(filter.should_include("my.debug.event", None));
+    }
+
+    #[test]
+    fn empty_prefix_filter_includes_everything() {
+        assert!(PrefixFilter::default().should_include("anything", None));
+    }
+}
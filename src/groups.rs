@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+/// The groups an event belongs to, as group names and member IDs. Example: `{"project": "nix-installer"}`
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Groups(HashMap<String, String>);
+
+impl Groups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a group's member ID, replacing any previous member.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.0.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set a group's member ID, unless the group already has one.
+    pub fn insert_if_absent(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.0.entry(key.into()).or_insert_with(|| value.into());
+        self
+    }
+
+    /// Add the groups from `other`, keeping this one's member ID for groups in both.
+    pub fn merge(&mut self, other: Groups) -> &mut Self {
+        for (key, value) in other {
+            self.0.entry(key).or_insert(value);
+        }
+        self
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn into_hashmap(self) -> HashMap<String, String> {
+        self.0
+    }
+}
+
+impl From<HashMap<String, String>> for Groups {
+    fn from(groups: HashMap<String, String>) -> Self {
+        Self(groups)
+    }
+}
+
+impl std::ops::Deref for Groups {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &HashMap<String, String> {
+        &self.0
+    }
+}
+
+impl FromIterator<(String, String)> for Groups {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self(HashMap::from_iter(iter))
+    }
+}
+
+/// Adds groups, replacing the member ID of groups already present.
+impl Extend<(String, String)> for Groups {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Groups {
+    type Item = (String, String);
+    type IntoIter = std::collections::hash_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Groups {
+    type Item = (&'a String, &'a String);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Groups;
+
+    fn groups(pairs: &[(&str, &str)]) -> Groups {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn insert_if_absent_keeps_existing_members() {
+        let mut groups = groups(&[("project", "nix-installer")]);
+        groups
+            .insert_if_absent("project", "flakehub")
+            .insert_if_absent("org", "acme");
+
+        assert_eq!(
+            groups,
+            self::groups(&[("project", "nix-installer"), ("org", "acme")])
+        );
+    }
+
+    #[test]
+    fn merge_prefers_self() {
+        let mut ours = groups(&[("project", "nix-installer"), ("ci", "github-actions")]);
+        ours.merge(groups(&[("project", "flakehub"), ("org", "acme")]));
+
+        assert_eq!(
+            ours,
+            groups(&[
+                ("project", "nix-installer"),
+                ("ci", "github-actions"),
+                ("org", "acme"),
+            ])
+        );
+    }
+
+    #[test]
+    fn remove_and_contains_key() {
+        let mut groups = groups(&[("project", "nix-installer")]);
+
+        assert!(groups.contains_key("project"));
+        assert_eq!(groups.remove("project"), Some("nix-installer".into()));
+        assert!(!groups.contains_key("project"));
+        assert_eq!(groups.remove("project"), None);
+    }
+
+    #[test]
+    fn serializes_as_a_plain_map() {
+        let groups = groups(&[("project", "nix-installer")]);
+        let json = serde_json::to_value(&groups).unwrap();
+
+        assert_eq!(json, serde_json::json!({ "project": "nix-installer" }));
+        assert_eq!(serde_json::from_value::<Groups>(json).unwrap(), groups);
+    }
+}
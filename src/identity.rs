@@ -53,6 +53,10 @@ impl DeviceId {
     pub fn new() -> DeviceId {
         DeviceId(format!("DIDS-DEV-{}", uuid::Uuid::now_v7()))
     }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl std::fmt::Display for DeviceId {
@@ -66,3 +70,12 @@ impl From<String> for DeviceId {
         Self(value)
     }
 }
+
+/// A change of the distinct ID attached to events, from identifying or resetting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DistinctIdChange {
+    /// The previously identified distinct ID, or None if the previous events were anonymous.
+    pub from: Option<String>,
+    /// The distinct ID attached to events from now on, which is the anonymous distinct ID after a reset.
+    pub to: String,
+}
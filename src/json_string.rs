@@ -3,15 +3,22 @@
 use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
 use serde::ser::{self, Serialize, Serializer};
 
+/// Accepts a string containing JSON, or plain JSON.
+/// A string which doesn't contain JSON is taken as-is.
 pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: DeserializeOwned,
     D: Deserializer<'de>,
 {
-    let j = String::deserialize(deserializer)?;
-    serde_json::from_str(&j).map_err(de::Error::custom)
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(j) => serde_json::from_str(&j)
+            .or_else(|_| serde_json::from_value(serde_json::Value::String(j)))
+            .map_err(de::Error::custom),
+        value => serde_json::from_value(value).map_err(de::Error::custom),
+    }
 }
 
+/// Always writes a string containing JSON, which every release can read back.
 pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
 where
     T: Serialize,
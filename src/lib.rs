@@ -1,50 +1,112 @@
 mod builder;
 pub mod checkin;
+mod coalesce;
 mod collator;
 mod compression_set;
 mod configuration_proxy;
 mod ds_correlation;
+mod dynamic_fact;
+mod filter;
+mod groups;
 mod identity;
 mod json_string;
+mod person_properties;
+mod process_stats;
 mod recorder;
 pub mod storage;
 mod submitter;
 pub mod system_snapshot;
+mod timed_event;
+mod transformer;
 pub mod transport;
+mod variant_match;
 mod worker;
 
 #[cfg(test)]
 mod test;
 
-use std::collections::HashMap;
-
 pub use builder::Builder;
-pub use identity::{AnonymousDistinctId, DeviceId, DistinctId};
-pub use recorder::{IdentifyProperties, Recorder};
-pub use worker::Worker;
+pub use coalesce::{CoalesceOptions, EventNameFilter};
+pub use configuration_proxy::{CheckinSource, CheckinStatusReport, CheckinSummary};
+pub use ds_correlation::{Correlation, MergeStrategy};
+pub use filter::{EventFilter, PrefixFilter};
+pub use groups::Groups;
+pub use identity::{AnonymousDistinctId, DeviceId, DistinctId, DistinctIdChange};
+pub use person_properties::PersonProperties;
+pub use recorder::{IdentifyProperties, Recorder, ResetOptions, ResolvedFeature};
+pub use submitter::SubmitterOverflowStrategy;
+pub use system_snapshot::HostNamePolicy;
+pub use timed_event::TimedEventHandle;
+pub use transformer::{EventTransformer, PiiScrubber};
+pub use variant_match::VariantMatch;
+pub use worker::{ShutdownHandle, Worker};
 
 pub type Map = serde_json::Map<String, serde_json::Value>;
-pub type Groups = HashMap<String, String>;
-pub type PersonProperties = Map;
 
+/// A Builder describing the calling crate.
+///
+/// `builder!(env_var = "MYAPP_TELEMETRY")` lets an application-specific variable enable or disable telemetry, falling back to `DETSYS_IDS_TELEMETRY`.
+/// `builder!(groups = { "project" => env!("CARGO_PKG_NAME") })` sets groups for every event, and can follow `env_var = ...,`.
 #[macro_export]
 macro_rules! builder {
-    () => {{
-        let builder = detsys_ids_client::Builder::new()
+    () => {{ $crate::builder!(@telemetry $crate::is_telemetry_enabled()) }};
+    (env_var = $env_var:expr) => {{
+        $crate::builder!(@telemetry $crate::is_telemetry_enabled_for_either(
+            $env_var,
+            "DETSYS_IDS_TELEMETRY"
+        ))
+    }};
+    (groups = { $($name:expr => $member:expr),* $(,)? }) => {{
+        $crate::builder!().groups(Some($crate::builder!(@groups $($name => $member),*)))
+    }};
+    (env_var = $env_var:expr, groups = { $($name:expr => $member:expr),* $(,)? }) => {{
+        $crate::builder!(env_var = $env_var)
+            .groups(Some($crate::builder!(@groups $($name => $member),*)))
+    }};
+    (@groups $($name:expr => $member:expr),*) => {
+        $crate::Groups::from_iter([$((::std::string::String::from($name), ::std::string::String::from($member))),*])
+    };
+    (@telemetry $enabled:expr) => {{
+        let builder = $crate::Builder::new()
             .fact("cargo_pkg_name", env!("CARGO_PKG_NAME"))
             .fact("$app_version", env!("CARGO_PKG_VERSION"))
             .fact("$app_name", env!("CARGO_CRATE_NAME"))
-            .enable_reporting(detsys_ids_client::is_telemetry_enabled())
-            .endpoint(detsys_ids_client::get_ambient_transport_endpoint());
+            .enable_reporting($enabled)
+            .endpoint($crate::get_ambient_transport_endpoint());
 
         builder
     }};
 }
 
 pub fn is_telemetry_enabled() -> bool {
-    let enabled = std::env::var_os("DETSYS_IDS_TELEMETRY").as_deref()
-        != Some(std::ffi::OsStr::new("disabled"));
+    is_telemetry_enabled_for("DETSYS_IDS_TELEMETRY")
+}
 
+/// Whether telemetry is enabled, which it is unless `env_var` is set to `disabled`.
+pub fn is_telemetry_enabled_for(env_var: &str) -> bool {
+    note_if_disabled(telemetry_enabled(|name| std::env::var_os(name), &[env_var]))
+}
+
+/// Like [`is_telemetry_enabled_for`], but `primary` decides when it is set, and `fallback` decides otherwise.
+pub fn is_telemetry_enabled_for_either(primary: &str, fallback: &str) -> bool {
+    note_if_disabled(telemetry_enabled(
+        |name| std::env::var_os(name),
+        &[primary, fallback],
+    ))
+}
+
+/// Whether telemetry is enabled, which it is unless the first of `env_vars` set in `env` is `disabled`.
+pub(crate) fn telemetry_enabled(
+    env: impl Fn(&str) -> Option<std::ffi::OsString>,
+    env_vars: &[&str],
+) -> bool {
+    env_vars
+        .iter()
+        .find_map(|name| env(name))
+        .is_none_or(|value| value != "disabled")
+}
+
+fn note_if_disabled(enabled: bool) -> bool {
     if !enabled {
         eprintln!(
             "{}",
@@ -63,3 +125,24 @@ pub fn is_telemetry_enabled() -> bool {
 pub fn get_ambient_transport_endpoint() -> Option<String> {
     std::env::var("DETSYS_IDS_TRANSPORT").ok()
 }
+
+pub fn get_ambient_storage_path() -> Option<std::path::PathBuf> {
+    ambient_storage_path(|name| std::env::var_os(name))
+}
+
+/// The storage path in `DETSYS_IDS_STORAGE_PATH`, looked up with `var`, if it is absolute.
+pub(crate) fn ambient_storage_path(
+    var: impl Fn(&str) -> Option<std::ffi::OsString>,
+) -> Option<std::path::PathBuf> {
+    let path = std::path::PathBuf::from(var("DETSYS_IDS_STORAGE_PATH")?);
+
+    if !path.is_absolute() {
+        tracing::warn!(
+            ?path,
+            "Ignoring DETSYS_IDS_STORAGE_PATH because it is not an absolute path"
+        );
+        return None;
+    }
+
+    Some(path)
+}
@@ -0,0 +1,142 @@
+use crate::Map;
+
+/// Properties of the person behind a distinct ID, set with [`crate::Recorder::identify_with_properties`].
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct PersonProperties(Map);
+
+impl PersonProperties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_map(map: Map) -> Self {
+        Self(map)
+    }
+
+    /// Person properties with just an `email`.
+    pub fn email(email: impl Into<String>) -> Self {
+        let mut properties = Self::new();
+        properties.insert("email", email.into());
+        properties
+    }
+
+    /// Person properties with just a `name`.
+    pub fn name(name: impl Into<String>) -> Self {
+        let mut properties = Self::new();
+        properties.insert("name", name.into());
+        properties
+    }
+
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> &mut Self {
+        self.0.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn into_map(self) -> Map {
+        self.0
+    }
+}
+
+impl From<Map> for PersonProperties {
+    fn from(map: Map) -> Self {
+        Self::from_map(map)
+    }
+}
+
+impl std::ops::Deref for PersonProperties {
+    type Target = Map;
+
+    fn deref(&self) -> &Map {
+        &self.0
+    }
+}
+
+impl IntoIterator for PersonProperties {
+    type Item = (String, serde_json::Value);
+    type IntoIter = serde_json::map::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PersonProperties {
+    type Item = (&'a String, &'a serde_json::Value);
+    type IntoIter = serde_json::map::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PersonProperties;
+    use crate::IdentifyProperties;
+
+    #[test]
+    fn constructors() {
+        assert_eq!(
+            PersonProperties::email("hi@example.com").get("email"),
+            Some(&"hi@example.com".into())
+        );
+        assert_eq!(
+            PersonProperties::name("Alice").get("name"),
+            Some(&"Alice".into())
+        );
+
+        let mut properties = PersonProperties::email("hi@example.com");
+        properties.insert("plan", "pro").insert("seats", 3);
+
+        assert_eq!(properties.len(), 3);
+        assert_eq!(
+            properties
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            ["email", "plan", "seats"]
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_plain_map() {
+        let properties = IdentifyProperties {
+            set: PersonProperties::name("Alice"),
+            set_once: PersonProperties::from_map(crate::Map::from_iter([(
+                "first_seen".to_string(),
+                "2024-01-01".into(),
+            )])),
+            unset: vec![],
+        };
+
+        assert_eq!(
+            serde_json::Value::Object(properties.as_map()),
+            serde_json::json!({
+                "$set": { "name": "Alice" },
+                "$set_once": { "first_seen": "2024-01-01" },
+            })
+        );
+    }
+
+    #[test]
+    fn unset_serializes_as_a_list_of_names() {
+        let properties = IdentifyProperties {
+            unset: vec!["email".into(), "plan".into()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            serde_json::Value::Object(properties.as_map()),
+            serde_json::json!({
+                "$set": {},
+                "$set_once": {},
+                "$unset": ["email", "plan"],
+            })
+        );
+    }
+}
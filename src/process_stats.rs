@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// How long a sample of the process's resource usage is reused before reading it again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The process's resource usage, added to each event when `Builder::capture_process_stats` is on.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub(crate) struct ProcessStats {
+    /// Example: `24576000`
+    pub(crate) process_rss_bytes: u64,
+
+    /// User and system CPU time used so far. Example: `1.25`
+    pub(crate) process_cpu_seconds: f64,
+
+    /// Not reported where sysinfo can't count them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) open_fd_count: Option<u64>,
+}
+
+/// Reads this process's resource usage, at most once per `REFRESH_INTERVAL`.
+pub(crate) struct ProcessStatsSampler {
+    pid: Pid,
+    state: Mutex<SamplerState>,
+}
+
+struct SamplerState {
+    system: System,
+    last: Option<(Instant, ProcessStats)>,
+}
+
+impl ProcessStatsSampler {
+    /// None if the current process's ID can't be found.
+    pub(crate) fn new() -> Option<Self> {
+        let pid = match sysinfo::get_current_pid() {
+            Ok(pid) => pid,
+            Err(e) => {
+                tracing::debug!(e, "Not capturing process stats without the current PID");
+                return None;
+            }
+        };
+
+        Some(Self {
+            pid,
+            state: Mutex::new(SamplerState {
+                system: System::new(),
+                last: None,
+            }),
+        })
+    }
+
+    /// The latest sample, read again if it's older than `REFRESH_INTERVAL`.
+    pub(crate) fn current(&self) -> Option<ProcessStats> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some((taken_at, stats)) = &state.last
+            && taken_at.elapsed() < REFRESH_INTERVAL
+        {
+            return Some(stats.clone());
+        }
+
+        state.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[self.pid]),
+            false,
+            ProcessRefreshKind::nothing().with_memory().with_cpu(),
+        );
+
+        let process = state.system.process(self.pid)?;
+        let stats = ProcessStats {
+            process_rss_bytes: process.memory(),
+            process_cpu_seconds: process.accumulated_cpu_time() as f64 / 1000.0,
+            open_fd_count: process.open_files().map(|count| count as u64),
+        };
+
+        state.last = Some((Instant::now(), stats.clone()));
+
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProcessStatsSampler;
+
+    #[test]
+    fn samples_this_process() {
+        let sampler = ProcessStatsSampler::new().unwrap();
+        let stats = sampler.current().unwrap();
+
+        assert!(stats.process_rss_bytes > 0);
+        assert!(stats.process_cpu_seconds >= 0.0);
+
+        #[cfg(target_os = "linux")]
+        assert!(stats.open_fd_count.unwrap() > 0);
+    }
+
+    #[test]
+    fn samples_are_reused_within_the_refresh_interval() {
+        let sampler = ProcessStatsSampler::new().unwrap();
+        let first = sampler.current().unwrap();
+
+        // Burn some CPU time, which would show up if the sample were read again
+        let busy = (0..5_000_000u64).fold(0u64, |acc, n| acc.wrapping_add(n * n));
+        assert_ne!(std::hint::black_box(busy), 1);
+
+        assert_eq!(sampler.current().unwrap(), first);
+    }
+}
@@ -4,9 +4,18 @@ use tracing::Instrument;
 
 use crate::checkin::{Checkin, Feature};
 use crate::collator::FeatureFacts;
-use crate::configuration_proxy::{CheckinStatus, ConfigurationProxySignal};
-use crate::identity::DistinctId;
-use crate::{Map, PersonProperties};
+use crate::configuration_proxy::{
+    CheckinStatus, CheckinStatusReport, CheckinSummary, ConfigurationProxySignal, ServedFeature,
+};
+use crate::dynamic_fact::DynamicFact;
+use crate::identity::{DistinctId, DistinctIdChange};
+use crate::storage::{SchemaError, StoredProperties};
+use crate::timed_event::TimedEventHandle;
+use crate::variant_match::VariantMatch;
+use crate::{Groups, Map, PersonProperties};
+
+/// How many pointers `get_feature_ptr` follows before giving up.
+pub(crate) const DEFAULT_FEATURE_POINTER_DEPTH: usize = 5;
 
 #[derive(Debug)]
 pub(crate) enum RawSignal {
@@ -14,23 +23,56 @@ pub(crate) enum RawSignal {
         key: String,
         value: serde_json::Value,
     },
+    DynamicFact {
+        key: String,
+        fact: DynamicFact,
+    },
     UpdateFeatureConfiguration(Option<Checkin>, FeatureFacts),
     Event {
         event_name: String,
         properties: Option<Map>,
+        groups: Option<Groups>,
+    },
+    PriorityEvent {
+        event_name: String,
+        properties: Option<Map>,
+    },
+    EventBatch(Vec<(String, Option<Map>)>),
+    EventWithKey {
+        event_name: String,
+        properties: Option<Map>,
+        key: String,
     },
     GetSessionProperties {
         tx: tokio::sync::oneshot::Sender<Map>,
     },
+    GetSessionIds {
+        tx: tokio::sync::oneshot::Sender<SessionIds>,
+    },
+    ExportIdentity {
+        tx: tokio::sync::oneshot::Sender<StoredProperties>,
+    },
+    ImportIdentity(Box<StoredProperties>),
+    SubscribeToIdentityChanges(
+        tokio::sync::oneshot::Sender<tokio::sync::broadcast::Receiver<DistinctIdChange>>,
+    ),
     FlushNow,
     Identify(DistinctId, IdentifyProperties),
     SetPersonProperties(IdentifyProperties),
+    UnsetPersonProperty(String),
     AddGroup {
         group_name: String,
         group_member_id: String,
     },
     Alias(String),
-    Reset,
+    Reset(ResetOptions),
+}
+
+/// The IDs attached to every event, read without snapshotting the system.
+#[derive(Debug)]
+pub(crate) struct SessionIds {
+    pub(crate) session_id: String,
+    pub(crate) distinct_id: String,
 }
 
 #[derive(Default, Debug, serde::Serialize)]
@@ -39,6 +81,9 @@ pub struct IdentifyProperties {
     pub set: PersonProperties,
     #[serde(rename = "$set_once")]
     pub set_once: PersonProperties,
+    /// Names of person properties to remove.
+    #[serde(rename = "$unset", skip_serializing_if = "Vec::is_empty")]
+    pub unset: Vec<String>,
 }
 
 impl IdentifyProperties {
@@ -64,6 +109,35 @@ impl IdentifyProperties {
     }
 }
 
+/// A feature reached by following pointer payloads, from [`Recorder::get_feature_resolved`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedFeature<T: serde::ser::Serialize + serde::de::DeserializeOwned> {
+    pub feature: Feature<T>,
+    /// Every key followed, from the requested feature to the resolved one.
+    pub chain: Vec<String>,
+}
+
+/// Select which state is cleared by [`Recorder::reset_with`], in addition to the distinct ID and anonymous distinct ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResetOptions {
+    /// Clear all groups. Enabled by default.
+    pub groups: bool,
+    /// Drop facts set with [`Recorder::set_fact`], reverting to the facts the Recorder was built with.
+    pub facts: bool,
+    /// Generate a new device ID.
+    pub device_id: bool,
+}
+
+impl Default for ResetOptions {
+    fn default() -> Self {
+        Self {
+            groups: true,
+            facts: false,
+            device_id: false,
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum RecorderError {
     #[error("Timed out waiting for configuration to complete: {0:?}")]
@@ -80,12 +154,20 @@ pub enum RecorderError {
 
     #[error(transparent)]
     Response(#[from] tokio::sync::oneshot::error::RecvError),
+
+    #[error("The identity could not be imported: {0}")]
+    ImportIdentity(#[from] SchemaError),
+
+    #[error("Refreshing the configuration failed: {0}")]
+    RefreshConfiguration(String),
 }
 
 pub struct Recorder {
     outgoing: Sender<RawSignal>,
     auto_refresh_config: bool,
+    feature_pointer_depth: usize,
     to_configuration_proxy: Sender<ConfigurationProxySignal>,
+    prefix: Option<String>,
 }
 
 impl Clone for Recorder {
@@ -93,14 +175,18 @@ impl Clone for Recorder {
         Self {
             outgoing: self.outgoing.clone(),
             auto_refresh_config: true,
+            feature_pointer_depth: self.feature_pointer_depth,
             to_configuration_proxy: self.to_configuration_proxy.clone(),
+            prefix: self.prefix.clone(),
         }
     }
 }
 
 impl std::fmt::Debug for Recorder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Recorder").finish()
+        f.debug_struct("Recorder")
+            .field("prefix", &self.prefix)
+            .finish()
     }
 }
 
@@ -109,14 +195,53 @@ impl Recorder {
     pub(crate) fn new(
         snapshotter_tx: Sender<RawSignal>,
         to_configuration_proxy: Sender<ConfigurationProxySignal>,
+        feature_pointer_depth: usize,
     ) -> Self {
         Self {
             outgoing: snapshotter_tx,
             to_configuration_proxy,
             auto_refresh_config: true,
+            feature_pointer_depth,
+            prefix: None,
         }
     }
 
+    /// A Recorder which records events named `{prefix}.{event_name}`, for namespacing a subsystem's events.
+    /// Everything else, like facts and identity, is shared with this Recorder.
+    pub fn child_recorder(&self, prefix: impl Into<String>) -> Recorder {
+        let prefix = prefix.into();
+
+        Recorder {
+            prefix: Some(match self.prefix {
+                Some(ref parent) => format!("{parent}.{prefix}"),
+                None => prefix,
+            }),
+            ..self.clone()
+        }
+    }
+
+    fn prefixed(&self, event_name: String) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}.{event_name}"),
+            None => event_name,
+        }
+    }
+
+    /// Whether the Worker has shut down, which means recorded events are being dropped.
+    pub fn is_closed(&self) -> bool {
+        self.outgoing.is_closed()
+    }
+
+    /// The number of messages which can be enqueued before recording waits on the Worker.
+    pub fn outgoing_capacity(&self) -> usize {
+        self.outgoing.capacity()
+    }
+
+    /// The number of messages enqueued and not yet processed by the Worker.
+    pub fn outgoing_len(&self) -> usize {
+        self.outgoing.max_capacity() - self.outgoing.capacity()
+    }
+
     // Execute a series of operations without triggering multiple configuration refreshes.
     // Note: there are no atomic semantics, and configuration is refreshed at the end no matter what your function does.
     pub async fn in_configuration_txn<F, T>(&self, f: F) -> T
@@ -147,6 +272,83 @@ impl Recorder {
             .ok()
     }
 
+    /// The variant of a feature flag used as an A/B test, deserialized into `T`.
+    /// Besides `$feature_flag_called`, records each exposure as an `$experiment_exposure` event, unless the variant is a default given to the Builder.
+    #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
+    pub async fn get_experiment_variant<
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send,
+    >(
+        &self,
+        experiment_key: impl Into<String> + std::fmt::Debug,
+    ) -> Option<T> {
+        let key: String = experiment_key.into();
+
+        let served = self.fetch_feature(key.clone()).await?;
+        self.record_feature_flag_called(key.clone(), &served, [])
+            .await;
+
+        let variant: T = serde_json::from_value(served.feature.variant.clone())
+            .inspect_err(|e| tracing::debug!(%e, "Deserializing experiment variant failed"))
+            .ok()?;
+
+        if !served.is_default {
+            let mut properties = Map::from_iter([
+                ("experiment".into(), key.into()),
+                ("variant".into(), served.feature.variant.clone()),
+            ]);
+            if let Some(experiment_id) = &served.feature.experiment_id {
+                properties.insert("$experiment_id".into(), experiment_id.clone().into());
+            }
+
+            self.send_event("$experiment_exposure".into(), Some(properties), None)
+                .await;
+        }
+
+        Some(variant)
+    }
+
+    /// The variant of a multivariate flag, deserialized into `T`, or None if the flag is unset or the variant is unknown.
+    #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
+    pub async fn get_feature_variant_enum<
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send,
+    >(
+        &self,
+        key: impl Into<String> + std::fmt::Debug,
+    ) -> Option<T> {
+        self.match_feature_variant(key).await.matched()
+    }
+
+    /// Match a multivariate flag's variant against `T`.
+    /// The matched arm is recorded as `$feature_flag_variant_matched` on `$feature_flag_called`, or null if the variant is unknown.
+    #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
+    pub async fn match_feature_variant<
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send,
+    >(
+        &self,
+        key: impl Into<String> + std::fmt::Debug,
+    ) -> VariantMatch<T> {
+        let key: String = key.into();
+
+        let Some(served) = self.fetch_feature(key.clone()).await else {
+            return VariantMatch::Missing;
+        };
+
+        let matched = VariantMatch::from_variant(&key, Some(&served.feature.variant));
+        let matched_arm = match &matched {
+            VariantMatch::Matched(arm) => serde_json::to_value(arm).unwrap_or_default(),
+            VariantMatch::Unknown(_) | VariantMatch::Missing => serde_json::Value::Null,
+        };
+
+        self.record_feature_flag_called(
+            key,
+            &served,
+            [("$feature_flag_variant_matched".into(), matched_arm)],
+        )
+        .await;
+
+        matched
+    }
+
     #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
     pub async fn get_feature_ptr_variant<
         T: serde::ser::Serialize + serde::de::DeserializeOwned + Send + std::fmt::Debug,
@@ -179,6 +381,7 @@ impl Recorder {
         self.get_feature_ptr::<T>(key).await?.payload
     }
 
+    /// The feature named by `key`'s payload, following further pointers like [`Recorder::get_feature_resolved`].
     #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
     pub async fn get_feature_ptr<
         T: serde::ser::Serialize + serde::de::DeserializeOwned + Send + std::fmt::Debug,
@@ -186,8 +389,86 @@ impl Recorder {
         &self,
         key: impl Into<String> + std::fmt::Debug,
     ) -> Option<Feature<T>> {
-        let ptr = self.get_feature_payload::<String>(key).await?;
-        self.get_feature::<T>(ptr).await
+        Some(self.get_feature_resolved::<T>(key).await?.feature)
+    }
+
+    /// Follow `key`'s payload to the feature it names, and onward while each payload names another feature.
+    ///
+    /// Returns None if `key`'s payload doesn't name a feature, the pointers form a cycle,
+    /// or there are more than `Builder::feature_pointer_depth` of them.
+    /// Only a resolved feature is recorded as called, with the chain in `$feature_flag_pointer_chain`.
+    #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
+    pub async fn get_feature_resolved<
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + Send + std::fmt::Debug,
+    >(
+        &self,
+        key: impl Into<String> + std::fmt::Debug,
+    ) -> Option<ResolvedFeature<T>> {
+        let key: String = key.into();
+
+        let pointer = self.fetch_feature(key.clone()).await?;
+
+        let mut chain = vec![key];
+        let mut current = pointer;
+
+        while let Some(next) = current
+            .feature
+            .payload
+            .as_ref()
+            .and_then(serde_json::Value::as_str)
+            .map(String::from)
+        {
+            if chain.contains(&next) {
+                chain.push(next);
+                tracing::debug!(cycle = chain.join(" -> "), "Feature pointers form a cycle");
+                return None;
+            }
+
+            let Some(target) = self.fetch_feature(next.clone()).await else {
+                // A string payload which isn't a feature name ends the chain
+                break;
+            };
+
+            if chain.len() > self.feature_pointer_depth {
+                tracing::debug!(
+                    chain = chain.join(" -> "),
+                    max_depth = self.feature_pointer_depth,
+                    "Feature pointers go deeper than allowed"
+                );
+                return None;
+            }
+
+            chain.push(next);
+            current = target;
+        }
+
+        if chain.len() == 1 {
+            tracing::debug!(
+                key = chain[0],
+                "Feature payload doesn't name another feature"
+            );
+            return None;
+        }
+
+        let feature = Feature {
+            variant: current.feature.variant.clone(),
+            payload: match current.feature.payload {
+                Some(ref p) => Some(serde_json::from_value(p.clone()).ok()?),
+                None => None,
+            },
+            experiment_id: current.feature.experiment_id.clone(),
+        };
+
+        // Only the feature which was resolved counts as called, not the pointers to it
+        let resolved = chain.last().cloned().unwrap_or_default();
+        self.record_feature_flag_called(
+            resolved,
+            &current,
+            [("$feature_flag_pointer_chain".into(), chain.clone().into())],
+        )
+        .await;
+
+        Some(ResolvedFeature { feature, chain })
     }
 
     pub async fn wait_for_checkin(
@@ -215,13 +496,59 @@ impl Recorder {
             return Err(RecorderError::SubscribeFailed);
         };
 
-        if let Some(duration) = duration {
-            Ok(tokio::time::timeout(duration, subscription.recv()).await??)
+        let received = if let Some(duration) = duration {
+            tokio::time::timeout(duration, subscription.recv()).await?
         } else {
-            Ok(subscription.recv().await?)
+            subscription.recv().await
+        };
+
+        match received {
+            // Missing some notifications still means the configuration changed
+            Ok(()) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => Ok(()),
+            Err(e) => Err(e.into()),
         }
     }
 
+    /// When the configuration was last fetched, where it came from, and whether the latest check-in failed.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn checkin_status(&self) -> CheckinStatusReport {
+        let (tx, rx) = oneshot();
+
+        if let Err(e) = self
+            .to_configuration_proxy
+            .send(ConfigurationProxySignal::QueryCheckinStatus(tx))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue QueryCheckinStatus message");
+        }
+
+        rx.await
+            .inspect_err(|e| tracing::debug!(%e, "Failed to get the check-in status"))
+            .unwrap_or_default()
+    }
+
+    /// Check in now, even if automatic configuration refreshes are paused, and report whether it worked.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn refresh_configuration(&self) -> Result<CheckinSummary, RecorderError> {
+        let (tx, rx) = oneshot();
+
+        let session_properties = self
+            .get_session_properties()
+            .await
+            .inspect_err(|e| tracing::debug!(%e, "Failed to get session properties"))
+            .unwrap_or_default();
+
+        self.to_configuration_proxy
+            .send(ConfigurationProxySignal::RefreshConfiguration(
+                session_properties,
+                tx,
+            ))
+            .await
+            .map_err(|e| RecorderError::SendToConfigurationProxy(format!("{e:?}")))?;
+
+        rx.await?.map_err(RecorderError::RefreshConfiguration)
+    }
+
     #[tracing::instrument(skip(self), ret(level = tracing::Level::TRACE))]
     pub async fn get_feature<
         T: serde::ser::Serialize + serde::de::DeserializeOwned + Send + std::fmt::Debug,
@@ -230,10 +557,32 @@ impl Recorder {
         key: impl Into<String> + std::fmt::Debug,
     ) -> Option<Feature<T>> {
         let key: String = key.into();
+
+        let served = self.fetch_feature(key.clone()).await?;
+
+        self.record_feature_flag_called(key, &served, []).await;
+
+        let feature = served.feature;
+        let variant = feature.variant.clone();
+        let payload = if let Some(ref p) = feature.payload {
+            let ret = serde_json::from_value(p.clone()).ok()?;
+            Some(ret)
+        } else {
+            None
+        };
+
+        Some(Feature {
+            variant,
+            payload,
+            experiment_id: feature.experiment_id.clone(),
+        })
+    }
+
+    async fn fetch_feature(&self, key: String) -> Option<ServedFeature> {
         let (tx, rx) = oneshot();
 
         self.to_configuration_proxy
-            .send(ConfigurationProxySignal::GetFeature(key.clone(), tx))
+            .send(ConfigurationProxySignal::GetFeature(key, tx))
             .instrument(tracing::trace_span!(
                 "requesting feature from the configuration proxy"
             ))
@@ -241,33 +590,64 @@ impl Recorder {
             .inspect_err(|e| tracing::trace!(%e, "Error sending the feature flag request"))
             .ok()?;
 
-        let feature = rx
-            .instrument(tracing::trace_span!("waiting for the feature"))
+        rx.instrument(tracing::trace_span!("waiting for the feature"))
             .await
             .inspect_err(|e| tracing::trace!(%e, "Error requesting the feature flag"))
             .ok()
-            .flatten()?;
-
-        self.record(
-            "$feature_flag_called",
-            Some(Map::from_iter([
-                ("$feature_flag".into(), key.into()),
-                ("$feature_flag_response".into(), feature.variant.clone()),
-            ])),
-        )
-        .await;
+            .flatten()
+    }
 
-        let variant = feature.variant.clone();
-        let payload = if let Some(ref p) = feature.payload {
-            let ret = serde_json::from_value(p.clone()).ok()?;
-            Some(ret)
-        } else {
-            None
-        };
+    async fn record_feature_flag_called(
+        &self,
+        key: String,
+        served: &ServedFeature,
+        extra_properties: impl IntoIterator<Item = (String, serde_json::Value)>,
+    ) {
+        let mut properties = Map::from_iter([
+            ("$feature_flag".into(), key.into()),
+            (
+                "$feature_flag_response".into(),
+                served.feature.variant.clone(),
+            ),
+        ]);
+        if served.is_default {
+            properties.insert("$default".into(), true.into());
+        }
+        if let Some(experiment_id) = &served.feature.experiment_id {
+            properties.insert("$experiment_id".into(), experiment_id.clone().into());
+        }
+        properties.extend(extra_properties);
 
-        Some(Feature { variant, payload })
+        self.send_event("$feature_flag_called".into(), Some(properties), None)
+            .await;
     }
 
+    /// Receive a DistinctIdChange whenever `identify` switches to a different distinct ID, or `reset` is called.
+    pub async fn subscribe_to_identity_changes(
+        &self,
+    ) -> Option<tokio::sync::broadcast::Receiver<DistinctIdChange>> {
+        let (tx, rx) = oneshot();
+
+        self.outgoing
+            .send(RawSignal::SubscribeToIdentityChanges(tx))
+            .instrument(tracing::debug_span!("subscribe to identity changes"))
+            .await
+            .inspect_err(|e| {
+                tracing::error!(error = ?e, "Failed to request subscription to identity changes");
+            })
+            .ok()?;
+
+        rx.await
+            .inspect_err(|e| {
+                tracing::error!(error = ?e, "No response when waiting for an identity change subscriber");
+            })
+            .ok()
+    }
+
+    /// Receive a notification each time the feature configuration changes.
+    ///
+    /// A subscriber which falls too far behind receives `RecvError::Lagged` instead of the notifications it missed.
+    /// That still means the configuration changed, so read the features it cares about again to catch up.
     pub async fn subscribe_to_feature_changes(
         &self,
     ) -> Option<tokio::sync::broadcast::Receiver<()>> {
@@ -307,17 +687,130 @@ impl Recorder {
         }
     }
 
+    /// Set a fact whose value is computed by `f` each time an event is recorded.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self, f)))]
+    pub async fn set_dynamic_fact(
+        &self,
+        key: impl Into<String> + std::fmt::Debug,
+        f: impl Fn() -> serde_json::Value + Send + Sync + 'static,
+    ) {
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::DynamicFact {
+                key: key.into(),
+                fact: DynamicFact::new(f),
+            })
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue a dynamic fact");
+        }
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     pub async fn record(
         &self,
         event: impl Into<String> + std::fmt::Debug,
         properties: Option<Map>,
+    ) {
+        self.send_event(self.prefixed(event.into()), properties, None)
+            .await;
+    }
+
+    /// Record an event with groups which take precedence over the session's groups for this event only.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn record_with_groups(
+        &self,
+        event: impl Into<String> + std::fmt::Debug,
+        properties: Option<Map>,
+        groups: Groups,
+    ) {
+        self.send_event(self.prefixed(event.into()), properties, Some(groups))
+            .await;
+    }
+
+    /// Record an event which is submitted as soon as possible, along with any events waiting to be submitted, instead of at the next flush interval.
+    /// Use it sparingly, for events like errors and crash reports which might otherwise be lost.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn record_priority(
+        &self,
+        event: impl Into<String> + std::fmt::Debug,
+        properties: Option<Map>,
+    ) {
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::PriorityEvent {
+                event_name: self.prefixed(event.into()),
+                properties,
+            })
+            .instrument(tracing::trace_span!("recording the priority event"))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue a priority event message");
+        }
+    }
+
+    /// Start timing an event, which is recorded with its duration when the handle is finished.
+    pub fn start_timed_event(&self, event_name: impl Into<String>) -> TimedEventHandle {
+        TimedEventHandle::new(event_name.into())
+    }
+
+    /// Record an event with an `$idempotency_key` property, so the server can discard duplicate submissions of it.
+    /// The key is any caller-supplied string which identifies the occurrence, like a job ID.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn record_with_idempotency_key(
+        &self,
+        event: impl Into<String> + std::fmt::Debug,
+        properties: Option<Map>,
+        key: impl Into<String> + std::fmt::Debug,
+    ) {
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::EventWithKey {
+                event_name: self.prefixed(event.into()),
+                properties,
+                key: key.into(),
+            })
+            .instrument(tracing::trace_span!("recording the event"))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue an event message");
+        }
+    }
+
+    /// Record several events in order, without any other recorded activity interleaved between them.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn record_batch(&self, events: Vec<(String, Option<Map>)>) {
+        if events.is_empty() {
+            return;
+        }
+
+        let events = events
+            .into_iter()
+            .map(|(event_name, properties)| (self.prefixed(event_name), properties))
+            .collect();
+
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::EventBatch(events))
+            .instrument(tracing::trace_span!("recording the event batch"))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue an event batch message");
+        }
+    }
+
+    async fn send_event(
+        &self,
+        event_name: String,
+        properties: Option<Map>,
+        groups: Option<Groups>,
     ) {
         if let Err(e) = self
             .outgoing
             .send(RawSignal::Event {
-                event_name: event.into(),
+                event_name,
                 properties,
+                groups,
             })
             .instrument(tracing::trace_span!("recording the event"))
             .await
@@ -366,6 +859,59 @@ impl Recorder {
             .await;
     }
 
+    /// Set one person property, like `set_person_properties` with a single `$set` entry.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self, value)))]
+    pub async fn set_person_property(
+        &self,
+        key: impl Into<String> + std::fmt::Debug,
+        value: impl Into<serde_json::Value>,
+    ) {
+        let mut set = PersonProperties::new();
+        set.insert(key, value);
+
+        self.set_person_properties(IdentifyProperties {
+            set,
+            ..Default::default()
+        })
+        .await;
+    }
+
+    /// Set one person property unless it's already set, like `set_person_properties` with a single `$set_once` entry.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self, value)))]
+    pub async fn set_person_property_once(
+        &self,
+        key: impl Into<String> + std::fmt::Debug,
+        value: impl Into<serde_json::Value>,
+    ) {
+        let mut set_once = PersonProperties::new();
+        set_once.insert(key, value);
+
+        self.set_person_properties(IdentifyProperties {
+            set_once,
+            ..Default::default()
+        })
+        .await;
+    }
+
+    /// Remove a person property, with an `$identify` event listing it under `$unset`.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn unset_person_property(&self, key: impl Into<String> + std::fmt::Debug) {
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::UnsetPersonProperty(key.into()))
+            .instrument(tracing::trace_span!(
+                "sending the UnsetPersonProperty message"
+            ))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue unset_person_property message");
+        }
+
+        self.trigger_configuration_refresh()
+            .instrument(tracing::trace_span!("triggering a configuration refresh"))
+            .await;
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     pub async fn add_group(
         &self,
@@ -407,9 +953,14 @@ impl Recorder {
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     pub async fn reset(&self) {
+        self.reset_with(ResetOptions::default()).await;
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn reset_with(&self, options: ResetOptions) {
         if let Err(e) = self
             .outgoing
-            .send(RawSignal::Reset)
+            .send(RawSignal::Reset(options))
             .instrument(tracing::trace_span!("sending the Reset message"))
             .await
         {
@@ -421,6 +972,86 @@ impl Recorder {
             .await;
     }
 
+    /// The ID of this session, which is attached to every event as `$session_id`.
+    /// Returns None if the Worker has shut down.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self), ret(level = tracing::Level::TRACE)))]
+    pub async fn get_session_id(&self) -> Option<String> {
+        self.get_session_ids()
+            .await
+            .inspect_err(|e| tracing::debug!(%e, "Failed to get the session IDs"))
+            .ok()
+            .map(|ids| ids.session_id)
+    }
+
+    /// The distinct ID events are attributed to: the identified distinct ID, or else the anonymous distinct ID.
+    /// Returns an empty string if the Worker has shut down.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self), ret(level = tracing::Level::TRACE)))]
+    pub async fn get_distinct_id(&self) -> String {
+        self.get_session_ids()
+            .await
+            .inspect_err(|e| tracing::debug!(%e, "Failed to get the session IDs"))
+            .map(|ids| ids.distinct_id)
+            .unwrap_or_default()
+    }
+
+    /// Export the device ID, distinct IDs, and groups as a versioned JSON document, for [`Recorder::import_identity`] on another machine.
+    /// Returns an empty string if the Worker has shut down.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
+    pub async fn export_identity(&self) -> String {
+        let (tx, rx) = oneshot();
+
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::ExportIdentity { tx })
+            .instrument(tracing::trace_span!("sending the ExportIdentity message"))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue ExportIdentity message");
+            return String::new();
+        }
+
+        let properties = match rx.await {
+            Ok(properties) => properties,
+            Err(e) => {
+                tracing::debug!(%e, "Failed to get the identity to export");
+                return String::new();
+            }
+        };
+
+        let mut exported = Vec::new();
+        if let Err(e) = properties.export_to_writer(&mut exported) {
+            tracing::error!(%e, "Failed to export the identity");
+            return String::new();
+        }
+
+        String::from_utf8(exported).unwrap_or_default()
+    }
+
+    /// Replace the current identity with one exported by [`Recorder::export_identity`], and record an `$identity_imported` event.
+    /// Malformed exports are rejected without changing the current identity.
+    #[cfg_attr(
+        feature = "tracing-instrument",
+        tracing::instrument(skip(self, exported))
+    )]
+    pub async fn import_identity(&self, exported: &str) -> Result<(), RecorderError> {
+        let properties = StoredProperties::import_from_reader(exported.as_bytes())?;
+
+        if let Err(e) = self
+            .outgoing
+            .send(RawSignal::ImportIdentity(Box::new(properties)))
+            .instrument(tracing::trace_span!("sending the ImportIdentity message"))
+            .await
+        {
+            tracing::error!(error = ?e, "Failed to enqueue ImportIdentity message");
+        }
+
+        self.trigger_configuration_refresh()
+            .instrument(tracing::trace_span!("triggering a configuration refresh"))
+            .await;
+
+        Ok(())
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self), ret(level = tracing::Level::TRACE)))]
     async fn get_session_properties(&self) -> Result<Map, FullDuplexError> {
         let (tx, rx) = tokio::sync::oneshot::channel();
@@ -438,6 +1069,21 @@ impl Recorder {
             .await?)
     }
 
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self), ret(level = tracing::Level::TRACE)))]
+    async fn get_session_ids(&self) -> Result<SessionIds, FullDuplexError> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        self.outgoing
+            .send(RawSignal::GetSessionIds { tx })
+            .instrument(tracing::trace_span!("sending the GetSessionIds message"))
+            .await
+            .map_err(|_| FullDuplexError::SendError)?;
+
+        Ok(rx
+            .instrument(tracing::trace_span!("waiting for reply"))
+            .await?)
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     pub async fn flush_now(&self) {
         if let Err(e) = self.outgoing.send(RawSignal::FlushNow).await {
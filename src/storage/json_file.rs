@@ -1,32 +1,33 @@
-use std::io::Write;
-use std::path::PathBuf;
-
+use std::ffi::OsString;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::storage::schema::{self, Document};
 use crate::storage::{Storage, StoredProperties};
-use tokio::fs::OpenOptions;
-use tokio::io::AsyncReadExt;
-
-const XDG_PREFIX: &str = "systems.determinate.detsys-ids-client";
-const XDG_STORAGE_FILENAME: &str = "storage.json";
-const NOTES: &[&str] = &[
-    "The IDs in this file are randomly generated UUIDs.",
-    "Determinate Systems uses these IDs to know how many people use our software and how to focus our limited resources for research and development.",
-    "The data here contains no personally identifiable information.",
-    "You can delete this file at any time to create new IDs.",
-    "",
-    "See our privacy policy: https://determinate.systems/policies/privacy",
-    "See our docs on telemetry: https://dtr.mn/telemetry",
-];
+
+pub(crate) const APPLICATION_ID: &str = "systems.determinate.detsys-ids-client";
+const STORAGE_FILENAME: &str = "storage.json";
+/// How long to wait for another process to release the storage lock before proceeding without it.
+const LOCK_TIMEOUT: Duration = Duration::from_millis(500);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How many unreadable storage files are kept next to the storage for inspection.
+const MAX_CORRUPT_BACKUPS: usize = 2;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("No HOME is available")]
     NoHome,
 
-    #[error("The storage location has no parent directory")]
+    #[error("The storage location's parent directory doesn't exist")]
     LocationHasNoParent,
 
-    #[error("Serializing / deserializing failure: {0}")]
-    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Schema(#[from] schema::Error),
 
     #[error("Loading from storage failed when opening the file `{0}`: {1}")]
     Open(PathBuf, std::io::Error),
@@ -40,6 +41,9 @@ pub enum Error {
     #[error("Writing storage to `{0}` failed: {1}")]
     Write(PathBuf, std::io::Error),
 
+    #[error("The storage body's checksum is {actual}, but the document recorded {expected}")]
+    ChecksumMismatch { expected: String, actual: String },
+
     #[error(transparent)]
     Persist(#[from] tempfile::PersistError),
 
@@ -47,44 +51,214 @@ pub enum Error {
     Join(#[from] tokio::task::JoinError),
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-struct WrappedStorage {
-    notes: Vec<String>,
-    body: StoredProperties,
-}
-
 pub struct JsonFile {
     location: PathBuf,
     directory: PathBuf,
+
+    /// The file as of the last load or store, or None if the file didn't exist.
+    observed: Arc<Mutex<Option<Observed>>>,
+
+    /// Whether to write and verify a checksum of the stored body.
+    checksums: bool,
 }
 
 impl JsonFile {
-    #[allow(dead_code)]
+    #[deprecated(
+        note = "use `JsonFile::try_from_path`, which reports why the location is unusable"
+    )]
     #[tracing::instrument]
     pub fn new(location: PathBuf) -> Option<Self> {
         Some(Self {
             directory: location.parent()?.to_owned(),
             location,
+            observed: Arc::new(Mutex::new(None)),
+            checksums: true,
+        })
+    }
+
+    /// Store at `path`, whose parent directory must already exist.
+    #[tracing::instrument]
+    pub fn try_from_path(path: &Path) -> Result<Self, Error> {
+        let directory = path
+            .parent()
+            .filter(|directory| directory.is_dir())
+            .ok_or(Error::LocationHasNoParent)?;
+
+        Ok(Self {
+            directory: directory.to_owned(),
+            location: path.to_owned(),
+            observed: Arc::new(Mutex::new(None)),
+            checksums: true,
         })
     }
 
+    /// Whether to write a checksum of the stored properties, and reject storage whose checksum doesn't match. Enabled by default.
+    ///
+    /// Storage without a checksum, like storage written by earlier releases, is always accepted.
+    pub fn with_checksums(mut self, enabled: bool) -> Self {
+        self.checksums = enabled;
+        self
+    }
+
+    /// Store at `path`, creating its parent directory if it doesn't exist yet.
+    pub async fn try_create_parents(path: &Path) -> Result<Self, Error> {
+        if let Some(directory) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(directory)
+                .await
+                .map_err(|e| Error::Create(directory.to_owned(), e))?;
+        }
+
+        Self::try_from_path(path)
+    }
+
+    /// Store in the platform's state directory, moving storage there from the XDG state directory used by earlier releases.
     pub async fn try_default() -> Result<Self, Error> {
-        let xdg_dirs = xdg::BaseDirectories::with_prefix(XDG_PREFIX);
-
-        let file = xdg_dirs
-            .place_state_file(XDG_STORAGE_FILENAME)
-            .map_err(|e| {
-                match xdg_dirs
-                    .get_state_file(XDG_STORAGE_FILENAME)
-                    .ok_or(Error::NoHome)
-                {
-                    Ok(loc) => Error::Create(loc, e),
-                    Err(e) => e,
-                }
-            })?;
+        let locations = DefaultLocations::from_env().ok_or(Error::NoHome)?;
+
+        let location =
+            tokio::task::spawn_blocking(move || migrate(&locations.legacy, &locations.storage))
+                .await?;
+
+        Self::try_create_parents(&location).await
+    }
+}
+
+/// What this process last read from or wrote to the storage file.
+struct Observed {
+    hash: u64,
+
+    /// The properties in the file, if they could be read.
+    properties: Option<StoredProperties>,
+}
+
+/// Platforms with their own convention for where application state is kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Platform {
+    MacOs,
+    Windows,
+    Xdg,
+}
+
+impl Platform {
+    fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Xdg
+        }
+    }
+}
+
+/// Where default storage lives, and where earlier releases kept it.
+#[derive(Debug, PartialEq, Eq)]
+struct DefaultLocations {
+    storage: PathBuf,
+    legacy: PathBuf,
+}
+
+impl DefaultLocations {
+    fn from_env() -> Option<Self> {
+        Some(Self::resolve(
+            Platform::current(),
+            &dirs::home_dir()?,
+            |name| std::env::var_os(name),
+        ))
+    }
+
+    /// `var` looks up environment variables, which are ignored unless they hold an absolute path.
+    fn resolve(platform: Platform, home: &Path, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        let absolute_var = |name: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+        };
+
+        let xdg_state_home =
+            absolute_var("XDG_STATE_HOME").unwrap_or_else(|| home.join(".local").join("state"));
+
+        let state_dir = match platform {
+            Platform::MacOs => home.join("Library").join("Application Support"),
+            Platform::Windows => {
+                absolute_var("APPDATA").unwrap_or_else(|| home.join("AppData").join("Roaming"))
+            }
+            Platform::Xdg => xdg_state_home.clone(),
+        };
+
+        Self {
+            storage: state_dir.join(APPLICATION_ID).join(STORAGE_FILENAME),
+            legacy: xdg_state_home.join(APPLICATION_ID).join(STORAGE_FILENAME),
+        }
+    }
+}
+
+/// Move storage from `legacy` to `location`, unless storage already exists at `location`.
+///
+/// Returns the location to use, which stays `legacy` if the storage couldn't be moved.
+fn migrate(legacy: &Path, location: &Path) -> PathBuf {
+    if legacy == location || !legacy.exists() {
+        return location.to_owned();
+    }
+
+    match move_file(legacy, location) {
+        Ok(()) => {
+            tracing::debug!(
+                ?legacy,
+                ?location,
+                "Moved storage to the platform's state directory"
+            );
+            location.to_owned()
+        }
+        // Another process moved it first, or storage was already at `location`
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::AlreadyExists
+            ) =>
+        {
+            location.to_owned()
+        }
+        Err(e) => {
+            tracing::debug!(%e, ?legacy, ?location, "Failed to move storage, continuing to use its old location");
+            legacy.to_owned()
+        }
+    }
+}
 
-        Self::new(file).ok_or(Error::LocationHasNoParent)
+/// Move `from` to `to`, failing with `AlreadyExists` instead of replacing a file at `to`.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let directory = to
+        .parent()
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(directory)?;
+
+    match std::fs::hard_link(from, to) {
+        Ok(()) => {}
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::AlreadyExists
+            ) =>
+        {
+            return Err(e);
+        }
+        // Across devices, or on file systems without hard links
+        Err(_) => {
+            let mut temp = tempfile::NamedTempFile::new_in(directory)?;
+            std::io::copy(&mut File::open(from)?, &mut temp)?;
+            temp.as_file().sync_all()?;
+            temp.persist_noclobber(to).map_err(|e| e.error)?;
+        }
     }
+
+    std::fs::remove_file(from)
 }
 
 impl Storage for JsonFile {
@@ -92,35 +266,114 @@ impl Storage for JsonFile {
 
     #[tracing::instrument(skip(self))]
     async fn load(&self) -> Result<Option<StoredProperties>, Error> {
-        let mut file = OpenOptions::new()
-            .read(true)
-            .open(&self.location)
-            .await
-            .map_err(|e| Error::Open(self.location.clone(), e))?;
+        let location = self.location.clone();
+        let observed = self.observed.clone();
+        let checksums = self.checksums;
+
+        tokio::task::spawn_blocking(move || -> Result<Option<StoredProperties>, Error> {
+            let _lock = StorageLock::acquire(&location, LockKind::Shared);
+
+            let contents = read_contents(&location)?;
+            *observed.lock().unwrap_or_else(|e| e.into_inner()) =
+                contents.as_deref().map(|contents| Observed {
+                    hash: content_hash(contents),
+                    properties: None,
+                });
+
+            let Some(contents) = contents else {
+                return Err(Error::Open(
+                    location,
+                    std::io::Error::from(std::io::ErrorKind::NotFound),
+                ));
+            };
+
+            let document = match Document::decode(&contents) {
+                Ok(document) => document,
+                Err(e) if e.preserves_document() => return Err(e.into()),
+                Err(e) => {
+                    tracing::warn!(%e, ?location, "Storage is corrupt, moving it aside and starting fresh");
+                    move_aside(&location);
+                    *observed.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                    return Ok(None);
+                }
+            };
 
-        let mut contents = vec![];
-        file.read_to_end(&mut contents)
-            .await
-            .map_err(|e| Error::Read(self.location.clone(), e))?;
+            if checksums && let Some((expected, actual)) = document.checksum_mismatch() {
+                tracing::warn!(?location, "Storage doesn't match its checksum");
+                return Err(Error::ChecksumMismatch { expected, actual });
+            }
 
-        let wrapped: WrappedStorage = serde_json::from_slice(&contents)?;
+            if let Some(observed) = observed.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+                observed.properties = Some(document.properties.clone());
+            }
 
-        Ok(Some(wrapped.body))
+            Ok(Some(document.properties))
+        })
+        .await?
     }
 
-    #[tracing::instrument(skip(self, props))]
     async fn store(&mut self, props: StoredProperties) -> Result<(), Error> {
-        let wrapped = WrappedStorage {
-            notes: NOTES.iter().map(|v| String::from(*v)).collect(),
-            body: props,
-        };
-        let json = serde_json::to_string_pretty(&wrapped)?;
+        self.store_merged(props).await.map(|_| ())
+    }
 
+    #[tracing::instrument(skip(self, props))]
+    async fn store_merged(&mut self, props: StoredProperties) -> Result<StoredProperties, Error> {
         let directory = self.directory.clone();
         let location = self.location.clone();
+        let observed = self.observed.clone();
+        let checksums = self.checksums;
 
         tracing::trace!("Storing properties");
-        tokio::task::spawn_blocking(move || -> Result<(), Error> {
+        let stored = tokio::task::spawn_blocking(move || -> Result<StoredProperties, Error> {
+            let _lock = StorageLock::acquire(&location, LockKind::Exclusive);
+
+            let mut observed = observed.lock().unwrap_or_else(|e| e.into_inner());
+
+            let existing = match read_contents(&location)? {
+                Some(contents) => {
+                    let changed =
+                        observed.as_ref().map(|observed| observed.hash) != Some(content_hash(&contents));
+
+                    match Document::decode(&contents) {
+                        Ok(document) if checksums && document.checksum_mismatch().is_some() => {
+                            tracing::warn!(?location, "Storage doesn't match its checksum, moving it aside and starting fresh");
+                            move_aside(&location);
+                            None
+                        }
+                        Ok(document) => Some((document, changed)),
+                        Err(e) if e.preserves_document() => {
+                            tracing::warn!(%e, ?location, "Not overwriting storage which this version can't read");
+                            return Err(e.into());
+                        }
+                        Err(e) => {
+                            tracing::trace!(%e, "Existing storage is unreadable, overwriting");
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            let props = match existing {
+                Some((ref theirs, true)) => {
+                    tracing::debug!(
+                        "Storage was changed by another process since it was loaded, merging"
+                    );
+                    merge(
+                        props,
+                        observed.as_ref().and_then(|observed| observed.properties.as_ref()),
+                        theirs.properties.clone(),
+                    )
+                }
+                _ => props,
+            };
+
+            let json = Document::encode(
+                existing.as_ref().map(|(document, _)| document),
+                &props,
+                checksums,
+            )?;
+
             let mut tempfile = tempfile::NamedTempFile::new_in(&directory)
                 .map_err(|e| Error::Create(directory.clone(), e))?;
 
@@ -130,13 +383,194 @@ impl Storage for JsonFile {
 
             tempfile.persist(&location)?;
 
-            Ok(())
+            *observed = Some(Observed {
+                hash: content_hash(json.as_bytes()),
+                properties: Some(props.clone()),
+            });
+
+            Ok(props)
         })
         .await??;
 
         tracing::trace!(location = ?self.location, "Storage persisted");
 
-        Ok(())
+        Ok(stored)
+    }
+}
+
+/// Combine our properties with ones another process stored since we last saw the file, as of `base`.
+///
+/// IDs we changed since `base`, like by resetting, are kept. Otherwise the IDs which reached the disk first win, so concurrent first runs converge on one set of IDs.
+fn merge(
+    ours: StoredProperties,
+    base: Option<&StoredProperties>,
+    theirs: StoredProperties,
+) -> StoredProperties {
+    let anonymous_id_changed =
+        base.is_some_and(|base| base.anonymous_distinct_id != ours.anonymous_distinct_id);
+    let device_id_changed = base.is_some_and(|base| base.device_id != ours.device_id);
+    let distinct_id_changed = base.is_some_and(|base| base.distinct_id != ours.distinct_id);
+
+    let mut groups = ours.groups;
+    groups.merge(theirs.groups);
+
+    let (anonymous_distinct_id, anonymous_id_created_at) = if anonymous_id_changed {
+        (ours.anonymous_distinct_id, ours.anonymous_id_created_at)
+    } else {
+        (theirs.anonymous_distinct_id, theirs.anonymous_id_created_at)
+    };
+
+    let (distinct_id, sent_set_once) = if distinct_id_changed {
+        (ours.distinct_id, ours.sent_set_once)
+    } else {
+        let mut sent_set_once = theirs.sent_set_once;
+        for key in ours.sent_set_once {
+            if !sent_set_once.contains(&key) {
+                sent_set_once.push(key);
+            }
+        }
+
+        (ours.distinct_id.or(theirs.distinct_id), sent_set_once)
+    };
+
+    StoredProperties {
+        anonymous_distinct_id,
+        anonymous_id_created_at,
+        distinct_id,
+        device_id: if device_id_changed {
+            ours.device_id
+        } else {
+            theirs.device_id
+        },
+        groups,
+        checkin: ours.checkin,
+        checkin_fetched_at: ours.checkin_fetched_at,
+        sent_set_once,
+    }
+}
+
+fn read_contents(location: &Path) -> Result<Option<Vec<u8>>, Error> {
+    let mut file = match File::open(location) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::Open(location.to_owned(), e)),
+    };
+
+    let mut contents = vec![];
+    file.read_to_end(&mut contents)
+        .map_err(|e| Error::Read(location.to_owned(), e))?;
+
+    Ok(Some(contents))
+}
+
+/// Rename an unreadable storage file to `<name>.corrupt-<timestamp>`, pruning all but the newest few backups.
+fn move_aside(location: &Path) {
+    let Some(file_name) = location.file_name().and_then(|name| name.to_str()) else {
+        return;
+    };
+    let prefix = format!("{file_name}.corrupt-");
+
+    let backup = location.with_file_name(format!(
+        "{prefix}{}",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    ));
+
+    if let Err(e) = std::fs::rename(location, &backup) {
+        tracing::debug!(%e, ?backup, "Failed to move the corrupt storage aside");
+        return;
+    }
+
+    let Some(directory) = location.parent() else {
+        return;
+    };
+
+    let mut backups: Vec<PathBuf> = match std::fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix))
+            })
+            .collect(),
+        Err(e) => {
+            tracing::debug!(%e, "Failed to list corrupt storage backups");
+            return;
+        }
+    };
+
+    // The timestamps sort lexicographically, so the oldest backups come first
+    backups.sort();
+    for stale in backups.iter().rev().skip(MAX_CORRUPT_BACKUPS) {
+        if let Err(e) = std::fs::remove_file(stale) {
+            tracing::debug!(%e, ?stale, "Failed to remove an old corrupt storage backup");
+        }
+    }
+}
+
+fn content_hash(contents: &[u8]) -> u64 {
+    let mut hasher = std::hash::DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Clone, Copy, Debug)]
+enum LockKind {
+    Shared,
+    Exclusive,
+}
+
+/// An advisory lock on a sidecar `.lock` file next to the storage.
+///
+/// The storage file itself is replaced on every store, so it can't carry the lock.
+/// Locking is best-effort: if the lock can't be taken within `LOCK_TIMEOUT` we proceed without it rather than hang the caller.
+struct StorageLock(Option<File>);
+
+impl StorageLock {
+    fn acquire(location: &Path, kind: LockKind) -> Self {
+        let mut lock_path = location.as_os_str().to_owned();
+        lock_path.push(".lock");
+
+        let file = match File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::trace!(%e, ?lock_path, "Cannot open the storage lock file, proceeding without a lock");
+                return Self(None);
+            }
+        };
+
+        let started = Instant::now();
+        loop {
+            let attempt = match kind {
+                LockKind::Shared => file.try_lock_shared(),
+                LockKind::Exclusive => file.try_lock(),
+            };
+
+            match attempt {
+                Ok(()) => return Self(Some(file)),
+                Err(std::fs::TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => {
+                    std::thread::sleep(LOCK_POLL_INTERVAL);
+                }
+                Err(e) => {
+                    tracing::debug!(%e, ?kind, ?lock_path, "Failed to lock the storage, proceeding without a lock");
+                    return Self(None);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        if let Some(file) = self.0.take() {
+            let _ = file.unlock();
+        }
     }
 }
 
@@ -151,7 +585,7 @@ mod test {
     async fn round_trips() {
         let tempfile = tempfile::NamedTempFile::new().unwrap();
 
-        let mut store = super::JsonFile::new(tempfile.path().into()).unwrap();
+        let mut store = super::JsonFile::try_from_path(tempfile.path()).unwrap();
         let identity = StoredProperties {
             anonymous_distinct_id: AnonymousDistinctId::default(),
             device_id: "hi".to_string().into(),
@@ -162,4 +596,369 @@ mod test {
 
         assert_eq!(identity, store.load().await.unwrap().unwrap());
     }
+
+    #[test]
+    fn missing_parents_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            super::JsonFile::try_from_path(&dir.path().join("missing/storage.json")),
+            Err(super::Error::LocationHasNoParent)
+        ));
+        assert!(matches!(
+            super::JsonFile::try_from_path(std::path::Path::new("/")),
+            Err(super::Error::LocationHasNoParent)
+        ));
+        assert!(super::JsonFile::try_from_path(&dir.path().join("storage.json")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn parents_can_be_created() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("nested/state/storage.json");
+
+        let mut store = super::JsonFile::try_create_parents(&location)
+            .await
+            .unwrap();
+        let mode = std::fs::metadata(location.parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        store.store(StoredProperties::default()).await.unwrap();
+        assert!(location.exists());
+    }
+
+    /// Change the device ID in the stored body without updating its checksum.
+    fn tamper(location: &std::path::Path) {
+        let mut document: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(location).unwrap()).unwrap();
+        document["body"]["device_id"] = "tampered".into();
+        std::fs::write(location, serde_json::to_vec(&document).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn tampering_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+
+        let mut store = super::JsonFile::try_from_path(&location).unwrap();
+        store.store(StoredProperties::default()).await.unwrap();
+        assert!(store.load().await.is_ok());
+
+        tamper(&location);
+        assert!(matches!(
+            store.load().await,
+            Err(super::Error::ChecksumMismatch { expected, actual }) if expected != actual
+        ));
+
+        // Storing again moves the tampered document aside and replaces it
+        let tampered = std::fs::read(&location).unwrap();
+        let identity = StoredProperties {
+            device_id: "fresh".to_string().into(),
+            ..Default::default()
+        };
+        store.store(identity.clone()).await.unwrap();
+        assert_eq!(store.load().await.unwrap().unwrap(), identity);
+
+        let backups = backups(dir.path());
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read(&backups[0]).unwrap(), tampered);
+    }
+
+    #[tokio::test]
+    async fn checksums_can_be_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+
+        let mut store = super::JsonFile::try_from_path(&location)
+            .unwrap()
+            .with_checksums(false);
+        store.store(StoredProperties::default()).await.unwrap();
+
+        let document: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&location).unwrap()).unwrap();
+        assert!(document.get("checksum").is_none());
+
+        // Checksums written with them enabled aren't verified either
+        let mut checked = super::JsonFile::try_from_path(&location).unwrap();
+        checked.store(StoredProperties::default()).await.unwrap();
+        tamper(&location);
+
+        let loaded = store.load().await.unwrap().unwrap();
+        assert_eq!(loaded.device_id, "tampered".to_string().into());
+    }
+
+    #[tokio::test]
+    async fn documents_without_checksums_are_accepted() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+        std::fs::write(&location, include_bytes!("fixtures/v1.json")).unwrap();
+
+        let store = super::JsonFile::try_from_path(&location).unwrap();
+        assert!(store.load().await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn interleaved_stores_converge_on_the_first_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+
+        let mut first = super::JsonFile::try_from_path(&location).unwrap();
+        let mut second = super::JsonFile::try_from_path(&location).unwrap();
+
+        // Both processes start with no storage and mint their own identity
+        assert!(first.load().await.is_err());
+        assert!(second.load().await.is_err());
+
+        let first_identity = StoredProperties {
+            anonymous_distinct_id: AnonymousDistinctId::default(),
+            device_id: "first".to_string().into(),
+            ..Default::default()
+        };
+        let second_identity = StoredProperties {
+            anonymous_distinct_id: AnonymousDistinctId::default(),
+            distinct_id: Some("someone".to_string().into()),
+            device_id: "second".to_string().into(),
+            groups: crate::Groups::from_iter([("org".to_string(), "acme".to_string())]),
+            ..Default::default()
+        };
+
+        first.store(first_identity.clone()).await.unwrap();
+        second.store(second_identity.clone()).await.unwrap();
+
+        let stored = first.load().await.unwrap().unwrap();
+        assert_eq!(
+            stored.anonymous_distinct_id,
+            first_identity.anonymous_distinct_id
+        );
+        assert_eq!(stored.device_id, first_identity.device_id);
+        assert_eq!(stored.distinct_id, second_identity.distinct_id);
+        assert_eq!(stored.groups, second_identity.groups);
+
+        // The first process now sees the merged state, so its next store isn't a merge
+        let mut updated = stored.clone();
+        updated.device_id = "updated".to_string().into();
+        first.store(updated.clone()).await.unwrap();
+        assert_eq!(second.load().await.unwrap().unwrap(), updated);
+    }
+
+    #[tokio::test]
+    async fn preserves_storage_from_newer_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+
+        std::fs::write(&location, include_bytes!("fixtures/v2-unknown-fields.json")).unwrap();
+
+        let mut store = super::JsonFile::try_from_path(&location).unwrap();
+        let mut props = store.load().await.unwrap().unwrap();
+        props.distinct_id = Some("someone-else".to_string().into());
+        store.store(props.clone()).await.unwrap();
+
+        let on_disk: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&location).unwrap()).unwrap();
+        assert_eq!(on_disk["version"], 2);
+        assert_eq!(on_disk["body"]["from_the_future"]["keep"], "me");
+        assert_eq!(store.load().await.unwrap().unwrap(), props);
+
+        let unreadable = include_bytes!("fixtures/v2-unreadable.json");
+        std::fs::write(&location, unreadable).unwrap();
+
+        assert!(store.load().await.is_err());
+        assert!(store.store(StoredProperties::default()).await.is_err());
+        assert_eq!(std::fs::read(&location).unwrap(), unreadable);
+    }
+
+    fn backups(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+        let mut backups: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .starts_with("storage.json.corrupt-")
+            })
+            .collect();
+        backups.sort();
+        backups
+    }
+
+    #[tokio::test]
+    async fn corrupt_storage_is_moved_aside() {
+        for corrupt in [
+            &br#"{"version": 1, "notes": [], "body": {"anonymous_distinct_id": "01"#[..],
+            &b"\x00\xffgarbage"[..],
+            &b"[1, 2, 3]"[..],
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let location = dir.path().join("storage.json");
+            std::fs::write(&location, corrupt).unwrap();
+
+            let mut store = super::JsonFile::try_from_path(&location).unwrap();
+            assert_eq!(store.load().await.unwrap(), None);
+
+            let backups = backups(dir.path());
+            assert_eq!(backups.len(), 1);
+            assert_eq!(std::fs::read(&backups[0]).unwrap(), corrupt);
+
+            let identity = StoredProperties::default();
+            store.store(identity.clone()).await.unwrap();
+            assert_eq!(store.load().await.unwrap().unwrap(), identity);
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_a_couple_of_corrupt_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+        let store = super::JsonFile::try_from_path(&location).unwrap();
+
+        for attempt in 0..4 {
+            std::fs::write(&location, format!("garbage {attempt}")).unwrap();
+            assert_eq!(store.load().await.unwrap(), None);
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let backups = backups(dir.path());
+        assert_eq!(backups.len(), super::MAX_CORRUPT_BACKUPS);
+        assert_eq!(std::fs::read(&backups[1]).unwrap(), b"garbage 3");
+    }
+
+    #[tokio::test]
+    async fn partially_recovers_documents_missing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+        std::fs::write(
+            &location,
+            br#"{"version": 1, "notes": [], "body": {"anonymous_distinct_id": "kept"}}"#,
+        )
+        .unwrap();
+
+        let store = super::JsonFile::try_from_path(&location).unwrap();
+        let recovered = store.load().await.unwrap().unwrap();
+
+        assert_eq!(recovered.anonymous_distinct_id.to_string(), "kept");
+        assert!(backups(dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn proceeds_when_the_lock_is_held() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("storage.json");
+
+        let held = std::fs::File::create(dir.path().join("storage.json.lock")).unwrap();
+        held.lock().unwrap();
+
+        let mut store = super::JsonFile::try_from_path(&location).unwrap();
+        let identity = StoredProperties::default();
+
+        store.store(identity.clone()).await.unwrap();
+        assert_eq!(identity, store.load().await.unwrap().unwrap());
+    }
+
+    fn no_vars(_: &str) -> Option<std::ffi::OsString> {
+        None
+    }
+
+    #[test]
+    fn default_locations_follow_platform_conventions() {
+        let home = std::path::Path::new("/home/alice");
+        let legacy = home.join(".local/state/systems.determinate.detsys-ids-client/storage.json");
+
+        let locations = super::DefaultLocations::resolve(super::Platform::MacOs, home, no_vars);
+        assert_eq!(
+            locations.storage,
+            home.join(
+                "Library/Application Support/systems.determinate.detsys-ids-client/storage.json"
+            )
+        );
+        assert_eq!(locations.legacy, legacy);
+
+        let locations = super::DefaultLocations::resolve(super::Platform::Windows, home, no_vars);
+        assert_eq!(
+            locations.storage,
+            home.join("AppData/Roaming/systems.determinate.detsys-ids-client/storage.json")
+        );
+
+        let locations = super::DefaultLocations::resolve(super::Platform::Xdg, home, no_vars);
+        assert_eq!(locations.storage, legacy);
+        assert_eq!(locations.legacy, legacy);
+    }
+
+    #[test]
+    fn default_locations_honor_absolute_environment_overrides() {
+        let home = std::path::Path::new("/home/alice");
+        let var = |name: &str| match name {
+            "XDG_STATE_HOME" => Some("/state".into()),
+            "APPDATA" => Some("relative/appdata".into()),
+            _ => None,
+        };
+
+        let locations = super::DefaultLocations::resolve(super::Platform::Xdg, home, var);
+        assert_eq!(
+            locations.storage,
+            std::path::Path::new("/state/systems.determinate.detsys-ids-client/storage.json")
+        );
+
+        let locations = super::DefaultLocations::resolve(super::Platform::Windows, home, var);
+        assert_eq!(
+            locations.storage,
+            home.join("AppData/Roaming/systems.determinate.detsys-ids-client/storage.json")
+        );
+        assert_eq!(
+            locations.legacy,
+            std::path::Path::new("/state/systems.determinate.detsys-ids-client/storage.json")
+        );
+    }
+
+    #[test]
+    fn migration_moves_legacy_storage_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join("legacy/storage.json");
+        let location = dir.path().join("native/app/storage.json");
+
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(&legacy, b"old").unwrap();
+
+        assert_eq!(super::migrate(&legacy, &location), location);
+        assert!(!legacy.exists());
+        assert_eq!(std::fs::read(&location).unwrap(), b"old");
+
+        // Running again, or after an older release recreated the legacy storage, leaves the new storage alone
+        std::fs::write(&legacy, b"older").unwrap();
+        assert_eq!(super::migrate(&legacy, &location), location);
+        assert_eq!(std::fs::read(&location).unwrap(), b"old");
+        assert_eq!(std::fs::read(&legacy).unwrap(), b"older");
+    }
+
+    #[test]
+    fn moving_never_replaces_the_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from.json");
+        let to = dir.path().join("to.json");
+
+        std::fs::write(&from, b"from").unwrap();
+        std::fs::write(&to, b"to").unwrap();
+
+        let e = super::move_file(&from, &to).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read(&from).unwrap(), b"from");
+        assert_eq!(std::fs::read(&to).unwrap(), b"to");
+    }
+
+    #[test]
+    fn migration_without_legacy_storage_uses_the_new_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join("legacy/storage.json");
+        let location = dir.path().join("native/storage.json");
+
+        assert_eq!(super::migrate(&legacy, &location), location);
+        assert!(!location.exists());
+        assert_eq!(super::migrate(&location, &location), location);
+    }
 }
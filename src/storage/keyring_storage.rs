@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use keyring::Entry;
+use keyring::credential::CredentialPersistence;
+
+use crate::storage::schema::{self, Document};
+use crate::storage::{Storage, StoredProperties};
+
+const KEYRING_USER: &str = "storage";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("The platform secret store failed: {0}")]
+    Keyring(#[from] keyring::Error),
+
+    #[error("The platform secret store does not persist secrets across reboots")]
+    NotPersistent,
+
+    #[error(transparent)]
+    Schema(#[from] schema::Error),
+
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// Store properties as a single secret in the platform's secret store, like the macOS Keychain or the Secret Service.
+pub struct KeyringStorage {
+    entry: Arc<Entry>,
+}
+
+impl KeyringStorage {
+    /// Use the platform's default secret store, if it persists secrets and is reachable.
+    pub async fn try_default() -> Result<Self, Error> {
+        if !matches!(
+            keyring::default::default_credential_builder().persistence(),
+            CredentialPersistence::UntilDelete
+        ) {
+            return Err(Error::NotPersistent);
+        }
+
+        let storage = Self::with_entry(Entry::new(super::json_file::APPLICATION_ID, KEYRING_USER)?);
+
+        // Reading proves the service is reachable and unlocked, and a missing entry is fine
+        storage.load().await?;
+
+        Ok(storage)
+    }
+
+    pub fn with_entry(entry: Entry) -> Self {
+        Self {
+            entry: Arc::new(entry),
+        }
+    }
+}
+
+impl Storage for KeyringStorage {
+    type Error = Error;
+
+    #[tracing::instrument(skip(self))]
+    async fn load(&self) -> Result<Option<StoredProperties>, Error> {
+        let entry = self.entry.clone();
+
+        tokio::task::spawn_blocking(move || match entry.get_password() {
+            Ok(contents) => Ok(Some(Document::decode(contents.as_bytes())?.properties)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        })
+        .await?
+    }
+
+    #[tracing::instrument(skip(self, props))]
+    async fn store(&mut self, props: StoredProperties) -> Result<(), Error> {
+        let entry = self.entry.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<(), Error> {
+            let existing = match entry.get_password() {
+                Ok(contents) => match Document::decode(contents.as_bytes()) {
+                    Ok(document) => Some(document),
+                    Err(e) if e.preserves_document() => return Err(e.into()),
+                    Err(_) => None,
+                },
+                Err(keyring::Error::NoEntry) => None,
+                Err(e) => return Err(e.into()),
+            };
+
+            entry.set_password(&Document::encode(existing.as_ref(), &props, false)?)?;
+
+            Ok(())
+        })
+        .await??;
+
+        tracing::trace!("Storage persisted to the platform secret store");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use keyring::Entry;
+    use keyring::mock::MockCredential;
+
+    use super::{Error, KeyringStorage};
+    use crate::AnonymousDistinctId;
+    use crate::storage::{Storage, StoredProperties};
+
+    fn mock_entry() -> Entry {
+        Entry::new_with_credential(
+            keyring::mock::default_credential_builder()
+                .build(None, "detsys-ids-client-test", "storage")
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn round_trips() {
+        let mut store = KeyringStorage::with_entry(mock_entry());
+        assert_eq!(store.load().await.unwrap(), None);
+
+        let identity = StoredProperties {
+            anonymous_distinct_id: AnonymousDistinctId::default(),
+            device_id: "hi".to_string().into(),
+            ..Default::default()
+        };
+
+        store.store(identity.clone()).await.unwrap();
+        assert_eq!(identity, store.load().await.unwrap().unwrap());
+
+        let stored = store.entry.get_password().unwrap();
+        assert!(stored.contains("\"version\""));
+    }
+
+    #[tokio::test]
+    async fn maps_platform_errors() {
+        let store = KeyringStorage::with_entry(mock_entry());
+
+        store
+            .entry
+            .get_credential()
+            .downcast_ref::<MockCredential>()
+            .unwrap()
+            .set_error(keyring::Error::PlatformFailure("locked".into()));
+
+        assert!(matches!(
+            store.load().await,
+            Err(Error::Keyring(keyring::Error::PlatformFailure(_)))
+        ));
+    }
+}
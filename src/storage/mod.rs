@@ -1,8 +1,18 @@
+use std::path::PathBuf;
+
 mod generic;
 mod json_file;
+#[cfg(feature = "keyring-storage")]
+mod keyring_storage;
+mod schema;
+mod shared;
 
 pub use generic::Generic;
 pub use json_file::JsonFile;
+#[cfg(feature = "keyring-storage")]
+pub use keyring_storage::KeyringStorage;
+pub use schema::Error as SchemaError;
+pub use shared::{Shared, SharedHandle};
 
 use crate::checkin::Checkin;
 use crate::identity::AnonymousDistinctId;
@@ -11,12 +21,83 @@ use crate::{DeviceId, DistinctId, Groups};
 #[derive(Default, Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StoredProperties {
     pub anonymous_distinct_id: AnonymousDistinctId,
+    /// When `anonymous_distinct_id` was minted, for rotating it after a maximum age.
+    #[serde(default)]
+    pub anonymous_id_created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub distinct_id: Option<DistinctId>,
     pub device_id: DeviceId,
     #[serde(default)]
     pub groups: Groups,
     #[serde(default)]
     pub checkin: Checkin,
+    /// When `checkin` was fetched from the server, so stale configuration isn't used forever on an offline machine.
+    #[serde(default)]
+    pub checkin_fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `$set_once` person property keys already sent for `distinct_id`.
+    #[serde(default)]
+    pub sent_set_once: Vec<String>,
+}
+
+impl StoredProperties {
+    /// Write these properties as a versioned storage document, for carrying an identity to another machine.
+    pub fn export_to_writer(&self, mut writer: impl std::io::Write) -> Result<(), SchemaError> {
+        writer.write_all(schema::Document::encode(None, self, false)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Read properties written by [`StoredProperties::export_to_writer`].
+    /// Unlike loading storage, incomplete documents are rejected instead of filled in with fresh IDs.
+    pub fn import_from_reader(mut reader: impl std::io::Read) -> Result<Self, SchemaError> {
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents)?;
+
+        Ok(schema::Document::decode_complete(&contents)?.properties)
+    }
+
+    /// Combine two identity states, preferring `self` and filling in from `other`.
+    ///
+    /// The distinct ID and device ID come from `self` unless it has none, and groups and feature options present in both take `self`'s value.
+    /// The anonymous distinct ID always comes from `self`, and the `$set_once` record follows the distinct ID.
+    pub fn merge(self, other: StoredProperties) -> StoredProperties {
+        let (distinct_id, sent_set_once) = match self.distinct_id {
+            Some(distinct_id) => (Some(distinct_id), self.sent_set_once),
+            None => (other.distinct_id, other.sent_set_once),
+        };
+
+        let device_id = if self.device_id.is_empty() {
+            other.device_id
+        } else {
+            self.device_id
+        };
+
+        let mut groups = self.groups;
+        groups.merge(other.groups);
+
+        let (checkin, checkin_fetched_at) = if self.checkin == Checkin::default() {
+            (other.checkin, other.checkin_fetched_at)
+        } else {
+            let mut checkin = self.checkin;
+            for (key, feature) in other.checkin.options {
+                checkin.options.entry(key).or_insert(feature);
+            }
+
+            (
+                checkin,
+                self.checkin_fetched_at.or(other.checkin_fetched_at),
+            )
+        };
+
+        StoredProperties {
+            anonymous_distinct_id: self.anonymous_distinct_id,
+            anonymous_id_created_at: self.anonymous_id_created_at,
+            distinct_id,
+            device_id,
+            groups,
+            checkin,
+            checkin_fetched_at,
+            sent_set_once,
+        }
+    }
 }
 
 pub trait Storage: Send + Sync + 'static {
@@ -29,15 +110,36 @@ pub trait Storage: Send + Sync + 'static {
         &mut self,
         properties: StoredProperties,
     ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Store `properties`, returning the properties as they were stored.
+    ///
+    /// Storage shared between processes may keep IDs another process stored first, which the caller should adopt.
+    fn store_merged(
+        &mut self,
+        properties: StoredProperties,
+    ) -> impl std::future::Future<Output = Result<StoredProperties, Self::Error>> + Send {
+        async move {
+            self.store(properties.clone()).await?;
+            Ok(properties)
+        }
+    }
 }
 
+// Built once per Worker, so the in-memory fallback's size doesn't matter
+#[allow(clippy::large_enum_variant)]
 pub enum DefaultStorageChain {
+    #[cfg(feature = "keyring-storage")]
+    Keyring(KeyringStorage),
     JsonFile(JsonFile),
     Generic(Generic),
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum DefaultStorageChainError {
+    #[cfg(feature = "keyring-storage")]
+    #[error(transparent)]
+    Keyring(#[from] <KeyringStorage as Storage>::Error),
+
     #[error(transparent)]
     JsonFile(#[from] <JsonFile as Storage>::Error),
 
@@ -46,8 +148,57 @@ pub enum DefaultStorageChainError {
 }
 
 impl DefaultStorageChain {
-    pub async fn new() -> DefaultStorageChain {
-        match JsonFile::try_default().await {
+    /// Use JsonFile storage at `DETSYS_IDS_STORAGE_PATH`, `storage_path`, or the platform's state directory, in that order of precedence.
+    ///
+    /// With the `keyring-storage` feature, the platform's secret store is preferred over the platform's state directory when it is available.
+    /// An empty secret store starts with a copy of the identity in the state directory.
+    pub async fn new(storage_path: Option<PathBuf>) -> DefaultStorageChain {
+        Self::from_env(storage_path, |name| std::env::var_os(name)).await
+    }
+
+    /// Like [`DefaultStorageChain::new`], looking up environment variables with `var`.
+    pub(crate) async fn from_env(
+        storage_path: Option<PathBuf>,
+        var: impl Fn(&str) -> Option<std::ffi::OsString>,
+    ) -> DefaultStorageChain {
+        let json_file = match crate::ambient_storage_path(var).or(storage_path) {
+            Some(location) => JsonFile::try_create_parents(&location).await,
+            None => {
+                #[cfg(feature = "keyring-storage")]
+                match KeyringStorage::try_default().await {
+                    Ok(mut keyring) => {
+                        match JsonFile::try_default().await {
+                            Ok(json_file) => {
+                                if let Err(e) = migrate_to_keyring(&mut keyring, &json_file).await {
+                                    tracing::debug!(
+                                        ?e,
+                                        "Failed to copy JsonFile storage into the platform secret store"
+                                    );
+                                }
+                            }
+                            Err(e) => {
+                                tracing::debug!(
+                                    ?e,
+                                    "No JsonFile storage to copy into the platform secret store"
+                                );
+                            }
+                        }
+
+                        return Self::Keyring(keyring);
+                    }
+                    Err(e) => {
+                        tracing::debug!(
+                            ?e,
+                            "The platform secret store is unavailable, falling back to JsonFile"
+                        );
+                    }
+                }
+
+                JsonFile::try_default().await
+            }
+        };
+
+        match json_file {
             Ok(json) => Self::JsonFile(json),
             Err(e) => {
                 tracing::debug!(
@@ -60,11 +211,31 @@ impl DefaultStorageChain {
     }
 }
 
+/// Copy the identity in `json_file` into `keyring` if the keyring is empty, so switching to the platform secret store keeps the existing IDs.
+#[cfg(feature = "keyring-storage")]
+async fn migrate_to_keyring(
+    keyring: &mut KeyringStorage,
+    json_file: &JsonFile,
+) -> Result<(), DefaultStorageChainError> {
+    if keyring.load().await?.is_some() {
+        return Ok(());
+    }
+
+    if let Some(properties) = json_file.load().await? {
+        tracing::debug!("Copying JsonFile storage into the platform secret store");
+        keyring.store(properties).await?;
+    }
+
+    Ok(())
+}
+
 impl Storage for DefaultStorageChain {
     type Error = DefaultStorageChainError;
 
     async fn load(&self) -> Result<Option<StoredProperties>, Self::Error> {
         match self {
+            #[cfg(feature = "keyring-storage")]
+            DefaultStorageChain::Keyring(keyring) => Ok(keyring.load().await?),
             DefaultStorageChain::JsonFile(json_file) => Ok(json_file.load().await?),
             DefaultStorageChain::Generic(generic) => Ok(generic.load().await?),
         }
@@ -72,8 +243,220 @@ impl Storage for DefaultStorageChain {
 
     async fn store(&mut self, properties: StoredProperties) -> Result<(), Self::Error> {
         match self {
+            #[cfg(feature = "keyring-storage")]
+            DefaultStorageChain::Keyring(keyring) => Ok(keyring.store(properties).await?),
             DefaultStorageChain::JsonFile(json_file) => Ok(json_file.store(properties).await?),
             DefaultStorageChain::Generic(generic) => Ok(generic.store(properties).await?),
         }
     }
+
+    async fn store_merged(
+        &mut self,
+        properties: StoredProperties,
+    ) -> Result<StoredProperties, Self::Error> {
+        match self {
+            #[cfg(feature = "keyring-storage")]
+            DefaultStorageChain::Keyring(keyring) => Ok(keyring.store_merged(properties).await?),
+            DefaultStorageChain::JsonFile(json_file) => {
+                Ok(json_file.store_merged(properties).await?)
+            }
+            DefaultStorageChain::Generic(generic) => Ok(generic.store_merged(properties).await?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StoredProperties;
+    use crate::checkin::Checkin;
+
+    fn checkin(options: serde_json::Value) -> Checkin {
+        serde_json::from_value(serde_json::json!({ "options": options })).unwrap()
+    }
+
+    fn properties(
+        distinct_id: Option<&str>,
+        device_id: &str,
+        groups: &[(&str, &str)],
+        checkin: Checkin,
+    ) -> StoredProperties {
+        StoredProperties {
+            distinct_id: distinct_id.map(|id| id.to_string().into()),
+            device_id: device_id.to_string().into(),
+            groups: groups
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            checkin,
+            sent_set_once: distinct_id.into_iter().map(String::from).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merge_ids() {
+        for (ours, theirs, expected) in [
+            (Some("ours"), Some("theirs"), Some("ours")),
+            (Some("ours"), None, Some("ours")),
+            (None, Some("theirs"), Some("theirs")),
+            (None, None, None),
+        ] {
+            let merged = properties(ours, "", &[], Checkin::default()).merge(properties(
+                theirs,
+                "",
+                &[],
+                Checkin::default(),
+            ));
+
+            assert_eq!(merged.distinct_id, expected.map(|id| id.to_string().into()));
+            assert_eq!(
+                merged.sent_set_once,
+                Vec::from_iter(expected.map(String::from))
+            );
+        }
+
+        for (ours, theirs, expected) in [
+            ("ours", "theirs", "ours"),
+            ("ours", "", "ours"),
+            ("", "theirs", "theirs"),
+            ("", "", ""),
+        ] {
+            let merged = properties(None, ours, &[], Checkin::default()).merge(properties(
+                None,
+                theirs,
+                &[],
+                Checkin::default(),
+            ));
+
+            assert_eq!(merged.device_id, expected.to_string().into());
+        }
+    }
+
+    #[test]
+    fn merge_keeps_our_anonymous_id() {
+        let ours = properties(None, "", &[], Checkin::default());
+        let anonymous_distinct_id = ours.anonymous_distinct_id.clone();
+
+        let merged = ours.merge(properties(None, "", &[], Checkin::default()));
+        assert_eq!(merged.anonymous_distinct_id, anonymous_distinct_id);
+    }
+
+    #[test]
+    fn merge_groups() {
+        let ours = &[("org", "ours"), ("team", "ours")][..];
+        let theirs = &[("org", "theirs"), ("project", "theirs")][..];
+
+        for (ours, theirs, expected) in [
+            (
+                ours,
+                theirs,
+                &[("org", "ours"), ("team", "ours"), ("project", "theirs")][..],
+            ),
+            (ours, &[][..], ours),
+            (&[][..], theirs, theirs),
+            (&[][..], &[][..], &[][..]),
+        ] {
+            let merged = properties(None, "", ours, Checkin::default()).merge(properties(
+                None,
+                "",
+                theirs,
+                Checkin::default(),
+            ));
+
+            assert_eq!(
+                merged.groups,
+                crate::Groups::from_iter(
+                    expected.iter().map(|(k, v)| (k.to_string(), v.to_string()))
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn merge_checkins() {
+        let ours = checkin(serde_json::json!({
+            "shared": { "variant": "ours" },
+            "mine": { "variant": true },
+        }));
+        let theirs = checkin(serde_json::json!({
+            "shared": { "variant": "theirs" },
+            "yours": { "variant": true },
+        }));
+        let both = checkin(serde_json::json!({
+            "shared": { "variant": "ours" },
+            "mine": { "variant": true },
+            "yours": { "variant": true },
+        }));
+
+        for (ours, theirs, expected) in [
+            (ours.clone(), theirs.clone(), both),
+            (ours.clone(), Checkin::default(), ours),
+            (Checkin::default(), theirs.clone(), theirs),
+            (Checkin::default(), Checkin::default(), Checkin::default()),
+        ] {
+            let merged = properties(None, "", &[], ours).merge(properties(None, "", &[], theirs));
+
+            assert_eq!(merged.checkin, expected);
+        }
+    }
+
+    #[test]
+    fn merge_checkin_timestamps() {
+        let fetched_at = |secs| chrono::DateTime::from_timestamp(secs, 0);
+        let ours = checkin(serde_json::json!({ "mine": { "variant": true } }));
+
+        let merged = StoredProperties {
+            checkin: ours.clone(),
+            checkin_fetched_at: fetched_at(1),
+            ..Default::default()
+        }
+        .merge(StoredProperties {
+            checkin: ours.clone(),
+            checkin_fetched_at: fetched_at(2),
+            ..Default::default()
+        });
+        assert_eq!(merged.checkin_fetched_at, fetched_at(1));
+
+        // Without a check-in of our own, theirs comes with its timestamp
+        let merged = StoredProperties::default().merge(StoredProperties {
+            checkin: ours,
+            checkin_fetched_at: fetched_at(2),
+            ..Default::default()
+        });
+        assert_eq!(merged.checkin_fetched_at, fetched_at(2));
+    }
+
+    #[cfg(feature = "keyring-storage")]
+    #[tokio::test]
+    async fn json_file_storage_is_copied_into_an_empty_keyring() {
+        use super::{JsonFile, KeyringStorage, Storage};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut json_file = JsonFile::try_from_path(&dir.path().join("storage.json")).unwrap();
+        let existing = properties(
+            Some("alice"),
+            "device",
+            &[("org", "acme")],
+            Checkin::default(),
+        );
+        json_file.store(existing.clone()).await.unwrap();
+
+        let mut keyring = KeyringStorage::with_entry(keyring::Entry::new_with_credential(
+            keyring::mock::default_credential_builder()
+                .build(None, "detsys-ids-client-test", "storage")
+                .unwrap(),
+        ));
+        super::migrate_to_keyring(&mut keyring, &json_file)
+            .await
+            .unwrap();
+        assert_eq!(keyring.load().await.unwrap(), Some(existing.clone()));
+
+        // Once the keyring has an identity, it's kept
+        let newer = properties(Some("bob"), "device", &[], Checkin::default());
+        keyring.store(newer.clone()).await.unwrap();
+        super::migrate_to_keyring(&mut keyring, &json_file)
+            .await
+            .unwrap();
+        assert_eq!(keyring.load().await.unwrap(), Some(newer));
+    }
 }
@@ -0,0 +1,312 @@
+use crate::Map;
+use crate::storage::StoredProperties;
+
+/// The version of the storage document written by this release.
+pub(crate) const CURRENT_VERSION: u32 = 1;
+
+/// Upgrades for each historical document shape, indexed by the version they upgrade from.
+const MIGRATIONS: &[fn(&mut Map)] = &[
+    // v0 -> v1: documents gained a `version` marker, and the body is unchanged.
+    |_document| {},
+];
+
+const NOTES: &[&str] = &[
+    "The IDs in this file are randomly generated UUIDs.",
+    "Determinate Systems uses these IDs to know how many people use our software and how to focus our limited resources for research and development.",
+    "The data here contains no personally identifiable information.",
+    "You can delete this file at any time to create new IDs.",
+    "",
+    "See our privacy policy: https://determinate.systems/policies/privacy",
+    "See our docs on telemetry: https://dtr.mn/telemetry",
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Serializing / deserializing failure: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("The storage document is not a JSON object")]
+    NotAnObject,
+
+    #[error("The storage document has no body")]
+    MissingBody,
+
+    #[error("The storage document has an unrecognized version: {0}")]
+    InvalidVersion(serde_json::Value),
+
+    #[error("The storage document is from a newer version ({0}) and could not be read: {1}")]
+    NewerVersion(u32, serde_json::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Whether the document on disk should be left alone rather than overwritten.
+    pub(crate) fn preserves_document(&self) -> bool {
+        matches!(self, Error::InvalidVersion(_) | Error::NewerVersion(..))
+    }
+}
+
+/// A storage document, upgraded to the current version if it was older.
+#[derive(Debug)]
+pub(crate) struct Document {
+    pub(crate) version: u32,
+    pub(crate) properties: StoredProperties,
+
+    /// The complete document, including any fields this version doesn't know about.
+    raw: Map,
+}
+
+impl Document {
+    pub(crate) fn decode(contents: &[u8]) -> Result<Self, Error> {
+        Self::decode_with(contents, true)
+    }
+
+    /// Decode a document which must be complete, instead of filling in missing fields with fresh values.
+    pub(crate) fn decode_complete(contents: &[u8]) -> Result<Self, Error> {
+        Self::decode_with(contents, false)
+    }
+
+    fn decode_with(contents: &[u8], fill_missing: bool) -> Result<Self, Error> {
+        let serde_json::Value::Object(mut raw) = serde_json::from_slice(contents)? else {
+            return Err(Error::NotAnObject);
+        };
+
+        let mut version = match raw.get("version") {
+            None => 0,
+            Some(v) => v
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| Error::InvalidVersion(v.clone()))?,
+        };
+
+        while let Some(migration) = MIGRATIONS.get(version as usize) {
+            tracing::trace!(from = version, "Migrating the storage document");
+            migration(&mut raw);
+            version += 1;
+        }
+        raw.insert("version".into(), version.into());
+
+        let body = raw.get("body").cloned().ok_or(Error::MissingBody)?;
+        let properties = match serde_json::from_value(body.clone()) {
+            Ok(properties) => properties,
+            Err(e) if version > CURRENT_VERSION => return Err(Error::NewerVersion(version, e)),
+            Err(e) if !fill_missing => return Err(Error::Json(e)),
+            Err(e) => {
+                let properties = fill_missing_fields(body).ok_or(Error::Json(e))?;
+                tracing::debug!("Recovered a storage document which was missing fields");
+                properties
+            }
+        };
+
+        if version > CURRENT_VERSION {
+            tracing::debug!(
+                version,
+                "Loaded storage written by a newer version, unknown fields are preserved"
+            );
+        }
+
+        Ok(Self {
+            version,
+            properties,
+            raw,
+        })
+    }
+
+    /// The checksum recorded in the document and the body's actual checksum, if they differ.
+    pub(crate) fn checksum_mismatch(&self) -> Option<(String, String)> {
+        let expected = self.raw.get("checksum")?.as_str()?;
+        let actual = body_checksum(self.raw.get("body")?);
+
+        (expected != actual).then(|| (expected.to_string(), actual))
+    }
+
+    /// Serialize `properties`, keeping the fields of `existing` which this version doesn't know about.
+    ///
+    /// With `checksum`, the document records a checksum of its body for detecting corruption.
+    pub(crate) fn encode(
+        existing: Option<&Document>,
+        properties: &StoredProperties,
+        checksum: bool,
+    ) -> Result<String, Error> {
+        let serde_json::Value::Object(ours) = serde_json::to_value(properties)? else {
+            return Err(Error::NotAnObject);
+        };
+
+        let mut raw = existing.map(|doc| doc.raw.clone()).unwrap_or_default();
+
+        let mut body = match raw.remove("body") {
+            Some(serde_json::Value::Object(body)) => body,
+            _ => Map::new(),
+        };
+        body.extend(ours);
+
+        let version = existing
+            .map(|doc| doc.version)
+            .unwrap_or(CURRENT_VERSION)
+            .max(CURRENT_VERSION);
+
+        raw.insert("version".into(), version.into());
+        raw.insert(
+            "notes".into(),
+            NOTES.iter().map(|v| String::from(*v)).collect(),
+        );
+        let body = serde_json::Value::from(body);
+        if checksum {
+            raw.insert("checksum".into(), body_checksum(&body).into());
+        } else {
+            raw.remove("checksum");
+        }
+        raw.insert("body".into(), body);
+
+        Ok(serde_json::to_string_pretty(&raw)?)
+    }
+}
+
+/// The hex SHA-256 digest of the body's compact serialization.
+fn body_checksum(body: &serde_json::Value) -> String {
+    use sha2::Digest;
+
+    format!("{:x}", sha2::Sha256::digest(body.to_string().as_bytes()))
+}
+
+/// Deserialize a body which is missing required fields by generating fresh values for them.
+fn fill_missing_fields(body: serde_json::Value) -> Option<StoredProperties> {
+    let serde_json::Value::Object(body) = body else {
+        return None;
+    };
+
+    let serde_json::Value::Object(mut filled) =
+        serde_json::to_value(StoredProperties::default()).ok()?
+    else {
+        return None;
+    };
+    filled.extend(body);
+
+    serde_json::from_value(filled.into()).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CURRENT_VERSION, Document, Error};
+    use crate::storage::StoredProperties;
+
+    fn identity() -> StoredProperties {
+        StoredProperties {
+            anonymous_distinct_id: "0195c8d2-6c1b-7b43-9d51-2b1e8b1f6a10".to_string().into(),
+            distinct_id: Some("someone".to_string().into()),
+            device_id: "DIDS-DEV-0195c8d2-6c1b-7b43-9d51-2b1e8b1f6a11"
+                .to_string()
+                .into(),
+            groups: crate::Groups::from_iter([("org".to_string(), "acme".to_string())]),
+            checkin: serde_json::from_value(
+                serde_json::json!({ "options": { "fast-path": { "variant": true } } }),
+            )
+            .unwrap(),
+            checkin_fetched_at: None,
+            anonymous_id_created_at: None,
+            sent_set_once: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn v0_minimal() {
+        let doc = Document::decode(include_bytes!("fixtures/v0-minimal.json")).unwrap();
+
+        assert_eq!(doc.version, CURRENT_VERSION);
+        assert_eq!(
+            doc.properties,
+            StoredProperties {
+                distinct_id: None,
+                groups: Default::default(),
+                checkin: Default::default(),
+                ..identity()
+            }
+        );
+    }
+
+    #[test]
+    fn v0() {
+        let doc = Document::decode(include_bytes!("fixtures/v0.json")).unwrap();
+
+        assert_eq!(doc.version, CURRENT_VERSION);
+        assert_eq!(doc.properties, identity());
+    }
+
+    #[test]
+    fn v1() {
+        let doc = Document::decode(include_bytes!("fixtures/v1.json")).unwrap();
+
+        assert_eq!(doc.version, 1);
+        assert_eq!(doc.properties, identity());
+    }
+
+    #[test]
+    fn newer_versions_keep_unknown_fields() {
+        let doc = Document::decode(include_bytes!("fixtures/v2-unknown-fields.json")).unwrap();
+        assert_eq!(doc.version, 2);
+        assert_eq!(doc.properties.distinct_id, identity().distinct_id);
+
+        let mut properties = doc.properties.clone();
+        properties.distinct_id = Some("someone-else".to_string().into());
+
+        let encoded: serde_json::Value =
+            serde_json::from_str(&Document::encode(Some(&doc), &properties, false).unwrap())
+                .unwrap();
+
+        assert_eq!(encoded["version"], 2);
+        assert_eq!(encoded["also_from_the_future"], true);
+        assert_eq!(encoded["body"]["from_the_future"]["keep"], "me");
+        assert_eq!(encoded["body"]["distinct_id"], "someone-else");
+    }
+
+    #[test]
+    fn unreadable_newer_versions_are_preserved() {
+        let err = Document::decode(include_bytes!("fixtures/v2-unreadable.json")).unwrap_err();
+
+        assert!(matches!(err, Error::NewerVersion(2, _)), "{err:?}");
+        assert!(err.preserves_document());
+    }
+
+    #[test]
+    fn invalid_versions_are_preserved() {
+        let err = Document::decode(include_bytes!("fixtures/invalid-version.json")).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidVersion(_)), "{err:?}");
+        assert!(err.preserves_document());
+    }
+
+    #[test]
+    fn round_trips_at_the_current_version() {
+        let encoded = Document::encode(None, &identity(), false).unwrap();
+        let doc = Document::decode(encoded.as_bytes()).unwrap();
+
+        assert_eq!(doc.version, CURRENT_VERSION);
+        assert_eq!(doc.properties, identity());
+    }
+
+    #[test]
+    fn exports_round_trip() {
+        let mut exported = Vec::new();
+        identity().export_to_writer(&mut exported).unwrap();
+
+        let imported = StoredProperties::import_from_reader(exported.as_slice()).unwrap();
+        assert_eq!(imported, identity());
+    }
+
+    #[test]
+    fn malformed_imports_are_rejected() {
+        for payload in [
+            "not json",
+            "[]",
+            r#"{"version": 1}"#,
+            r#"{"version": 1, "body": {"device_id": "only-a-device"}}"#,
+        ] {
+            assert!(
+                StoredProperties::import_from_reader(payload.as_bytes()).is_err(),
+                "{payload}"
+            );
+        }
+    }
+}
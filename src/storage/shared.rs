@@ -0,0 +1,54 @@
+use std::sync::{Arc, RwLock};
+
+use super::StoredProperties;
+
+/// In-memory storage whose state is shared between clones, so several Workers (or a test) can observe the same identity.
+#[derive(Clone, Default)]
+pub struct Shared {
+    state: Arc<RwLock<Option<StoredProperties>>>,
+}
+
+impl Shared {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start out with `properties` already stored.
+    pub fn seeded(properties: StoredProperties) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(Some(properties))),
+        }
+    }
+
+    /// A read-only handle to the stored state.
+    pub fn handle(&self) -> SharedHandle {
+        SharedHandle {
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SharedHandle {
+    state: Arc<RwLock<Option<StoredProperties>>>,
+}
+
+impl SharedHandle {
+    /// The most recently stored properties, if anything was stored.
+    pub fn get(&self) -> Option<StoredProperties> {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl super::Storage for Shared {
+    type Error = std::convert::Infallible;
+
+    async fn load(&self) -> Result<Option<StoredProperties>, Self::Error> {
+        Ok(self.handle().get())
+    }
+
+    async fn store(&mut self, properties: StoredProperties) -> Result<(), Self::Error> {
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = Some(properties);
+        Ok(())
+    }
+}
@@ -1,4 +1,10 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use std::time::Duration;
+
 use tokio::sync::mpsc::Receiver;
+use tokio::sync::watch;
 
 use crate::collator::{CollatedSignal, Event};
 
@@ -8,24 +14,254 @@ pub(crate) struct Batch<'a> {
     batch: &'a [Event],
 }
 
+impl<'a> Batch<'a> {
+    pub(crate) fn new(batch: &'a [Event]) -> Self {
+        Self {
+            sent_at: {
+                let now: chrono::DateTime<chrono::Utc> = std::time::SystemTime::now().into();
+                now.to_rfc3339()
+            },
+            batch,
+        }
+    }
+
+    /// Partition the batch into batches of at most `max_events` events, each with the same `sent_at`.
+    pub(crate) fn split(&self, max_events: usize) -> Vec<Batch<'a>> {
+        self.batch
+            .chunks(max_events.max(1))
+            .map(|chunk| Batch {
+                sent_at: self.sent_at.clone(),
+                batch: chunk,
+            })
+            .collect()
+    }
+
+    pub(crate) fn events(&self) -> &'a [Event] {
+        self.batch
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.batch.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.batch.is_empty()
+    }
+
+    /// The approximate size of the serialized batch, before compression.
+    pub(crate) fn size_bytes_estimate(&self) -> usize {
+        serde_json::to_vec(self).map(|v| v.len()).unwrap_or(0)
+    }
+}
+
+/// Counters maintained by the Submitter, readable from the Worker.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SubmitterMetrics {
+    submitted_events: Arc<AtomicU64>,
+    failed_batches: Arc<AtomicU64>,
+    buffered_events: Arc<AtomicUsize>,
+    dropped_events: Arc<AtomicU64>,
+}
+
+impl SubmitterMetrics {
+    pub(crate) fn submitted_event_count(&self) -> u64 {
+        self.submitted_events.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn failed_batch_count(&self) -> u64 {
+        self.failed_batches.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn buffered_event_count(&self) -> usize {
+        self.buffered_events.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn dropped_event_count(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    /// Count events dropped before they reached the Submitter.
+    pub(crate) fn record_dropped_events(&self, count: u64) {
+        self.dropped_events.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// What happens to new events when the Submitter can't keep up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SubmitterOverflowStrategy {
+    /// Wait for the Submitter's queue to have room, which slows down recording.
+    #[default]
+    Block,
+    /// Drop the new event when the Submitter's queue is full.
+    DropNewest,
+    /// Drop the oldest of the Submitter's pending events to make room for new ones.
+    ///
+    /// Pending events are limited to `Builder::max_pending_events`, or to the size of the Submitter's queue if that's unlimited.
+    /// Identity changes like `$identify` and `$create_alias` are never dropped.
+    /// Recording only waits while the Submitter is busy submitting a batch.
+    DropOldest,
+}
+
+/// How long the server asked the client to stop sending events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Pause {
+    /// Until a later check-in lifts the pause.
+    Indefinitely,
+    Until(tokio::time::Instant),
+}
+
+/// The server's request to stop sending events, set by the ConfigurationProxy and obeyed by the Submitter.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PauseSwitch {
+    pause: Arc<Mutex<Option<Pause>>>,
+}
+
+impl PauseSwitch {
+    pub(crate) fn set(&self, pause: Option<Pause>) {
+        let mut current = self.pause.lock().unwrap_or_else(|e| e.into_inner());
+
+        if *current != pause {
+            tracing::debug!(?pause, "The server changed whether events are paused");
+        }
+
+        *current = pause;
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        match *self.pause.lock().unwrap_or_else(|e| e.into_inner()) {
+            None => false,
+            Some(Pause::Indefinitely) => true,
+            Some(Pause::Until(deadline)) => tokio::time::Instant::now() < deadline,
+        }
+    }
+}
+
+/// How often queued events are flushed when neither the server nor the Builder says.
+pub(crate) const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The server's batching options, published by the ConfigurationProxy. `None` leaves the choice to the client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BatchingRequest {
+    pub(crate) flush_interval: Option<Duration>,
+    pub(crate) max_batch_size: Option<usize>,
+    pub(crate) max_pending_events: Option<usize>,
+}
+
 pub(crate) struct Submitter<T: crate::transport::Transport> {
     transport: T,
     incoming: Receiver<CollatedSignal>,
     events: Vec<Event>,
+    max_batch_size: Option<usize>,
+    max_pending_events: Option<usize>,
+    flush_interval: Duration,
+    flush_jitter: Duration,
+    metrics: SubmitterMetrics,
+    pause: PauseSwitch,
+    batching: watch::Receiver<BatchingRequest>,
 }
 
 impl<T: crate::transport::Transport> Submitter<T> {
-    pub(crate) fn new(transport: T, incoming: Receiver<CollatedSignal>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        transport: T,
+        incoming: Receiver<CollatedSignal>,
+        max_batch_size: Option<usize>,
+        max_pending_events: Option<usize>,
+        flush_interval: Duration,
+        flush_jitter: Duration,
+        metrics: SubmitterMetrics,
+        pause: PauseSwitch,
+        batching: watch::Receiver<BatchingRequest>,
+    ) -> Self {
+        if flush_interval < crate::checkin::MIN_FLUSH_INTERVAL {
+            tracing::warn!(
+                ?flush_interval,
+                minimum = ?crate::checkin::MIN_FLUSH_INTERVAL,
+                "The flush interval is too short, using the minimum"
+            );
+        }
+
         Self {
             transport,
             incoming,
             events: vec![],
+            max_batch_size,
+            max_pending_events,
+            flush_interval: flush_interval.max(crate::checkin::MIN_FLUSH_INTERVAL),
+            flush_jitter,
+            metrics,
+            pause,
+            batching,
         }
     }
 
+    fn flush_interval(&self) -> Duration {
+        self.batching
+            .borrow()
+            .flush_interval
+            .unwrap_or(self.flush_interval)
+    }
+
+    /// When to flush next, `period` from now plus up to the flush jitter.
+    fn next_flush(&self, period: Duration) -> tokio::time::Instant {
+        tokio::time::Instant::now()
+            + period
+            + self
+                .flush_jitter
+                .mul_f64(crate::configuration_proxy::random_unit())
+    }
+
+    /// The server's limit, if it set one, capped by the Builder's limit.
+    fn effective_max_batch_size(&self) -> Option<usize> {
+        min_limit(self.batching.borrow().max_batch_size, self.max_batch_size)
+    }
+
+    /// The server's limit, if it set one, capped by the Builder's limit.
+    fn effective_max_pending_events(&self) -> Option<usize> {
+        min_limit(
+            self.batching.borrow().max_pending_events,
+            self.max_pending_events,
+        )
+    }
+
+    /// Drop the oldest events beyond the pending event limit.
+    /// Identity changes are kept even beyond the limit, since the events after them depend on them.
+    fn enforce_pending_limit(&mut self) {
+        if let Some(max) = self.effective_max_pending_events()
+            && self.events.len() > max
+        {
+            let mut excess = self.events.len() - max;
+            let pending = self.events.len();
+            self.events.retain(|event| {
+                let drop = excess > 0 && !event.is_identity_change();
+                if drop {
+                    excess -= 1;
+                }
+                !drop
+            });
+
+            let dropped = pending - self.events.len();
+            if dropped > 0 {
+                tracing::debug!(
+                    dropped,
+                    max,
+                    "Dropping the oldest events since too many are waiting to be submitted"
+                );
+                self.metrics
+                    .dropped_events
+                    .fetch_add(dropped as u64, Ordering::Relaxed);
+            }
+        }
+
+        self.metrics
+            .buffered_events
+            .store(self.events.len(), Ordering::Relaxed);
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
     pub(crate) async fn execute(mut self) {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        let mut period = self.flush_interval();
+        let mut next_flush = tokio::time::Instant::now();
 
         loop {
             if self.incoming.is_closed() && self.incoming.is_empty() {
@@ -33,17 +269,38 @@ impl<T: crate::transport::Transport> Submitter<T> {
             }
             tokio::select! {
                 biased;
-                _ = interval.tick() => {
+                _ = tokio::time::sleep_until(next_flush) => {
                     self.try_flush().await;
+                    next_flush = self.next_flush(period);
+                }
+                Ok(()) = self.batching.changed() => {
+                    let requested = self.flush_interval();
+                    if requested != period {
+                        tracing::debug!(?period, ?requested, "The server changed the flush interval");
+                        period = requested;
+                        next_flush = self.next_flush(period);
+                    }
+
+                    self.enforce_pending_limit();
                 }
                 incoming_message = self.incoming.recv() => {
                     match incoming_message {
+                        Some(CollatedSignal::Event(_) | CollatedSignal::PriorityEvent(_)) if self.pause.is_paused() => {
+                            self.metrics.dropped_events.fetch_add(1, Ordering::Relaxed);
+                        }
                         Some(CollatedSignal::Event(event)) => {
                             self.events.push(*event);
+                            self.enforce_pending_limit();
+                        }
+                        Some(CollatedSignal::PriorityEvent(event)) => {
+                            self.events.push(*event);
+                            self.enforce_pending_limit();
+                            self.try_flush().await;
+                            next_flush = self.next_flush(period);
                         }
                         Some(CollatedSignal::FlushNow) => {
                             self.try_flush().await;
-                            interval.reset();
+                            next_flush = self.next_flush(period);
                         }
                         None => {
                             self.try_flush().await;
@@ -57,28 +314,249 @@ impl<T: crate::transport::Transport> Submitter<T> {
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
     async fn try_flush(&mut self) {
-        if self.events.is_empty() {
+        if self.pause.is_paused() && !self.events.is_empty() {
+            tracing::debug!(
+                event_count = self.events.len(),
+                "Dropping events since the server paused submissions"
+            );
+            self.metrics
+                .dropped_events
+                .fetch_add(self.events.len() as u64, Ordering::Relaxed);
+            self.events.clear();
+            self.metrics.buffered_events.store(0, Ordering::Relaxed);
+        }
+
+        let batch = Batch::new(&self.events);
+        if batch.is_empty() {
             return;
         }
 
-        let batch = Batch {
-            sent_at: {
-                let now: chrono::DateTime<chrono::Utc> = std::time::SystemTime::now().into();
-                now.to_rfc3339()
-            },
-            batch: &self.events,
-        };
+        let batches = batch.split(self.effective_max_batch_size().unwrap_or(usize::MAX));
 
-        tracing::trace!(?batch, "Submitting batch");
+        let mut submitted = 0;
+        for batch in batches {
+            let len = batch.len();
 
-        match self.transport.submit(batch).await {
-            Ok(_) => {
-                tracing::trace!("submitted events");
-                self.events.truncate(0);
-            }
-            Err(e) => {
-                tracing::debug!(?e, "submission error");
+            tracing::debug!(
+                batch_size_bytes = batch.size_bytes_estimate(),
+                event_count = len,
+                "Submitting batch"
+            );
+            tracing::trace!(?batch, "Submitting batch");
+
+            match self.transport.submit(batch).await {
+                Ok(_) => {
+                    tracing::trace!("submitted events");
+                    submitted += len;
+                    self.metrics
+                        .submitted_events
+                        .fetch_add(len as u64, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    tracing::debug!(?e, "submission error");
+                    self.metrics.failed_batches.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
             }
         }
+
+        self.events.drain(..submitted);
+        self.metrics
+            .buffered_events
+            .store(self.events.len(), Ordering::Relaxed);
+    }
+}
+
+fn min_limit(server: Option<usize>, builder: Option<usize>) -> Option<usize> {
+    match (server, builder) {
+        (Some(server), Some(builder)) => Some(server.min(builder)),
+        (server, builder) => server.or(builder),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Batch, Pause, PauseSwitch, Submitter, SubmitterMetrics};
+    use crate::collator::Event;
+    use crate::test::recording_transport::RecordingTransport;
+
+    fn submitter(
+        max_batch_size: Option<usize>,
+    ) -> (Submitter<RecordingTransport>, RecordingTransport) {
+        submitter_with_limits(max_batch_size, None)
+    }
+
+    fn submitter_with_limits(
+        max_batch_size: Option<usize>,
+        max_pending_events: Option<usize>,
+    ) -> (Submitter<RecordingTransport>, RecordingTransport) {
+        let transport = RecordingTransport::new();
+        let (_, incoming) = tokio::sync::mpsc::channel(1);
+
+        (
+            Submitter::new(
+                transport.clone(),
+                incoming,
+                max_batch_size,
+                max_pending_events,
+                super::DEFAULT_FLUSH_INTERVAL,
+                Duration::ZERO,
+                SubmitterMetrics::default(),
+                PauseSwitch::default(),
+                tokio::sync::watch::channel(Default::default()).1,
+            ),
+            transport,
+        )
+    }
+
+    #[tokio::test]
+    async fn metrics_count_successes_and_failures() {
+        let (mut submitter, transport) = submitter(Some(3));
+        let metrics = submitter.metrics.clone();
+
+        submitter
+            .events
+            .extend((0..5).map(|i| Event::for_test(format!("{i}"))));
+        submitter.try_flush().await;
+
+        assert_eq!(metrics.submitted_event_count(), 5);
+        assert_eq!(metrics.failed_batch_count(), 0);
+        assert_eq!(metrics.buffered_event_count(), 0);
+
+        transport.set_fail_submissions(true);
+        submitter
+            .events
+            .extend((0..4).map(|i| Event::for_test(format!("{i}"))));
+        submitter.try_flush().await;
+        submitter.try_flush().await;
+
+        assert_eq!(metrics.submitted_event_count(), 5);
+        assert_eq!(metrics.failed_batch_count(), 2);
+        assert_eq!(metrics.buffered_event_count(), 4);
+
+        transport.set_fail_submissions(false);
+        submitter.try_flush().await;
+
+        assert_eq!(metrics.submitted_event_count(), 9);
+        assert_eq!(metrics.failed_batch_count(), 2);
+        assert_eq!(metrics.buffered_event_count(), 0);
+        assert_eq!(transport.events().await.len(), 9);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_flushes_drop_queued_events() {
+        let (mut submitter, transport) = submitter(None);
+        let metrics = submitter.metrics.clone();
+
+        submitter.pause.set(Some(Pause::Until(
+            tokio::time::Instant::now() + std::time::Duration::from_secs(60),
+        )));
+        submitter
+            .events
+            .extend((0..3).map(|i| Event::for_test(format!("{i}"))));
+        submitter.try_flush().await;
+
+        assert_eq!(metrics.dropped_event_count(), 3);
+        assert_eq!(metrics.buffered_event_count(), 0);
+        assert!(transport.events().await.is_empty());
+
+        tokio::time::advance(std::time::Duration::from_secs(61)).await;
+        submitter.events.push(Event::for_test("after".to_string()));
+        submitter.try_flush().await;
+
+        assert_eq!(metrics.submitted_event_count(), 1);
+        assert_eq!(metrics.dropped_event_count(), 3);
+    }
+
+    fn pending_event_names<T: crate::transport::Transport>(submitter: &Submitter<T>) -> Vec<&str> {
+        submitter.events.iter().map(Event::name).collect()
+    }
+
+    #[test]
+    fn pending_limit_drops_the_oldest_events() {
+        let (mut submitter, _) = submitter_with_limits(None, Some(2));
+        let metrics = submitter.metrics.clone();
+
+        for name in ["first", "second", "third"] {
+            submitter.events.push(Event::for_test(name));
+            submitter.enforce_pending_limit();
+        }
+
+        assert_eq!(pending_event_names(&submitter), ["second", "third"]);
+        assert_eq!(metrics.dropped_event_count(), 1);
+        assert_eq!(metrics.buffered_event_count(), 2);
+    }
+
+    #[test]
+    fn pending_limit_keeps_identity_changes() {
+        let (mut submitter, _) = submitter_with_limits(None, Some(2));
+        let metrics = submitter.metrics.clone();
+
+        for name in ["$identify", "first", "second"] {
+            submitter.events.push(Event::for_test(name));
+            submitter.enforce_pending_limit();
+        }
+
+        assert_eq!(pending_event_names(&submitter), ["$identify", "second"]);
+        assert_eq!(metrics.dropped_event_count(), 1);
+
+        // Identity changes go beyond the limit, and push out every other event
+        for name in ["$create_alias", "$create_alias", "third"] {
+            submitter.events.push(Event::for_test(name));
+            submitter.enforce_pending_limit();
+        }
+
+        assert_eq!(
+            pending_event_names(&submitter),
+            ["$identify", "$create_alias", "$create_alias"]
+        );
+        assert_eq!(metrics.dropped_event_count(), 3);
+    }
+
+    #[test]
+    fn split_into_chunks() {
+        let events: Vec<Event> = (0..10).map(|i| Event::for_test(format!("{i}"))).collect();
+        let batch = Batch::new(&events);
+
+        let batches = batch.split(3);
+
+        assert_eq!(
+            batches.iter().map(Batch::len).collect::<Vec<_>>(),
+            vec![3, 3, 3, 1]
+        );
+        assert!(batches.iter().all(|b| b.sent_at == batch.sent_at));
+    }
+
+    #[test]
+    fn split_larger_than_batch() {
+        let events: Vec<Event> = (0..10).map(|i| Event::for_test(format!("{i}"))).collect();
+        let batch = Batch::new(&events);
+
+        assert_eq!(batch.split(usize::MAX).len(), 1);
+        assert_eq!(batch.split(10).len(), 1);
+    }
+
+    #[test]
+    fn size_bytes_estimate_is_plausible() {
+        let events: Vec<Event> = (0..10).map(|i| Event::for_test(format!("{i}"))).collect();
+
+        let empty = Batch::new(&[]).size_bytes_estimate();
+        let one = Batch::new(&events[..1]).size_bytes_estimate();
+        let ten = Batch::new(&events).size_bytes_estimate();
+
+        // `{"sent_at":"<rfc3339>","batch":[]}`
+        assert!((40..100).contains(&empty), "{empty}");
+        assert!(one > empty + 200, "{one}");
+        assert!(ten > one * 9 && ten < one * 11, "{ten}");
+    }
+
+    #[test]
+    fn split_empty() {
+        let batch = Batch::new(&[]);
+
+        assert!(batch.is_empty());
+        assert!(batch.split(3).is_empty());
     }
 }
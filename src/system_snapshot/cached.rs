@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::system_snapshot::{SystemSnapshot, SystemSnapshotter};
+
+/// Reuses another snapshotter's last snapshot until it is `ttl` old.
+///
+/// Clones share the cache, so a clone kept after handing this to the Builder can still `invalidate` it.
+pub struct Cached<S> {
+    inner: Arc<S>,
+    ttl: Duration,
+    last: Arc<Mutex<Option<(Instant, SystemSnapshot)>>>,
+}
+
+impl<S: SystemSnapshotter> Cached<S> {
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            ttl,
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Take a fresh snapshot the next time one is needed.
+    pub fn invalidate(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = None;
+        }
+    }
+
+    fn cached(&self) -> Option<SystemSnapshot> {
+        let last = self.last.lock().ok()?;
+        let (taken_at, ref snapshot) = *last.as_ref()?;
+
+        (taken_at.elapsed() < self.ttl).then(|| snapshot.clone())
+    }
+}
+
+impl<S> Clone for Cached<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            ttl: self.ttl,
+            last: self.last.clone(),
+        }
+    }
+}
+
+impl<S: SystemSnapshotter> SystemSnapshotter for Cached<S> {
+    async fn snapshot(&self) -> SystemSnapshot {
+        if let Some(snapshot) = self.cached() {
+            return snapshot;
+        }
+
+        let snapshot = self.inner.snapshot().await;
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some((Instant::now(), snapshot.clone()));
+        }
+
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::Cached;
+    use crate::system_snapshot::{SystemSnapshot, SystemSnapshotter};
+
+    #[derive(Clone, Default)]
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SystemSnapshotter for Counting {
+        async fn snapshot(&self) -> SystemSnapshot {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+            SystemSnapshot {
+                extra_fields: Some(crate::Map::from_iter([("calls".into(), calls.into())])),
+                ..SystemSnapshot::for_disk_paths(&[])
+            }
+        }
+    }
+
+    fn calls(snapshot: &SystemSnapshot) -> &serde_json::Value {
+        &snapshot.extra_fields.as_ref().unwrap()["calls"]
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reuses_snapshots_until_the_ttl() {
+        let counting = Counting::default();
+        let cached = Cached::new(counting.clone(), Duration::from_secs(60));
+
+        assert_eq!(calls(&cached.snapshot().await), 1);
+        assert_eq!(calls(&cached.snapshot().await), 1);
+
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert_eq!(calls(&cached.snapshot().await), 1);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(calls(&cached.snapshot().await), 2);
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clones_share_invalidation() {
+        let counting = Counting::default();
+        let cached = Cached::new(counting.clone(), Duration::from_secs(60));
+        let handle = cached.clone();
+
+        assert_eq!(calls(&cached.snapshot().await), 1);
+
+        handle.invalidate();
+        assert_eq!(calls(&cached.snapshot().await), 2);
+        assert_eq!(calls(&handle.snapshot().await), 2);
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+    }
+}
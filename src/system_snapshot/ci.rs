@@ -0,0 +1,183 @@
+use super::probe::Probe;
+
+/// A CI provider, recognized by an environment variable it always sets.
+struct Provider {
+    name: &'static str,
+    variable: &'static str,
+    /// A variable naming the runner's OS image, where the provider sets one.
+    image_variable: Option<&'static str>,
+}
+
+/// Checked in order, so providers which also set another provider's variable come first.
+const PROVIDERS: &[Provider] = &[
+    Provider {
+        name: "github-actions",
+        variable: "GITHUB_ACTIONS",
+        image_variable: Some("ImageOS"),
+    },
+    Provider {
+        name: "gitlab",
+        variable: "GITLAB_CI",
+        image_variable: None,
+    },
+    Provider {
+        name: "buildkite",
+        variable: "BUILDKITE",
+        image_variable: None,
+    },
+    Provider {
+        name: "circleci",
+        variable: "CIRCLECI",
+        image_variable: None,
+    },
+    Provider {
+        name: "azure-pipelines",
+        variable: "TF_BUILD",
+        image_variable: Some("ImageOS"),
+    },
+    Provider {
+        name: "travis",
+        variable: "TRAVIS",
+        image_variable: Some("TRAVIS_DIST"),
+    },
+    Provider {
+        name: "bitbucket-pipelines",
+        variable: "BITBUCKET_BUILD_NUMBER",
+        image_variable: None,
+    },
+    Provider {
+        name: "aws-codebuild",
+        variable: "CODEBUILD_BUILD_ID",
+        image_variable: None,
+    },
+    Provider {
+        name: "teamcity",
+        variable: "TEAMCITY_VERSION",
+        image_variable: None,
+    },
+    Provider {
+        name: "drone",
+        variable: "DRONE",
+        image_variable: None,
+    },
+    // Plugins for other providers can set JENKINS_URL, so it's checked last
+    Provider {
+        name: "jenkins",
+        variable: "JENKINS_URL",
+        image_variable: None,
+    },
+];
+
+/// The CI provider running this process, and the runner's OS image where it says.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CiProvider {
+    pub(crate) name: Option<String>,
+    pub(crate) runner_os_image: Option<String>,
+}
+
+pub(crate) fn current() -> CiProvider {
+    detect(&super::probe::Host)
+}
+
+/// Find the first provider whose variable is set.
+pub(crate) fn detect(probe: &impl Probe) -> CiProvider {
+    let set = |name: &str| {
+        probe
+            .var(name)
+            .filter(|value| !value.is_empty() && value != "false")
+    };
+
+    let Some(provider) = PROVIDERS
+        .iter()
+        .find(|provider| set(provider.variable).is_some())
+    else {
+        return CiProvider::default();
+    };
+
+    CiProvider {
+        name: Some(provider.name.into()),
+        runner_os_image: provider.image_variable.and_then(set),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::{CiProvider, PROVIDERS, detect};
+
+    fn ci(env: &[(&str, &str)]) -> CiProvider {
+        let probe = env.iter().fold(Fixture::default(), |probe, (name, value)| {
+            probe.with_var(name, value)
+        });
+
+        detect(&probe)
+    }
+
+    fn named(name: &str) -> CiProvider {
+        CiProvider {
+            name: Some(name.into()),
+            runner_os_image: None,
+        }
+    }
+
+    #[test]
+    fn no_ci() {
+        assert_eq!(ci(&[]), CiProvider::default());
+        assert_eq!(ci(&[("HOME", "/root")]), CiProvider::default());
+    }
+
+    #[test]
+    fn each_provider() {
+        for (variable, value, name) in [
+            ("GITHUB_ACTIONS", "true", "github-actions"),
+            ("GITLAB_CI", "true", "gitlab"),
+            ("BUILDKITE", "true", "buildkite"),
+            ("CIRCLECI", "true", "circleci"),
+            ("TF_BUILD", "True", "azure-pipelines"),
+            ("TRAVIS", "true", "travis"),
+            ("BITBUCKET_BUILD_NUMBER", "42", "bitbucket-pipelines"),
+            ("CODEBUILD_BUILD_ID", "project:0d8a", "aws-codebuild"),
+            ("TEAMCITY_VERSION", "2024.03", "teamcity"),
+            ("DRONE", "true", "drone"),
+            ("JENKINS_URL", "https://jenkins.example.com/", "jenkins"),
+        ] {
+            assert_eq!(ci(&[(variable, value)]), named(name), "{variable}");
+        }
+
+        assert_eq!(PROVIDERS.len(), 11, "every provider is tested");
+    }
+
+    #[test]
+    fn unset_values_are_ignored() {
+        assert_eq!(ci(&[("GITHUB_ACTIONS", "")]), CiProvider::default());
+        assert_eq!(ci(&[("CIRCLECI", "false")]), CiProvider::default());
+    }
+
+    #[test]
+    fn runner_images() {
+        assert_eq!(
+            ci(&[("GITHUB_ACTIONS", "true"), ("ImageOS", "ubuntu24")]),
+            CiProvider {
+                name: Some("github-actions".into()),
+                runner_os_image: Some("ubuntu24".into()),
+            }
+        );
+
+        // Only the detected provider's image variable counts
+        assert_eq!(
+            ci(&[("GITLAB_CI", "true"), ("ImageOS", "ubuntu24")]),
+            named("gitlab")
+        );
+    }
+
+    #[test]
+    fn jenkins_is_checked_last() {
+        assert_eq!(
+            ci(&[
+                ("JENKINS_URL", "https://jenkins.example.com/"),
+                ("GITHUB_ACTIONS", "true"),
+            ]),
+            named("github-actions")
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use super::probe::Probe;
+
+/// The container runtime this process is running under, if any.
+#[cfg(target_os = "linux")]
+pub(crate) fn current_runtime() -> Option<String> {
+    detect(&super::probe::Host)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn current_runtime() -> Option<String> {
+    None
+}
+
+/// Name the container runtime from the marks each leaves on the machine, preferring the most specific.
+///
+/// Kubernetes sets `KUBERNETES_SERVICE_HOST` in every pod, systemd-nspawn and other managers write their name to `/run/systemd/container`, Podman creates `/run/.containerenv`, and Docker creates `/.dockerenv`.
+/// Failing those, PID 1's cgroup usually names the runtime.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn detect(probe: &impl Probe) -> Option<String> {
+    if probe
+        .var("KUBERNETES_SERVICE_HOST")
+        .is_some_and(|host| !host.is_empty())
+    {
+        return Some("kubernetes".into());
+    }
+
+    if let Some(name) = probe
+        .read(Path::new("/run/systemd/container"))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+    {
+        return Some(name);
+    }
+
+    if probe.exists(Path::new("/run/.containerenv")) {
+        return Some("podman".into());
+    }
+
+    if probe.exists(Path::new("/.dockerenv")) {
+        return Some("docker".into());
+    }
+
+    let cgroup = probe.read(Path::new("/proc/1/cgroup"))?;
+    [
+        ("kubepods", "kubernetes"),
+        ("libpod", "podman"),
+        ("docker", "docker"),
+        ("containerd", "containerd"),
+        ("lxc", "lxc"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| cgroup.contains(marker))
+    .map(|(_, runtime)| runtime.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::detect;
+
+    const HOST_CGROUP_V1: &str =
+        "12:pids:/init.scope\n11:memory:/init.scope\n1:name=systemd:/init.scope\n";
+    const HOST_CGROUP_V2: &str = "0::/init.scope\n";
+    const DOCKER_CGROUP: &str =
+        "12:pids:/docker/3f1c6e2c1a9b\n1:name=systemd:/docker/3f1c6e2c1a9b\n";
+    const PODMAN_CGROUP: &str = "0::/machine.slice/libpod-8d2c1f0e4b.scope/container\n";
+    const KUBERNETES_CGROUP: &str = "12:pids:/kubepods/besteffort/pod5a6b/0e3f1d\n1:name=systemd:/kubepods/besteffort/pod5a6b/0e3f1d\n";
+    const LXC_CGROUP: &str = "12:pids:/lxc/builder\n";
+
+    fn cgroup(contents: &str) -> Fixture {
+        Fixture::default().with_file("/proc/1/cgroup", contents)
+    }
+
+    #[test]
+    fn hosts_are_not_containers() {
+        assert_eq!(detect(&Fixture::default()), None);
+        assert_eq!(detect(&cgroup(HOST_CGROUP_V1)), None);
+        assert_eq!(detect(&cgroup(HOST_CGROUP_V2)), None);
+    }
+
+    #[test]
+    fn cgroups() {
+        for (contents, runtime) in [
+            (DOCKER_CGROUP, "docker"),
+            (PODMAN_CGROUP, "podman"),
+            (KUBERNETES_CGROUP, "kubernetes"),
+            (LXC_CGROUP, "lxc"),
+        ] {
+            assert_eq!(detect(&cgroup(contents)).as_deref(), Some(runtime));
+        }
+    }
+
+    #[test]
+    fn dockerenv() {
+        let probe = cgroup(HOST_CGROUP_V2).with_file("/.dockerenv", "");
+        assert_eq!(detect(&probe).as_deref(), Some("docker"));
+    }
+
+    #[test]
+    fn containerenv() {
+        let probe =
+            cgroup(HOST_CGROUP_V2).with_file("/run/.containerenv", "engine=\"podman-4.9.3\"\n");
+        assert_eq!(detect(&probe).as_deref(), Some("podman"));
+
+        let empty = Fixture::default().with_file("/run/.containerenv", "");
+        assert_eq!(detect(&empty).as_deref(), Some("podman"));
+    }
+
+    #[test]
+    fn systemd_container() {
+        let probe = cgroup(HOST_CGROUP_V2).with_file("/run/systemd/container", "systemd-nspawn\n");
+        assert_eq!(detect(&probe).as_deref(), Some("systemd-nspawn"));
+
+        let blank = Fixture::default().with_file("/run/systemd/container", "\n");
+        assert_eq!(detect(&blank), None);
+    }
+
+    #[test]
+    fn kubernetes_service_host() {
+        let probe = cgroup(DOCKER_CGROUP)
+            .with_file("/.dockerenv", "")
+            .with_var("KUBERNETES_SERVICE_HOST", "10.96.0.1");
+        assert_eq!(detect(&probe).as_deref(), Some("kubernetes"));
+
+        let empty = Fixture::default().with_var("KUBERNETES_SERVICE_HOST", "");
+        assert_eq!(detect(&empty), None);
+    }
+}
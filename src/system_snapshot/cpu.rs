@@ -0,0 +1,100 @@
+use std::sync::OnceLock;
+
+use sysinfo::System;
+
+/// Facts about the CPU, which don't change while the process runs.
+#[derive(Debug)]
+pub(crate) struct CpuInfo {
+    pub(crate) brand: Option<String>,
+    pub(crate) arch: &'static str,
+    pub(crate) logical_core_count: Option<u64>,
+    pub(crate) features: Option<Vec<String>>,
+}
+
+/// The CPU facts, read from `system` the first time and cached afterwards.
+pub(crate) fn current(system: &System) -> &'static CpuInfo {
+    static CPU: OnceLock<CpuInfo> = OnceLock::new();
+
+    CPU.get_or_init(|| CpuInfo {
+        brand: system
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string())
+            .filter(|brand| !brand.is_empty()),
+        arch: std::env::consts::ARCH,
+        logical_core_count: Some(system.cpus().len() as u64).filter(|count| *count > 0),
+        features: features(),
+    })
+}
+
+/// Which of the instruction set extensions that matter for performance the CPU supports.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn features() -> Option<Vec<String>> {
+    let detected = [
+        ("sse4.2", std::arch::is_x86_feature_detected!("sse4.2")),
+        ("popcnt", std::arch::is_x86_feature_detected!("popcnt")),
+        ("aes", std::arch::is_x86_feature_detected!("aes")),
+        ("sha", std::arch::is_x86_feature_detected!("sha")),
+        ("avx", std::arch::is_x86_feature_detected!("avx")),
+        ("avx2", std::arch::is_x86_feature_detected!("avx2")),
+        ("fma", std::arch::is_x86_feature_detected!("fma")),
+        ("bmi2", std::arch::is_x86_feature_detected!("bmi2")),
+        ("avx512f", std::arch::is_x86_feature_detected!("avx512f")),
+    ];
+
+    Some(
+        detected
+            .into_iter()
+            .filter(|(_, detected)| *detected)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+    )
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn features() -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::system_snapshot::SystemSnapshot;
+
+    #[test]
+    fn host_fields_populate() {
+        let snapshot = SystemSnapshot::default();
+
+        assert_eq!(snapshot.cpu_arch, std::env::consts::ARCH);
+        assert!(snapshot.logical_core_count.is_some_and(|count| count > 0));
+        assert_eq!(
+            snapshot.cpu_features.is_some(),
+            cfg!(any(target_arch = "x86", target_arch = "x86_64"))
+        );
+    }
+
+    #[test]
+    fn serialization_shape() {
+        let snapshot = SystemSnapshot {
+            cpu_brand: Some("Apple M2 Pro".into()),
+            cpu_arch: "aarch64".into(),
+            logical_core_count: Some(12),
+            cpu_features: None,
+            ..SystemSnapshot::default()
+        };
+        let json = serde_json::to_value(&snapshot).unwrap();
+
+        assert_eq!(json["cpu_brand"], "Apple M2 Pro");
+        assert_eq!(json["cpu_arch"], "aarch64");
+        assert_eq!(json["logical_core_count"], 12);
+        assert!(json.get("cpu_features").is_none());
+
+        let x86 = SystemSnapshot {
+            cpu_features: Some(vec!["avx2".into(), "avx512f".into()]),
+            ..snapshot
+        };
+        assert_eq!(
+            serde_json::to_value(&x86).unwrap()["cpu_features"],
+            serde_json::json!(["avx2", "avx512f"])
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use sysinfo::Disks;
+
+/// The size and free space of the disk holding a path.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct DiskSpace {
+    /// The path as configured. Example: `/nix`
+    pub path: PathBuf,
+
+    /// Example: `994662584320`
+    pub disk_total_bytes: u64,
+
+    /// Example: `512110190592`
+    pub disk_available_bytes: u64,
+}
+
+/// A mounted disk, as reported by sysinfo.
+#[derive(Debug)]
+pub(crate) struct Mount {
+    pub(crate) mount_point: PathBuf,
+    pub(crate) total_bytes: u64,
+    pub(crate) available_bytes: u64,
+}
+
+/// `/nix` if it exists, else `/`.
+pub(crate) fn default_paths() -> Vec<PathBuf> {
+    let nix = Path::new("/nix");
+
+    if nix.exists() {
+        vec![nix.into()]
+    } else {
+        vec!["/".into()]
+    }
+}
+
+/// The space on the disks holding each of `paths`.
+pub(crate) fn current(paths: &[PathBuf]) -> Vec<DiskSpace> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+
+    let mounts = Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|disk| Mount {
+            mount_point: disk.mount_point().to_path_buf(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+        })
+        .collect::<Vec<_>>();
+
+    select(paths, &mounts)
+}
+
+/// Match each path to the mount with the longest mount point containing it, skipping paths that don't exist.
+pub(crate) fn select(paths: &[PathBuf], mounts: &[Mount]) -> Vec<DiskSpace> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let canonical = match path.canonicalize() {
+                Ok(canonical) => canonical,
+                Err(e) => {
+                    tracing::trace!(?path, %e, "Skipping disk space for a path that can't be resolved");
+                    return None;
+                }
+            };
+
+            let mount = mounts
+                .iter()
+                .filter(|mount| canonical.starts_with(&mount.mount_point))
+                .max_by_key(|mount| mount.mount_point.components().count())?;
+
+            Some(DiskSpace {
+                path: path.clone(),
+                disk_total_bytes: mount.total_bytes,
+                disk_available_bytes: mount.available_bytes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{DiskSpace, Mount, select};
+    use crate::system_snapshot::SystemSnapshot;
+
+    fn mount(mount_point: impl Into<PathBuf>, total_bytes: u64) -> Mount {
+        Mount {
+            mount_point: mount_point.into(),
+            total_bytes,
+            available_bytes: total_bytes / 2,
+        }
+    }
+
+    #[test]
+    fn longest_mount_point_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let nested = root.join("nix");
+        std::fs::create_dir(&nested).unwrap();
+
+        let mounts = [mount("/", 100), mount(&nested, 20), mount(&root, 50)];
+
+        assert_eq!(
+            select(&[nested.clone(), root.join(".")], &mounts),
+            vec![
+                DiskSpace {
+                    path: nested,
+                    disk_total_bytes: 20,
+                    disk_available_bytes: 10,
+                },
+                DiskSpace {
+                    path: root.join("."),
+                    disk_total_bytes: 50,
+                    disk_available_bytes: 25,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_paths_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+
+        assert_eq!(
+            select(&[missing, dir.path().into()], &[mount("/", 100)]),
+            vec![DiskSpace {
+                path: dir.path().into(),
+                disk_total_bytes: 100,
+                disk_available_bytes: 50,
+            }]
+        );
+    }
+
+    #[test]
+    fn paths_without_a_mount_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(select(&[dir.path().into()], &[mount("/nonexistent-mount", 100)]).is_empty());
+    }
+
+    #[test]
+    fn serialization_shape() {
+        let snapshot = SystemSnapshot {
+            disks: vec![DiskSpace {
+                path: "/nix".into(),
+                disk_total_bytes: 100,
+                disk_available_bytes: 40,
+            }],
+            ..SystemSnapshot::for_disk_paths(&[])
+        };
+
+        assert_eq!(
+            serde_json::to_value(&snapshot).unwrap()["disks"],
+            serde_json::json!([{
+                "path": "/nix",
+                "disk_total_bytes": 100,
+                "disk_available_bytes": 40,
+            }])
+        );
+
+        let without = SystemSnapshot::for_disk_paths(&[]);
+        assert!(without.disks.is_empty());
+        assert!(
+            serde_json::to_value(&without)
+                .unwrap()
+                .get("disks")
+                .is_none()
+        );
+    }
+}
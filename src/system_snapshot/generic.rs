@@ -1,11 +1,55 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::Map;
 use crate::system_snapshot::{SystemSnapshot, SystemSnapshotter};
 
+type ExtraFieldsProvider = Arc<dyn Fn(&mut Map) + Send + Sync>;
+
 #[derive(Default)]
-pub struct Generic {}
+pub struct Generic {
+    disk_paths: Option<Vec<PathBuf>>,
+    extra: Vec<ExtraFieldsProvider>,
+}
+
+impl Generic {
+    /// Report the space on the disks holding these paths, instead of `/nix` if it exists or else `/`.
+    /// Paths that don't exist are skipped.
+    pub fn with_disk_paths(disk_paths: Option<Vec<PathBuf>>) -> Self {
+        Self {
+            disk_paths,
+            ..Self::default()
+        }
+    }
+
+    /// Add fields to every snapshot's `extra_fields` by calling `provider`.
+    /// Providers run in the order they were added, so later ones can replace earlier fields.
+    pub fn with_extra(mut self, provider: impl Fn(&mut Map) + Send + Sync + 'static) -> Self {
+        self.extra.push(Arc::new(provider));
+        self
+    }
+
+    /// Add the same fields to every snapshot's `extra_fields`.
+    pub fn with_fields(self, fields: Map) -> Self {
+        self.with_extra(move |extra| extra.extend(fields.clone()))
+    }
+}
 
 impl SystemSnapshotter for Generic {
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     async fn snapshot(&self) -> SystemSnapshot {
-        SystemSnapshot::default()
+        let mut snapshot = match self.disk_paths {
+            Some(ref disk_paths) => SystemSnapshot::for_disk_paths(disk_paths),
+            None => SystemSnapshot::default(),
+        };
+
+        if !self.extra.is_empty() {
+            let extra_fields = snapshot.extra_fields.get_or_insert_with(Map::new);
+            for provider in &self.extra {
+                provider(extra_fields);
+            }
+        }
+
+        snapshot
     }
 }
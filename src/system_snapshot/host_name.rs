@@ -0,0 +1,96 @@
+use sha2::Digest;
+
+use crate::system_snapshot::SystemSnapshot;
+
+/// How many bytes of the salted digest a hashed host name keeps.
+const HASHED_HOST_NAME_BYTES: usize = 8;
+
+/// How the host name is reported in system snapshots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HostNamePolicy {
+    /// Report the host name as it is.
+    Raw,
+
+    /// Report a truncated SHA-256 of the host name salted with the device ID, so it's stable on one machine but can't be joined across machines.
+    #[default]
+    Hashed,
+
+    /// Leave the host name out.
+    Omit,
+}
+
+impl HostNamePolicy {
+    pub(crate) fn apply(self, snapshot: &mut SystemSnapshot, device_id: &str) {
+        match self {
+            Self::Raw => {}
+            Self::Hashed => {
+                snapshot.host_name = snapshot
+                    .host_name
+                    .as_deref()
+                    .map(|host_name| hash(host_name, device_id));
+            }
+            Self::Omit => snapshot.host_name = None,
+        }
+    }
+}
+
+fn hash(host_name: &str, salt: &str) -> String {
+    let digest = sha2::Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update([0])
+        .chain_update(host_name.as_bytes())
+        .finalize();
+
+    digest[..HASHED_HOST_NAME_BYTES]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::HostNamePolicy;
+    use crate::system_snapshot::SystemSnapshot;
+
+    fn host_name(policy: HostNamePolicy, device_id: &str) -> serde_json::Value {
+        let mut snapshot = SystemSnapshot {
+            host_name: Some("grahams-macbook-pro.local".into()),
+            ..SystemSnapshot::default()
+        };
+        policy.apply(&mut snapshot, device_id);
+
+        serde_json::to_value(&snapshot).unwrap()["host_name"].clone()
+    }
+
+    #[test]
+    fn raw() {
+        assert_eq!(
+            host_name(HostNamePolicy::Raw, "device"),
+            "grahams-macbook-pro.local"
+        );
+    }
+
+    #[test]
+    fn hashed() {
+        let hashed = host_name(HostNamePolicy::Hashed, "device");
+        let hashed = hashed.as_str().unwrap();
+
+        assert_eq!(hashed.len(), 16);
+        assert!(hashed.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(host_name(HostNamePolicy::Hashed, "device"), hashed);
+        assert_ne!(host_name(HostNamePolicy::Hashed, "another-device"), hashed);
+    }
+
+    #[test]
+    fn omit() {
+        assert_eq!(
+            host_name(HostNamePolicy::Omit, "device"),
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn hashed_is_the_default() {
+        assert_eq!(HostNamePolicy::default(), HostNamePolicy::Hashed);
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use super::probe::Probe;
+
+/// The init system managing this machine, if it could be told.
+#[cfg(target_os = "linux")]
+pub(crate) fn current() -> Option<String> {
+    detect(&super::probe::Host)
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn current() -> Option<String> {
+    Some("launchd".into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn current() -> Option<String> {
+    None
+}
+
+/// Name the init system from the markers each one leaves in `/run`, falling back to the name of PID 1.
+///
+/// Processes started directly as PID 1, like a shell or `tini` in a minimal container, are reported as `none`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn detect(probe: &impl Probe) -> Option<String> {
+    for (marker, init) in [
+        ("/run/systemd/system", "systemd"),
+        ("/run/openrc", "openrc"),
+        ("/run/runit", "runit"),
+        ("/run/s6", "s6"),
+    ] {
+        if probe.exists(Path::new(marker)) {
+            return Some(init.into());
+        }
+    }
+
+    let pid1 = probe.read(Path::new("/proc/1/comm"))?;
+    let init = match pid1.trim() {
+        "" => return None,
+        "systemd" => "systemd",
+        "openrc-init" => "openrc",
+        "runit" => "runit",
+        "s6-svscan" => "s6",
+        "init" if probe.exists(Path::new("/etc/inittab")) => "sysvinit",
+        // Busybox and others call their init `init` too
+        "init" => return None,
+        _ => "none",
+    };
+
+    Some(init.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::detect;
+
+    fn pid1(comm: &str) -> Fixture {
+        Fixture::default().with_file("/proc/1/comm", &format!("{comm}\n"))
+    }
+
+    fn init(fixture: Fixture) -> Option<String> {
+        detect(&fixture)
+    }
+
+    #[test]
+    fn run_markers() {
+        for (marker, expected) in [
+            ("/run/systemd/system/session.slice", "systemd"),
+            ("/run/openrc/softlevel", "openrc"),
+            ("/run/runit/stopit", "runit"),
+            ("/run/s6/basedir", "s6"),
+        ] {
+            let fixture = pid1("init").with_file(marker, "");
+            assert_eq!(init(fixture).as_deref(), Some(expected), "{marker}");
+        }
+    }
+
+    #[test]
+    fn systemd_outside_pid1_is_not_the_init() {
+        // systemd's runtime directory without its `system` marker, as in a container sharing `/run`
+        let fixture = pid1("bash").with_file("/run/systemd/journal/socket", "");
+        assert_eq!(init(fixture).as_deref(), Some("none"));
+    }
+
+    #[test]
+    fn pid1_names() {
+        for (comm, expected) in [
+            ("systemd", Some("systemd")),
+            ("openrc-init", Some("openrc")),
+            ("runit", Some("runit")),
+            ("s6-svscan", Some("s6")),
+            ("init", None),
+            ("tini", Some("none")),
+            ("sh", Some("none")),
+        ] {
+            assert_eq!(init(pid1(comm)).as_deref(), expected, "{comm}");
+        }
+    }
+
+    #[test]
+    fn sysvinit_has_an_inittab() {
+        let fixture = pid1("init").with_file("/etc/inittab", "id:3:initdefault:\n");
+        assert_eq!(init(fixture).as_deref(), Some("sysvinit"));
+    }
+
+    #[test]
+    fn unknown_without_proc() {
+        assert_eq!(init(Fixture::default()), None);
+        assert_eq!(init(pid1("")), None);
+    }
+}
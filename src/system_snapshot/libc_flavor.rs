@@ -0,0 +1,132 @@
+// Detection only runs on Linux
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use super::probe::Probe;
+
+/// The libc this process uses, on Linux. Detected once per process.
+#[cfg(target_os = "linux")]
+pub(crate) fn current() -> Option<String> {
+    static LIBC: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+
+    LIBC.get_or_init(|| detect(&super::probe::Host)).clone()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn current() -> Option<String> {
+    None
+}
+
+/// Name the libc and its version, like `glibc 2.39` or `musl`.
+///
+/// Binaries linked against glibc ask it directly, and musl binaries are told by their target triple.
+/// Anything else falls back to asking `confstr`, and then `ldd --version`.
+pub(crate) fn detect(probe: &impl Probe) -> Option<String> {
+    if let Some(version) = probe.gnu_get_libc_version().filter(|v| !v.is_empty()) {
+        return Some(format!("glibc {version}"));
+    }
+
+    if probe.target_env() == "musl" {
+        return Some("musl".into());
+    }
+
+    if let Some(version) = probe
+        .confstr_libc_version()
+        .and_then(|s| s.strip_prefix("glibc ").map(str::to_string))
+    {
+        return Some(format!("glibc {version}"));
+    }
+
+    // musl's ldd prints its version to stderr and exits non-zero, so the status is ignored
+    let ldd = probe.run("ldd", &["--version"])?;
+    parse_ldd_version(&format!("{}{}", ldd.stdout, ldd.stderr))
+}
+
+/// Parse `ldd (GNU libc) 2.39` or musl's `musl libc (x86_64)` followed by `Version 1.2.5`.
+fn parse_ldd_version(output: &str) -> Option<String> {
+    let first = output.lines().next()?;
+
+    if first.contains("GNU libc") || first.contains("GLIBC") {
+        let version = first.rsplit(' ').next()?;
+        return looks_like_version(version).then(|| format!("glibc {version}"));
+    }
+
+    if output.contains("musl libc") {
+        let version = output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("Version "))
+            .filter(|version| looks_like_version(version));
+
+        return Some(match version {
+            Some(version) => format!("musl {version}"),
+            None => "musl".into(),
+        });
+    }
+
+    None
+}
+
+fn looks_like_version(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.')
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::detect;
+
+    #[test]
+    fn glibc_is_asked_directly() {
+        let probe = Fixture::default()
+            .with_gnu_get_libc_version("2.39")
+            .with_target_env("gnu")
+            .with_command("ldd", false, "", "musl libc (x86_64)\nVersion 1.2.5\n");
+
+        assert_eq!(detect(&probe).as_deref(), Some("glibc 2.39"));
+    }
+
+    #[test]
+    fn musl_comes_from_the_target() {
+        let probe = Fixture::default().with_target_env("musl").with_command(
+            "ldd",
+            true,
+            "ldd (GNU libc) 2.39\n",
+            "",
+        );
+
+        assert_eq!(detect(&probe).as_deref(), Some("musl"));
+    }
+
+    #[test]
+    fn confstr_is_a_fallback() {
+        let probe = Fixture::default().with_confstr_libc_version("glibc 2.35");
+
+        assert_eq!(detect(&probe).as_deref(), Some("glibc 2.35"));
+    }
+
+    #[test]
+    fn ldd_is_the_last_resort() {
+        let glibc = Fixture::default().with_command(
+            "ldd",
+            true,
+            "ldd (Ubuntu GLIBC 2.39-0ubuntu8.4) 2.39\nCopyright (C) 2024 Free Software Foundation, Inc.\n",
+            "",
+        );
+        assert_eq!(detect(&glibc).as_deref(), Some("glibc 2.39"));
+
+        let musl = Fixture::default().with_command(
+            "ldd",
+            false,
+            "",
+            "musl libc (x86_64)\nVersion 1.2.5\nDynamic Program Loader\n",
+        );
+        assert_eq!(detect(&musl).as_deref(), Some("musl 1.2.5"));
+    }
+
+    #[test]
+    fn unknown_libcs_are_none() {
+        assert_eq!(detect(&Fixture::default()), None);
+
+        let probe = Fixture::default().with_command("ldd", false, "", "ldd: unrecognized option\n");
+        assert_eq!(detect(&probe), None);
+    }
+}
@@ -1,11 +1,33 @@
 use std::io::IsTerminal;
+use std::path::PathBuf;
 
 use sysinfo::System;
 
 use crate::Map;
 
+mod cached;
+mod ci;
+mod container;
+mod cpu;
+mod disk;
 mod generic;
+mod host_name;
+mod init_system;
+mod libc_flavor;
+mod nix;
+mod probe;
+mod rosetta;
+mod sync_snapshotter;
+#[cfg(target_os = "macos")]
+mod sysctl;
+mod virtualization;
+mod wsl;
+pub use cached::Cached;
+pub use disk::DiskSpace;
 pub use generic::Generic;
+pub use host_name::HostNamePolicy;
+pub(crate) use nix::NixVersions;
+pub use sync_snapshotter::SyncSnapshotter;
 
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct SystemSnapshot {
@@ -30,10 +52,41 @@ pub struct SystemSnapshot {
     pub stdin_is_terminal: bool,
     pub is_ci: bool,
 
+    /// The CI provider running the process. Example: `github-actions`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_provider: Option<String>,
+
+    /// The CI runner's OS image, where the provider says. Example: `ubuntu24`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_runner_os_image: Option<String>,
+
     /// Example: `14`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub processor_count: Option<u64>,
 
+    /// Example: `Apple M2 Pro`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_brand: Option<String>,
+
+    /// Example: `aarch64`
+    pub cpu_arch: String,
+
+    /// Whether the process runs under Rosetta 2 translation, on macOS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rosetta_translated: Option<bool>,
+
+    /// The machine's own architecture, even when `cpu_arch` and `target_triple` describe a translated binary, on macOS. Example: `aarch64`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_arch: Option<String>,
+
+    /// Example: `12`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logical_core_count: Option<u64>,
+
+    /// Instruction set extensions the CPU supports, on x86. Example: `["sse4.2", "avx", "avx2"]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_features: Option<Vec<String>>,
+
     /// Example: `38654705664`
     pub physical_memory_bytes: u64,
 
@@ -44,6 +97,32 @@ pub struct SystemSnapshot {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub process_name: Option<String>,
 
+    /// The container runtime the process runs under, detected on Linux. Example: `docker`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_runtime: Option<String>,
+
+    pub in_container: bool,
+
+    /// The version of WSL the process runs under, 1 or 2, detected on Linux.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wsl_version: Option<u8>,
+
+    /// The hypervisor the machine runs under, detected on Linux and macOS. Example: `kvm`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub virtualization: Option<String>,
+
+    /// The C library the process uses, with its version where known, on Linux. Example: `glibc 2.39`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub libc: Option<String>,
+
+    /// The init system managing the machine, or `none` when a process like a shell runs as PID 1. Example: `systemd`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_system: Option<String>,
+
+    /// The size and free space of the disks holding the configured paths, `/nix` if it exists or else `/` by default.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disks: Vec<DiskSpace>,
+
     /// Additional fields to be flattened into the snapshot data
     #[serde(flatten)]
     pub extra_fields: Option<Map>,
@@ -51,10 +130,21 @@ pub struct SystemSnapshot {
 
 impl Default for SystemSnapshot {
     fn default() -> Self {
+        Self::for_disk_paths(&disk::default_paths())
+    }
+}
+
+impl SystemSnapshot {
+    /// Take a snapshot reporting the space on the disks holding `disk_paths`.
+    pub(crate) fn for_disk_paths(disk_paths: &[PathBuf]) -> Self {
         let system = System::new_all();
 
         let is_ci = is_ci::cached()
             || std::env::var("DETSYS_IDS_IN_CI").unwrap_or_else(|_| "0".into()) == "1";
+        let container_runtime = container::current_runtime();
+        let cpu = cpu::current(&system);
+        let rosetta = rosetta::current();
+        let ci = ci::current();
 
         Self {
             locale: sys_locale::get_locale(),
@@ -67,19 +157,38 @@ impl Default for SystemSnapshot {
             target_triple: target_lexicon::HOST.to_string(),
             stdin_is_terminal: std::io::stdin().is_terminal(),
             is_ci,
+            ci_provider: ci.name,
+            ci_runner_os_image: ci.runner_os_image,
 
             processor_count: System::physical_core_count().map(
                 |count| count as u64, /* safety: `as` truncates on overflow */
             ),
+            cpu_brand: cpu.brand.clone(),
+            cpu_arch: cpu.arch.into(),
+            rosetta_translated: rosetta.translated,
+            native_arch: rosetta.native_arch,
+            logical_core_count: cpu.logical_core_count,
+            cpu_features: cpu.features.clone(),
             physical_memory_bytes: system.total_memory(),
             boot_time: System::boot_time(),
             process_name: std::env::args().next(),
+            in_container: container_runtime.is_some(),
+            container_runtime,
+            wsl_version: wsl::current_version(),
+            virtualization: virtualization::current(),
+            libc: libc_flavor::current(),
+            init_system: init_system::current(),
+            disks: disk::current(disk_paths),
 
             extra_fields: None,
         }
     }
 }
 
+/// Takes the system snapshot sent with events and check-ins.
+///
+/// Snapshots are taken asynchronously, so implementations can do I/O such as asking a local daemon.
+/// Wrap synchronous functions with `SyncSnapshotter`.
 pub trait SystemSnapshotter: Send + Sync + 'static {
     fn snapshot(&self) -> impl std::future::Future<Output = SystemSnapshot> + std::marker::Send;
 }
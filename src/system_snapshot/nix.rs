@@ -0,0 +1,176 @@
+use tokio::sync::OnceCell;
+
+use crate::Map;
+use crate::system_snapshot::probe::Probe;
+use crate::system_snapshot::{SystemSnapshot, SystemSnapshotter};
+
+/// Adds the versions of Nix and determinate-nixd to another snapshotter's `extra_fields`, when enabled.
+pub(crate) struct NixVersions<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S> NixVersions<S> {
+    pub(crate) fn new(inner: S, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+}
+
+impl<S: SystemSnapshotter> SystemSnapshotter for NixVersions<S> {
+    async fn snapshot(&self) -> SystemSnapshot {
+        let mut snapshot = self.inner.snapshot().await;
+
+        if self.enabled {
+            let extra_fields = snapshot.extra_fields.get_or_insert_with(Map::new);
+            for (key, value) in cached().await {
+                extra_fields
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+
+        snapshot
+    }
+}
+
+/// The versions on this machine, looked up once per process.
+async fn cached() -> &'static Map {
+    static VERSIONS: OnceCell<Map> = OnceCell::const_new();
+
+    VERSIONS
+        .get_or_init(|| async {
+            tokio::task::spawn_blocking(|| collect(&super::probe::Host))
+                .await
+                .unwrap_or_default()
+        })
+        .await
+}
+
+/// Look up `nix_version`, `nix_flavor`, and `determinate_nixd_version`, leaving out whichever can't be found.
+pub(crate) fn collect(probe: &impl Probe) -> Map {
+    let stdout = |program, args| {
+        probe
+            .run(program, args)
+            .filter(|output| output.success)
+            .map(|output| output.stdout)
+    };
+    let nix = stdout("nix", &["--version"]);
+    let nixd = stdout("determinate-nixd", &["version"]);
+
+    let mut facts = Map::new();
+
+    if let Some((version, flavor)) = nix.as_deref().and_then(parse_nix_version) {
+        facts.insert("nix_version".into(), version.into());
+        if let Some(flavor) = flavor {
+            facts.insert("nix_flavor".into(), flavor.into());
+        }
+    }
+
+    if let Some(version) = nixd.as_deref().and_then(parse_determinate_nixd_version) {
+        facts.insert("determinate_nixd_version".into(), version.into());
+    }
+
+    facts
+}
+
+/// Parse `nix (Nix) 2.24.9` or `nix (Determinate Nix 3.6.2) 2.29.0` into the Nix version and its flavor.
+fn parse_nix_version(output: &str) -> Option<(String, Option<&'static str>)> {
+    let line = output.lines().next()?.trim();
+    let version = line.rsplit(' ').next().filter(|v| looks_like_version(v))?;
+
+    let flavor = line
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .and_then(|(name, _)| match name {
+            "Nix" => Some("upstream"),
+            name if name.starts_with("Determinate Nix") => Some("determinate"),
+            _ => None,
+        });
+
+    Some((version.to_string(), flavor))
+}
+
+/// Find the first version number in determinate-nixd's output, such as `Determinate Nixd daemon version: 3.6.2`.
+fn parse_determinate_nixd_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| looks_like_version(word))
+        .map(String::from)
+}
+
+fn looks_like_version(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.')
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::collect;
+
+    /// A machine where each program succeeds with the given output, and no others exist.
+    fn stub(outputs: &[(&str, &str)]) -> Fixture {
+        outputs
+            .iter()
+            .fold(Fixture::default(), |probe, (program, stdout)| {
+                probe.with_command(program, true, stdout, "")
+            })
+    }
+
+    #[test]
+    fn upstream_nix() {
+        let facts = collect(&stub(&[("nix", "nix (Nix) 2.24.9\n")]));
+
+        assert_eq!(
+            serde_json::Value::Object(facts),
+            serde_json::json!({ "nix_version": "2.24.9", "nix_flavor": "upstream" })
+        );
+    }
+
+    #[test]
+    fn determinate_nix() {
+        let facts = collect(&stub(&[
+            ("nix", "nix (Determinate Nix 3.6.2) 2.29.0\n"),
+            (
+                "determinate-nixd",
+                "Determinate Nixd daemon version: 3.6.2\nDeterminate Nixd client version: 3.6.2\n",
+            ),
+        ]));
+
+        assert_eq!(
+            serde_json::Value::Object(facts),
+            serde_json::json!({
+                "nix_version": "2.29.0",
+                "nix_flavor": "determinate",
+                "determinate_nixd_version": "3.6.2",
+            })
+        );
+    }
+
+    #[test]
+    fn other_flavors_have_no_flavor() {
+        let facts = collect(&stub(&[("nix", "nix (Lix, like Nix) 2.91.1\n")]));
+
+        assert_eq!(
+            serde_json::Value::Object(facts),
+            serde_json::json!({ "nix_version": "2.91.1" })
+        );
+    }
+
+    #[test]
+    fn failures_leave_the_facts_out() {
+        assert!(collect(&Fixture::default()).is_empty());
+        assert!(
+            collect(&stub(&[
+                ("nix", "error: something went wrong\n"),
+                ("determinate-nixd", ""),
+            ]))
+            .is_empty()
+        );
+
+        let failed = Fixture::default()
+            .with_command("nix", false, "nix (Nix) 2.24.9\n", "")
+            .with_command("determinate-nixd", false, "3.6.2\n", "");
+        assert!(collect(&failed).is_empty());
+    }
+}
@@ -0,0 +1,366 @@
+//! The machine as the snapshot's detectors see it.
+
+use std::ffi::CStr;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long to wait for a command before giving up on what it would have said.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What a command printed, and whether it exited successfully.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CommandOutput {
+    pub(crate) success: bool,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+}
+
+/// Everything the detectors read about the machine, so each can be tested against a made-up one.
+///
+/// Facts which can't be read, or don't exist on this platform, are None rather than errors.
+pub(crate) trait Probe {
+    /// The contents of a file.
+    fn read(&self, path: &Path) -> Option<String>;
+
+    /// Whether a file or directory exists.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// An environment variable of this process.
+    fn var(&self, name: &str) -> Option<String>;
+
+    /// An integer sysctl, on macOS. Example: `hw.optional.arm64`
+    fn sysctl_int(&self, name: &CStr) -> Option<i32>;
+
+    /// A string sysctl, on macOS. Example: `hw.model`
+    fn sysctl_string(&self, name: &CStr) -> Option<String>;
+
+    /// The hypervisor vendor reported by CPUID, if the CPU's hypervisor bit is set. Example: `KVMKVMKVM`
+    fn cpuid_hypervisor(&self) -> Option<String>;
+
+    /// The environment of the target triple this was built for. Example: `musl`
+    fn target_env(&self) -> &str;
+
+    /// `gnu_get_libc_version()`, when linked against glibc. Example: `2.39`
+    fn gnu_get_libc_version(&self) -> Option<String>;
+
+    /// `confstr(_CS_GNU_LIBC_VERSION)`. Example: `glibc 2.39`
+    fn confstr_libc_version(&self) -> Option<String>;
+
+    /// Run a command without input, or None if it couldn't be started or didn't finish within a second.
+    fn run(&self, program: &str, args: &[&str]) -> Option<CommandOutput>;
+}
+
+/// The machine this process runs on.
+pub(crate) struct Host;
+
+impl Probe for Host {
+    fn read(&self, path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    #[cfg(target_os = "macos")]
+    fn sysctl_int(&self, name: &CStr) -> Option<i32> {
+        super::sysctl::int(name)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn sysctl_int(&self, _name: &CStr) -> Option<i32> {
+        None
+    }
+
+    #[cfg(target_os = "macos")]
+    fn sysctl_string(&self, name: &CStr) -> Option<String> {
+        super::sysctl::string(name)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn sysctl_string(&self, _name: &CStr) -> Option<String> {
+        None
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn cpuid_hypervisor(&self) -> Option<String> {
+        use std::arch::x86_64::__cpuid;
+
+        if __cpuid(1).ecx & (1 << 31) == 0 {
+            return None;
+        }
+
+        let leaf = __cpuid(0x4000_0000);
+        let vendor: Vec<u8> = [leaf.ebx, leaf.ecx, leaf.edx]
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .collect();
+
+        Some(
+            String::from_utf8_lossy(&vendor)
+                .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+                .to_string(),
+        )
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn cpuid_hypervisor(&self) -> Option<String> {
+        None
+    }
+
+    fn target_env(&self) -> &str {
+        if cfg!(target_env = "musl") {
+            "musl"
+        } else if cfg!(target_env = "gnu") {
+            "gnu"
+        } else {
+            "unknown"
+        }
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn gnu_get_libc_version(&self) -> Option<String> {
+        // SAFETY: glibc returns a pointer to a static, NUL terminated string
+        let version = unsafe { CStr::from_ptr(libc::gnu_get_libc_version()) };
+
+        version.to_str().ok().map(String::from)
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    fn gnu_get_libc_version(&self) -> Option<String> {
+        None
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn confstr_libc_version(&self) -> Option<String> {
+        let mut buf = [0u8; 64];
+
+        // SAFETY: confstr writes at most `buf.len()` bytes, including the NUL terminator
+        let len = unsafe {
+            libc::confstr(
+                libc::_CS_GNU_LIBC_VERSION,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+            )
+        };
+        if len == 0 || len > buf.len() {
+            return None;
+        }
+
+        CStr::from_bytes_until_nul(&buf)
+            .ok()?
+            .to_str()
+            .ok()
+            .map(String::from)
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    fn confstr_libc_version(&self) -> Option<String> {
+        None
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> Option<CommandOutput> {
+        let mut child = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .inspect_err(|e| tracing::trace!(%e, program, "Failed to run a command"))
+            .ok()?;
+
+        // Read the pipes while waiting, so a chatty command can't fill them and never exit
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let deadline = Instant::now() + COMMAND_TIMEOUT;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Ok(None) => {
+                    tracing::trace!(program, "Timed out running a command");
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return None;
+                }
+                Err(e) => {
+                    tracing::trace!(%e, program, "Failed to wait for a command");
+                    return None;
+                }
+            }
+        };
+
+        Some(CommandOutput {
+            success: status.success(),
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut bytes = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// A made-up machine, which has only the facts it's given.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct Fixture {
+    files: std::collections::HashMap<std::path::PathBuf, String>,
+    vars: std::collections::HashMap<String, String>,
+    sysctl_ints: std::collections::HashMap<String, i32>,
+    sysctl_strings: std::collections::HashMap<String, String>,
+    cpuid_hypervisor: Option<String>,
+    target_env: Option<&'static str>,
+    gnu_get_libc_version: Option<String>,
+    confstr_libc_version: Option<String>,
+    commands: std::collections::HashMap<String, CommandOutput>,
+}
+
+#[cfg(test)]
+impl Fixture {
+    /// Add a file, and implicitly the directories above it.
+    pub(crate) fn with_file(mut self, path: &str, contents: &str) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
+    pub(crate) fn with_var(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    pub(crate) fn with_sysctl_int(mut self, name: &str, value: i32) -> Self {
+        self.sysctl_ints.insert(name.into(), value);
+        self
+    }
+
+    pub(crate) fn with_sysctl_string(mut self, name: &str, value: &str) -> Self {
+        self.sysctl_strings.insert(name.into(), value.into());
+        self
+    }
+
+    pub(crate) fn with_cpuid_hypervisor(mut self, vendor: &str) -> Self {
+        self.cpuid_hypervisor = Some(vendor.into());
+        self
+    }
+
+    pub(crate) fn with_target_env(mut self, env: &'static str) -> Self {
+        self.target_env = Some(env);
+        self
+    }
+
+    pub(crate) fn with_gnu_get_libc_version(mut self, version: &str) -> Self {
+        self.gnu_get_libc_version = Some(version.into());
+        self
+    }
+
+    pub(crate) fn with_confstr_libc_version(mut self, version: &str) -> Self {
+        self.confstr_libc_version = Some(version.into());
+        self
+    }
+
+    /// Make `program` exit with `success`, printing `stdout` and `stderr`, whatever its arguments.
+    pub(crate) fn with_command(
+        mut self,
+        program: &str,
+        success: bool,
+        stdout: &str,
+        stderr: &str,
+    ) -> Self {
+        let output = CommandOutput {
+            success,
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+        };
+        self.commands.insert(program.into(), output);
+        self
+    }
+}
+
+#[cfg(test)]
+impl Probe for Fixture {
+    fn read(&self, path: &Path) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.keys().any(|file| file.starts_with(path))
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+
+    fn sysctl_int(&self, name: &CStr) -> Option<i32> {
+        self.sysctl_ints.get(name.to_str().ok()?).copied()
+    }
+
+    fn sysctl_string(&self, name: &CStr) -> Option<String> {
+        self.sysctl_strings.get(name.to_str().ok()?).cloned()
+    }
+
+    fn cpuid_hypervisor(&self) -> Option<String> {
+        self.cpuid_hypervisor.clone()
+    }
+
+    fn target_env(&self) -> &str {
+        self.target_env.unwrap_or("unknown")
+    }
+
+    fn gnu_get_libc_version(&self) -> Option<String> {
+        self.gnu_get_libc_version.clone()
+    }
+
+    fn confstr_libc_version(&self) -> Option<String> {
+        self.confstr_libc_version.clone()
+    }
+
+    fn run(&self, program: &str, _args: &[&str]) -> Option<CommandOutput> {
+        self.commands.get(program).cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Host, Probe};
+
+    #[test]
+    fn commands_report_their_output_and_status() {
+        let output = Host
+            .run("sh", &["-c", "echo out; echo err >&2; exit 3"])
+            .unwrap();
+
+        assert!(!output.success);
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+
+    #[test]
+    fn commands_which_hang_are_abandoned() {
+        let started = std::time::Instant::now();
+
+        assert!(Host.run("sleep", &["10"]).is_none());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn missing_programs_are_none() {
+        assert!(Host.run("detsys-ids-no-such-program", &[]).is_none());
+    }
+}
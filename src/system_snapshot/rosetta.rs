@@ -0,0 +1,91 @@
+// Detection only runs on macOS
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use super::probe::Probe;
+
+/// Whether this process runs under Rosetta, and the machine's own architecture.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Rosetta {
+    pub(crate) translated: Option<bool>,
+    pub(crate) native_arch: Option<String>,
+}
+
+/// Whether this process runs under Rosetta. Detected once per process.
+#[cfg(target_os = "macos")]
+pub(crate) fn current() -> Rosetta {
+    static ROSETTA: std::sync::OnceLock<Rosetta> = std::sync::OnceLock::new();
+
+    ROSETTA.get_or_init(|| detect(&super::probe::Host)).clone()
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn current() -> Rosetta {
+    Rosetta::default()
+}
+
+/// Read `sysctl.proc_translated`, which is 1 under Rosetta, and `hw.optional.arm64`, which is 1 on Apple silicon.
+/// Intel Macs have neither, and are never translated.
+pub(crate) fn detect(probe: &impl Probe) -> Rosetta {
+    let arch = if probe.sysctl_int(c"hw.optional.arm64") == Some(1) {
+        "aarch64"
+    } else {
+        "x86_64"
+    };
+
+    Rosetta {
+        translated: Some(probe.sysctl_int(c"sysctl.proc_translated") == Some(1)),
+        native_arch: Some(arch.into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::{Rosetta, detect};
+
+    fn rosetta(translated: bool, native_arch: &str) -> Rosetta {
+        Rosetta {
+            translated: Some(translated),
+            native_arch: Some(native_arch.into()),
+        }
+    }
+
+    #[test]
+    fn sysctl_values() {
+        for (proc_translated, arm64, expected) in [
+            // An x86_64 binary under Rosetta
+            (Some(1), Some(1), rosetta(true, "aarch64")),
+            // A native binary on Apple silicon
+            (Some(0), Some(1), rosetta(false, "aarch64")),
+            // Intel Macs have neither sysctl
+            (None, None, rosetta(false, "x86_64")),
+            (None, Some(0), rosetta(false, "x86_64")),
+        ] {
+            let mut probe = Fixture::default();
+            if let Some(value) = proc_translated {
+                probe = probe.with_sysctl_int("sysctl.proc_translated", value);
+            }
+            if let Some(value) = arm64 {
+                probe = probe.with_sysctl_int("hw.optional.arm64", value);
+            }
+            assert_eq!(detect(&probe), expected, "{proc_translated:?} {arm64:?}");
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn current_matches_the_build() {
+        let current = super::current();
+
+        assert!(current.native_arch.is_some());
+        if cfg!(target_arch = "aarch64") {
+            assert_eq!(current, rosetta(false, "aarch64"));
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn current_is_inert_elsewhere() {
+        assert_eq!(super::current(), Rosetta::default());
+    }
+}
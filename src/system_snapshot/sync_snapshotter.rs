@@ -0,0 +1,27 @@
+use crate::system_snapshot::{SystemSnapshot, SystemSnapshotter};
+
+/// Adapts a synchronous function into a `SystemSnapshotter`.
+///
+/// The function runs on the worker's task, so it shouldn't block for long.
+/// Snapshotters that wait on I/O should implement `SystemSnapshotter` directly instead.
+pub struct SyncSnapshotter<F> {
+    snapshot: F,
+}
+
+impl<F> SyncSnapshotter<F>
+where
+    F: Fn() -> SystemSnapshot + Send + Sync + 'static,
+{
+    pub fn new(snapshot: F) -> Self {
+        Self { snapshot }
+    }
+}
+
+impl<F> SystemSnapshotter for SyncSnapshotter<F>
+where
+    F: Fn() -> SystemSnapshot + Send + Sync + 'static,
+{
+    async fn snapshot(&self) -> SystemSnapshot {
+        (self.snapshot)()
+    }
+}
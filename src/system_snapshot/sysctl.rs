@@ -0,0 +1,60 @@
+//! Reading macOS sysctls by name.
+
+use std::ffi::CStr;
+
+pub(crate) fn int(name: &CStr) -> Option<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut size = std::mem::size_of::<libc::c_int>();
+
+    // SAFETY: `value` and `size` describe a buffer big enough for the integer.
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            (&raw mut value).cast(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+
+    (ret == 0).then_some(value)
+}
+
+pub(crate) fn string(name: &CStr) -> Option<String> {
+    let mut size = 0;
+
+    // SAFETY: A null buffer asks for the size of the value.
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            std::ptr::null_mut(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 || size == 0 {
+        return None;
+    }
+
+    let mut buf = vec![0u8; size];
+
+    // SAFETY: `buf` and `size` describe a buffer of the size sysctl asked for.
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            buf.as_mut_ptr().cast(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+
+    buf.truncate(size);
+    CStr::from_bytes_until_nul(&buf)
+        .ok()
+        .map(|value| value.to_string_lossy().into_owned())
+}
@@ -0,0 +1,212 @@
+// Detection only runs on Linux and macOS
+#![cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+
+use std::path::Path;
+
+use super::probe::Probe;
+
+/// The hypervisor this machine runs under, if any. Detected once per process.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) fn current() -> Option<String> {
+    static VIRTUALIZATION: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+
+    VIRTUALIZATION
+        .get_or_init(|| detect(&super::probe::Host))
+        .clone()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn current() -> Option<String> {
+    None
+}
+
+/// DMI string prefixes, and the hypervisors they identify, following `systemd-detect-virt`.
+const DMI_VENDORS: &[(&str, &str)] = &[
+    ("KVM", "kvm"),
+    ("OpenStack", "kvm"),
+    ("KubeVirt", "kvm"),
+    ("Amazon EC2", "amazon"),
+    ("QEMU", "qemu"),
+    ("VMware", "vmware"),
+    ("VMW", "vmware"),
+    ("innotek GmbH", "oracle"),
+    ("VirtualBox", "oracle"),
+    ("Xen", "xen"),
+    ("Bochs", "bochs"),
+    ("Parallels", "parallels"),
+    ("BHYVE", "bhyve"),
+    ("Hyper-V", "microsoft"),
+    ("Apple Virtualization", "apple"),
+    ("Google Compute Engine", "google"),
+];
+
+/// DMI vendors which are more specific than the hypervisor CPUID reports, such as EC2 instances running on KVM.
+const PREFERRED_DMI_VENDORS: &[&str] = &["amazon", "oracle", "xen", "google"];
+
+/// CPUID hypervisor vendor strings, and the hypervisors they identify.
+const CPUID_VENDORS: &[(&str, &str)] = &[
+    ("XenVMMXenVMM", "xen"),
+    ("KVMKVMKVM", "kvm"),
+    ("Linux KVM Hv", "kvm"),
+    ("TCGTCGTCGTCG", "qemu"),
+    ("VMwareVMware", "vmware"),
+    ("Microsoft Hv", "microsoft"),
+    ("bhyve bhyve", "bhyve"),
+    ("QNXQVMBSQG", "qnx"),
+    ("ACRNACRNACRN", "acrn"),
+    ("Apple VZ", "apple"),
+];
+
+/// `hw.model` prefixes of macOS virtual machines, and the hypervisors they identify.
+const MAC_MODELS: &[(&str, &str)] = &[
+    ("VirtualMac", "apple"),
+    ("VMware", "vmware"),
+    ("Parallels", "parallels"),
+];
+
+/// The DMI strings under `/sys/class/dmi/id/` which may name a hypervisor.
+const DMI_FILES: &[&str] = &[
+    "sys_vendor",
+    "product_name",
+    "product_version",
+    "board_vendor",
+    "bios_vendor",
+];
+
+/// Name the hypervisor from macOS's `kern.hv_vmm_present` and `hw.model` sysctls, or from DMI and CPUID elsewhere, preferring the most specific.
+pub(crate) fn detect(probe: &impl Probe) -> Option<String> {
+    if probe.sysctl_int(c"kern.hv_vmm_present") == Some(1) {
+        let model = probe.sysctl_string(c"hw.model").unwrap_or_default();
+        return Some(lookup(MAC_MODELS, &model).unwrap_or("other").into());
+    }
+
+    let dmi = DMI_FILES.iter().find_map(|name| {
+        let value = probe.read(&Path::new("/sys/class/dmi/id").join(name))?;
+        lookup(DMI_VENDORS, value.trim())
+    });
+    if let Some(vendor) = dmi
+        && PREFERRED_DMI_VENDORS.contains(&vendor)
+    {
+        return Some(vendor.into());
+    }
+
+    let cpuid = probe.cpuid_hypervisor().map(|vendor| {
+        CPUID_VENDORS
+            .iter()
+            .find(|(name, _)| *name == vendor.trim())
+            .map(|(_, hypervisor)| *hypervisor)
+    });
+
+    match (cpuid, dmi) {
+        (Some(Some(hypervisor)), _) => Some(hypervisor.into()),
+        (_, Some(vendor)) => Some(vendor.into()),
+        // The hypervisor bit is set, but by a hypervisor we don't know
+        (Some(None), None) => Some("other".into()),
+        (None, None) => None,
+    }
+}
+
+fn lookup(table: &[(&str, &'static str)], value: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(prefix, _)| value.starts_with(prefix))
+        .map(|(_, hypervisor)| *hypervisor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::{DMI_FILES, detect};
+
+    fn dmi(values: &[&str]) -> Fixture {
+        DMI_FILES
+            .iter()
+            .zip(values)
+            .fold(Fixture::default(), |fixture, (name, value)| {
+                fixture.with_file(&format!("/sys/class/dmi/id/{name}"), &format!("{value}\n"))
+            })
+    }
+
+    #[test]
+    fn bare_metal_is_not_virtualized() {
+        assert_eq!(detect(&Fixture::default()), None);
+        assert_eq!(
+            detect(&dmi(&[
+                "Dell Inc.",
+                "PowerEdge R650",
+                "",
+                "Dell Inc.",
+                "Dell Inc."
+            ])),
+            None
+        );
+    }
+
+    #[test]
+    fn dmi_vendors() {
+        for (values, hypervisor) in [
+            (
+                &["innotek GmbH", "VirtualBox", "1.2", "Oracle Corporation"][..],
+                "oracle",
+            ),
+            (&["QEMU", "Standard PC (Q35 + ICH9, 2009)"][..], "qemu"),
+            (&["VMware, Inc.", "VMware7,1"][..], "vmware"),
+            (
+                &["Apple Inc.", "Apple Virtualization Generic Platform"][..],
+                "apple",
+            ),
+            (&["Amazon EC2", "m6i.large"][..], "amazon"),
+        ] {
+            assert_eq!(detect(&dmi(values)).as_deref(), Some(hypervisor));
+        }
+    }
+
+    #[test]
+    fn cpuid_vendors() {
+        // A Hetzner Cloud server, whose DMI strings don't name a hypervisor
+        let hetzner = dmi(&["Hetzner", "vServer", "20171111", "", "Hetzner"])
+            .with_cpuid_hypervisor("KVMKVMKVM");
+        assert_eq!(detect(&hetzner).as_deref(), Some("kvm"));
+
+        let hyperv = dmi(&["Microsoft Corporation", "Virtual Machine"])
+            .with_cpuid_hypervisor("Microsoft Hv");
+        assert_eq!(detect(&hyperv).as_deref(), Some("microsoft"));
+
+        let unknown = Fixture::default().with_cpuid_hypervisor("NewHypervisor");
+        assert_eq!(detect(&unknown).as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn cpuid_is_preferred_to_generic_dmi() {
+        // QEMU accelerated by KVM
+        let probe =
+            dmi(&["QEMU", "Standard PC (i440FX + PIIX, 1996)"]).with_cpuid_hypervisor("KVMKVMKVM");
+        assert_eq!(detect(&probe).as_deref(), Some("kvm"));
+    }
+
+    #[test]
+    fn specific_dmi_is_preferred_to_cpuid() {
+        let ec2 = dmi(&["Amazon EC2", "c5.xlarge"]).with_cpuid_hypervisor("KVMKVMKVM");
+        assert_eq!(detect(&ec2).as_deref(), Some("amazon"));
+    }
+
+    #[test]
+    fn macos_models() {
+        let vm = Fixture::default().with_sysctl_int("kern.hv_vmm_present", 1);
+        let mac = |hw_model: &str| {
+            Fixture::default()
+                .with_sysctl_int("kern.hv_vmm_present", 1)
+                .with_sysctl_string("hw.model", hw_model)
+        };
+
+        assert_eq!(detect(&mac("VirtualMac2,1")).as_deref(), Some("apple"));
+        assert_eq!(detect(&mac("VMware7,1")).as_deref(), Some("vmware"));
+        assert_eq!(detect(&mac("Parallels-ARM")).as_deref(), Some("parallels"));
+        assert_eq!(detect(&vm).as_deref(), Some("other"));
+
+        let bare_metal = Fixture::default()
+            .with_sysctl_int("kern.hv_vmm_present", 0)
+            .with_sysctl_string("hw.model", "Mac14,12");
+        assert_eq!(detect(&bare_metal), None);
+    }
+}
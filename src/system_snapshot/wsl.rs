@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use super::probe::Probe;
+
+/// The version of WSL this process is running under, if any.
+#[cfg(target_os = "linux")]
+pub(crate) fn current_version() -> Option<u8> {
+    detect(&super::probe::Host)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn current_version() -> Option<u8> {
+    None
+}
+
+/// Tell WSL1 from WSL2 by the kernel's name in `/proc/sys/kernel/osrelease` or `/proc/version`, falling back to the `WSL_DISTRO_NAME` variable WSL sets in every distribution.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn detect(probe: &impl Probe) -> Option<u8> {
+    if let Some(version) = ["/proc/sys/kernel/osrelease", "/proc/version"]
+        .into_iter()
+        .filter_map(|path| probe.read(Path::new(path)))
+        .find_map(|kernel| kernel_version(&kernel))
+    {
+        return Some(version);
+    }
+
+    // WSL1 always reports Microsoft's kernel release, but WSL2 can boot a custom kernel.
+    probe
+        .var("WSL_DISTRO_NAME")
+        .is_some_and(|name| !name.is_empty())
+        .then_some(2)
+}
+
+/// WSL2 kernels are named like `5.15.153.1-microsoft-standard-WSL2`, and WSL1 reports releases like `4.4.0-19041-Microsoft`.
+fn kernel_version(kernel: &str) -> Option<u8> {
+    let kernel = kernel.to_lowercase();
+
+    if !kernel.contains("microsoft") {
+        return None;
+    }
+
+    if kernel.contains("wsl2") || kernel.contains("microsoft-standard") {
+        Some(2)
+    } else {
+        Some(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::probe::Fixture;
+    use super::detect;
+
+    const WSL1_OSRELEASE: &str = "4.4.0-19041-Microsoft\n";
+    const WSL1_VERSION: &str = "Linux version 4.4.0-19041-Microsoft (Microsoft@Microsoft.com) (gcc version 5.4.0 (GCC) ) #3996-Microsoft Thu Jan 18 16:36:00 PST 2024\n";
+    const WSL2_OSRELEASE: &str = "5.15.153.1-microsoft-standard-WSL2\n";
+    const WSL2_EARLY_OSRELEASE: &str = "4.19.128-microsoft-standard\n";
+    const WSL2_VERSION: &str = "Linux version 5.15.153.1-microsoft-standard-WSL2 (root@941d701f84f1) (gcc (GCC) 11.2.0, GNU ld (GNU Binutils) 2.37) #1 SMP Fri Mar 29 23:14:13 UTC 2024\n";
+    const NATIVE_OSRELEASE: &str = "6.8.0-45-generic\n";
+    const NATIVE_VERSION: &str = "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-115) (x86_64-linux-gnu-gcc-13 (Ubuntu 13.2.0-23ubuntu4) 13.2.0, GNU ld (GNU Binutils for Ubuntu) 2.42) #45-Ubuntu SMP PREEMPT_DYNAMIC Fri Aug 30 12:02:04 UTC 2024\n";
+
+    fn kernel(osrelease: &str, proc_version: &str) -> Fixture {
+        Fixture::default()
+            .with_file("/proc/sys/kernel/osrelease", osrelease)
+            .with_file("/proc/version", proc_version)
+    }
+
+    #[test]
+    fn native_linux_is_not_wsl() {
+        assert_eq!(detect(&Fixture::default()), None);
+        assert_eq!(detect(&kernel(NATIVE_OSRELEASE, NATIVE_VERSION)), None);
+    }
+
+    #[test]
+    fn wsl1() {
+        assert_eq!(detect(&kernel(WSL1_OSRELEASE, WSL1_VERSION)), Some(1));
+    }
+
+    #[test]
+    fn wsl2() {
+        assert_eq!(detect(&kernel(WSL2_OSRELEASE, WSL2_VERSION)), Some(2));
+
+        let early =
+            Fixture::default().with_file("/proc/sys/kernel/osrelease", WSL2_EARLY_OSRELEASE);
+        assert_eq!(detect(&early), Some(2));
+    }
+
+    #[test]
+    fn unreadable_osrelease_falls_back_to_proc_version() {
+        for (proc_version, version) in [(WSL1_VERSION, 1), (WSL2_VERSION, 2)] {
+            let probe = Fixture::default().with_file("/proc/version", proc_version);
+            assert_eq!(detect(&probe), Some(version));
+        }
+    }
+
+    #[test]
+    fn distro_name() {
+        let custom_kernel =
+            kernel(NATIVE_OSRELEASE, NATIVE_VERSION).with_var("WSL_DISTRO_NAME", "Ubuntu-24.04");
+        assert_eq!(detect(&custom_kernel), Some(2));
+
+        let wsl1 = kernel(WSL1_OSRELEASE, WSL1_VERSION).with_var("WSL_DISTRO_NAME", "Ubuntu-24.04");
+        assert_eq!(detect(&wsl1), Some(1));
+
+        let empty = Fixture::default().with_var("WSL_DISTRO_NAME", "");
+        assert_eq!(detect(&empty), None);
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::system_snapshot::{SyncSnapshotter, SystemSnapshot, SystemSnapshotter};
+use crate::test::recording_transport::RecordingTransport;
+
+/// Waits before answering, like a snapshotter asking a local daemon would.
+struct Sleepy;
+
+impl SystemSnapshotter for Sleepy {
+    async fn snapshot(&self) -> SystemSnapshot {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        SystemSnapshot {
+            extra_fields: Some(crate::Map::from_iter([(
+                "snapshotter".into(),
+                "sleepy".into(),
+            )])),
+            ..SystemSnapshot::default()
+        }
+    }
+}
+
+async fn snapshotter_property<S: SystemSnapshotter>(snapshotter: S) -> serde_json::Value {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            snapshotter,
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    let worker = tokio::spawn(worker.wait());
+
+    recorder.record("tick", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("tick").await;
+    assert_eq!(events.len(), 1);
+
+    events[0]["properties"]["snapshotter"].clone()
+}
+
+#[tokio::test]
+async fn async_snapshotters_can_await() {
+    super::init_tracing();
+
+    assert_eq!(snapshotter_property(Sleepy).await, "sleepy");
+}
+
+#[tokio::test]
+async fn sync_snapshotters_are_adapted() {
+    super::init_tracing();
+
+    let snapshotter = SyncSnapshotter::new(|| SystemSnapshot {
+        extra_fields: Some(crate::Map::from_iter([(
+            "snapshotter".into(),
+            "sync".into(),
+        )])),
+        ..SystemSnapshot::default()
+    });
+
+    assert_eq!(snapshotter_property(snapshotter).await, "sync");
+}
@@ -18,6 +18,7 @@ async fn test() {
                 Arc::new(Feature {
                     variant: true.into(),
                     payload: None,
+                    experiment_id: None,
                 }),
             )]
             .into(),
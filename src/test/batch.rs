@@ -0,0 +1,68 @@
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn batched_events_arrive_in_order_without_interleaving() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    let noise = {
+        let recorder = recorder.clone();
+        tokio::spawn(async move {
+            for _ in 0..50 {
+                recorder.record("noise", None).await;
+                tokio::task::yield_now().await;
+            }
+        })
+    };
+
+    for _ in 0..10 {
+        recorder
+            .record_batch(vec![
+                ("build_started".into(), None),
+                (
+                    "build_config".into(),
+                    Some(crate::Map::from_iter([("jobs".into(), 4.into())])),
+                ),
+                ("build_queued".into(), None),
+            ])
+            .await;
+        tokio::task::yield_now().await;
+    }
+
+    noise.await.unwrap();
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events().await;
+    let names: Vec<&str> = events
+        .iter()
+        .map(|event| event["name"].as_str().unwrap())
+        .collect();
+
+    let starts: Vec<usize> = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| **name == "build_started")
+        .map(|(idx, _)| idx)
+        .collect();
+    assert_eq!(starts.len(), 10);
+
+    for start in starts {
+        assert_eq!(
+            &names[start..start + 3],
+            &["build_started", "build_config", "build_queued"]
+        );
+        assert_eq!(events[start + 1]["properties"]["jobs"], 4);
+    }
+
+    let mut uuids: Vec<&str> = events
+        .iter()
+        .map(|event| event["uuid"].as_str().unwrap())
+        .collect();
+    uuids.sort();
+    uuids.dedup();
+    assert_eq!(uuids.len(), events.len());
+}
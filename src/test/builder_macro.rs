@@ -0,0 +1,62 @@
+use crate::test::recording_transport::RecordingTransport;
+
+async fn record_with(builder: crate::Builder) -> serde_json::Value {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(builder).await;
+
+    recorder.record("from-the-macro", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let mut events = transport.events_named("from-the-macro").await;
+    assert_eq!(events.len(), 1);
+    events.remove(0)
+}
+
+#[tokio::test]
+async fn groups_are_attached_to_events() {
+    super::init_tracing();
+
+    let event = record_with(crate::builder!(groups = {
+        "project" => env!("CARGO_PKG_NAME"),
+        "team" => String::from("ids"),
+    }))
+    .await;
+
+    let groups = &event["properties"]["$groups"];
+    assert_eq!(groups["project"], env!("CARGO_PKG_NAME"));
+    assert_eq!(groups["team"], "ids");
+    assert_eq!(
+        event["properties"]["cargo_pkg_name"],
+        env!("CARGO_PKG_NAME")
+    );
+}
+
+#[tokio::test]
+async fn groups_follow_an_env_var() {
+    super::init_tracing();
+
+    let event = record_with(crate::builder!(
+        env_var = "DETSYS_IDS_TEST_MACRO_TELEMETRY",
+        groups = { "project" => "with-env-var" }
+    ))
+    .await;
+
+    assert_eq!(event["properties"]["$groups"]["project"], "with-env-var");
+}
+
+#[tokio::test]
+async fn no_groups_by_default() {
+    super::init_tracing();
+
+    let event = record_with(crate::builder!()).await;
+
+    assert!(
+        event["properties"]["$groups"]
+            .as_object()
+            .is_none_or(|groups| groups.is_empty()),
+        "{event}"
+    );
+}
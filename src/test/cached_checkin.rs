@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use crate::storage::{Shared, StoredProperties};
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+use crate::test::slow_transport::SlowTransport;
+
+fn stored_checkin(fetched_at: chrono::DateTime<chrono::Utc>) -> StoredProperties {
+    StoredProperties {
+        checkin: CheckinFixture::new()
+            .variant("cached-flag", "cached")
+            .build(),
+        checkin_fetched_at: Some(fetched_at),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn stored_checkin_is_served_without_the_network() {
+    super::init_tracing();
+
+    // The worker is never awaited, since its check-in never finishes
+    let (recorder, _worker) = crate::Builder::new()
+        .build_with(
+            SlowTransport::new(Duration::from_secs(60 * 60)),
+            crate::system_snapshot::Generic::default(),
+            Shared::seeded(stored_checkin(chrono::Utc::now())),
+        )
+        .await;
+
+    recorder
+        .wait_for_checkin(Some(Duration::from_millis(100)))
+        .await
+        .unwrap();
+    assert_eq!(
+        recorder.get_feature_variant::<String>("cached-flag").await,
+        Some("cached".to_string())
+    );
+}
+
+#[tokio::test]
+async fn stale_stored_checkin_is_not_served() {
+    super::init_tracing();
+
+    let (recorder, _worker) = crate::Builder::new()
+        .build_with(
+            SlowTransport::new(Duration::from_secs(60 * 60)),
+            crate::system_snapshot::Generic::default(),
+            Shared::seeded(stored_checkin(
+                chrono::Utc::now() - chrono::Duration::days(30),
+            )),
+        )
+        .await;
+
+    assert!(
+        recorder
+            .wait_for_checkin(Some(Duration::from_millis(100)))
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        recorder.get_feature_variant::<String>("cached-flag").await,
+        None
+    );
+}
+
+#[tokio::test]
+async fn failed_checkins_keep_the_stored_timestamp() {
+    super::init_tracing();
+
+    let fetched_at = chrono::Utc::now() - chrono::Duration::days(1);
+    let storage = Shared::seeded(stored_checkin(fetched_at));
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            RecordingTransport::new(),
+            crate::system_snapshot::Generic::default(),
+            storage.clone(),
+        )
+        .await;
+
+    recorder.trigger_configuration_refresh().await;
+
+    drop(recorder);
+    worker.wait().await;
+
+    let stored = storage.handle().get().unwrap();
+    assert_eq!(stored.checkin_fetched_at, Some(fetched_at));
+    assert!(stored.checkin.options.contains_key("cached-flag"));
+}
+
+#[tokio::test]
+async fn background_checkins_are_stored() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new().variant("fresh-flag", true).build(),
+        ))
+        .await;
+    let storage = Shared::new();
+    let before = chrono::Utc::now();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport,
+            crate::system_snapshot::Generic::default(),
+            storage.clone(),
+        )
+        .await;
+
+    recorder.wait_for_checkin(None).await.unwrap();
+    // Round trip through the collator, so it has handled the background check-in
+    recorder.get_distinct_id().await;
+
+    drop(recorder);
+    worker.wait().await;
+
+    let stored = storage.handle().get().unwrap();
+    assert!(stored.checkin.options.contains_key("fresh-flag"));
+    assert!(stored.checkin_fetched_at.unwrap() >= before);
+}
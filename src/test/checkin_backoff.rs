@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use crate::checkin::Checkin;
+use crate::test::recording_transport::RecordingTransport;
+
+async fn failing_worker() -> (
+    RecordingTransport,
+    crate::Recorder,
+    tokio::task::JoinHandle<()>,
+) {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_jitter(Some(0.0))
+                .configuration_refresh_interval(Some(Duration::from_secs(600))),
+        )
+        .await;
+
+    // Let the initial check-in fail
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    (transport, recorder, worker)
+}
+
+#[tokio::test(start_paused = true)]
+async fn failed_checkins_back_off() {
+    super::init_tracing();
+
+    let (transport, recorder, worker) = failing_worker().await;
+
+    // The first retry waits the configured interval, then 20, then 40 minutes
+    tokio::time::sleep(Duration::from_secs(9 * 60)).await;
+    assert_eq!(transport.checkins(), 1);
+    tokio::time::sleep(Duration::from_secs(2 * 60)).await;
+    assert_eq!(transport.checkins(), 2);
+
+    tokio::time::sleep(Duration::from_secs(18 * 60)).await;
+    assert_eq!(transport.checkins(), 2);
+    tokio::time::sleep(Duration::from_secs(2 * 60)).await;
+    assert_eq!(transport.checkins(), 3);
+
+    // Succeeding at 70 minutes returns to the configured interval
+    transport.set_checkin(Some(Checkin::default())).await;
+    tokio::time::sleep(Duration::from_secs(38 * 60)).await;
+    assert_eq!(transport.checkins(), 3);
+    tokio::time::sleep(Duration::from_secs(2 * 60)).await;
+    assert_eq!(transport.checkins(), 4);
+
+    tokio::time::sleep(Duration::from_secs(10 * 60)).await;
+    assert_eq!(transport.checkins(), 5);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn checkins_during_backoff_use_the_cache() {
+    super::init_tracing();
+
+    let (transport, recorder, worker) = failing_worker().await;
+
+    // A single failure doesn't hold triggered check-ins back
+    recorder.trigger_configuration_refresh().await;
+    assert_eq!(transport.checkins(), 2);
+
+    recorder.trigger_configuration_refresh().await;
+    assert_eq!(transport.checkins(), 2);
+
+    // Refreshing explicitly always reaches the transport
+    assert!(recorder.refresh_configuration().await.is_err());
+    assert_eq!(transport.checkins(), 3);
+
+    transport.set_checkin(Some(Checkin::default())).await;
+    recorder.refresh_configuration().await.unwrap();
+    assert_eq!(transport.checkins(), 4);
+
+    // Succeeding ends the backoff
+    recorder.trigger_configuration_refresh().await;
+    assert_eq!(transport.checkins(), 5);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn triggered_checkins_wait_at_most_the_configured_interval() {
+    super::init_tracing();
+
+    let (transport, recorder, worker) = failing_worker().await;
+
+    recorder.trigger_configuration_refresh().await;
+    assert!(recorder.refresh_configuration().await.is_err());
+    assert_eq!(transport.checkins(), 3);
+
+    // The periodic retry is now 40 minutes away, but triggered check-ins resume after 10
+    tokio::time::sleep(Duration::from_secs(9 * 60)).await;
+    recorder.trigger_configuration_refresh().await;
+    assert_eq!(transport.checkins(), 3);
+
+    tokio::time::sleep(Duration::from_secs(2 * 60)).await;
+    recorder.trigger_configuration_refresh().await;
+    assert_eq!(transport.checkins(), 4);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::checkin::Checkin;
+use crate::test::recording_transport::RecordingTransport;
+
+async fn checked_in_worker() -> (
+    RecordingTransport,
+    crate::Recorder,
+    tokio::task::JoinHandle<()>,
+) {
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(Checkin::default())).await;
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    recorder
+        .wait_for_checkin(Some(Duration::from_secs(5)))
+        .await
+        .unwrap();
+
+    (transport, recorder, worker)
+}
+
+#[tokio::test]
+async fn concurrent_refreshes_share_a_checkin() {
+    super::init_tracing();
+
+    let (transport, recorder, worker) = checked_in_worker().await;
+    let initial = transport.checkins();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(
+            recorder.trigger_configuration_refresh(),
+            recorder.trigger_configuration_refresh(),
+            recorder.trigger_configuration_refresh(),
+            recorder.trigger_configuration_refresh(),
+            recorder.trigger_configuration_refresh(),
+        )
+    })
+    .await
+    .expect("every refresh should get a reply");
+
+    let checkins = transport.checkins() - initial;
+    assert!((1..=2).contains(&checkins), "{checkins} check-ins");
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test]
+async fn summaries_are_shared_with_coalesced_refreshes() {
+    super::init_tracing();
+
+    let (transport, recorder, worker) = checked_in_worker().await;
+    let initial = transport.checkins();
+
+    let (summary, _, _) = tokio::join!(
+        recorder.refresh_configuration(),
+        recorder.trigger_configuration_refresh(),
+        recorder.trigger_configuration_refresh(),
+    );
+
+    assert_eq!(summary.unwrap().feature_count, 0);
+
+    let checkins = transport.checkins() - initial;
+    assert!((1..=2).contains(&checkins), "{checkins} check-ins");
+
+    drop(recorder);
+    worker.await.unwrap();
+}
@@ -0,0 +1,85 @@
+use crate::storage::{Shared, StoredProperties};
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+use crate::{CheckinSource, recorder::RecorderError};
+
+fn checkin() -> crate::checkin::Checkin {
+    CheckinFixture::new().variant("refreshed", true).build()
+}
+
+#[tokio::test]
+async fn status_follows_failing_then_working_checkins() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    assert!(matches!(
+        recorder.refresh_configuration().await,
+        Err(RecorderError::RefreshConfiguration(e)) if e == "No checkin configured"
+    ));
+
+    let status = recorder.checkin_status().await;
+    assert_eq!(status.source, CheckinSource::None);
+    assert_eq!(status.last_success, None);
+    assert_eq!(status.last_error.as_deref(), Some("No checkin configured"));
+
+    transport.set_checkin(Some(checkin())).await;
+
+    let summary = recorder.refresh_configuration().await.unwrap();
+    assert!(summary.changed);
+    assert_eq!(summary.feature_count, 1);
+    assert_eq!(
+        recorder.get_feature_variant::<bool>("refreshed").await,
+        Some(true)
+    );
+
+    let status = recorder.checkin_status().await;
+    assert_eq!(status.source, CheckinSource::Network);
+    assert_eq!(status.last_success, Some(summary.fetched_at));
+    assert_eq!(status.last_error, None);
+
+    assert!(!recorder.refresh_configuration().await.unwrap().changed);
+
+    // A failure is reported, but the last good configuration is still served
+    transport.set_checkin(None).await;
+    assert!(recorder.refresh_configuration().await.is_err());
+
+    let failed = recorder.checkin_status().await;
+    assert_eq!(failed.source, CheckinSource::Network);
+    assert!(failed.last_success >= status.last_success);
+    assert_eq!(failed.last_error.as_deref(), Some("No checkin configured"));
+    assert_eq!(
+        recorder.get_feature_variant::<bool>("refreshed").await,
+        Some(true)
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test]
+async fn stored_checkins_are_reported_as_cached() {
+    super::init_tracing();
+
+    let fetched_at = chrono::Utc::now() - chrono::Duration::hours(1);
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            RecordingTransport::new(),
+            crate::system_snapshot::Generic::default(),
+            Shared::seeded(StoredProperties {
+                checkin: checkin(),
+                checkin_fetched_at: Some(fetched_at),
+                ..Default::default()
+            }),
+        )
+        .await;
+
+    let status = recorder.checkin_status().await;
+    assert_eq!(status.source, CheckinSource::Cache);
+    assert_eq!(status.last_success, Some(fetched_at));
+
+    drop(recorder);
+    worker.wait().await;
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use crate::checkin::Checkin;
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+fn checkin_valid_for(valid_for: Duration) -> Checkin {
+    CheckinFixture::new().valid_for(valid_for).build()
+}
+
+async fn start(transport: &RecordingTransport) -> (crate::Recorder, tokio::task::JoinHandle<()>) {
+    transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_jitter(Some(0.0))
+                .configuration_refresh_interval(Some(Duration::from_secs(120))),
+        )
+        .await
+}
+
+#[tokio::test(start_paused = true)]
+async fn no_checkins_until_the_checkin_expires() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(checkin_valid_for(Duration::from_secs(5 * 60))))
+        .await;
+
+    // Refreshing every minute would check in four more times before the check-in expires
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_jitter(Some(0.0))
+                .configuration_refresh_interval(Some(Duration::from_secs(60))),
+        )
+        .await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    tokio::time::sleep(Duration::from_secs(5 * 60 - 2)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(transport.checkins(), 2);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn valid_until_replaces_the_refresh_interval() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(checkin_valid_for(Duration::from_secs(10 * 60))))
+        .await;
+
+    let (recorder, worker) = start(&transport).await;
+
+    // The two minute refresh interval is ignored
+    tokio::time::sleep(Duration::from_secs(9 * 60)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    // Without valid_until, the refresh interval applies again
+    transport.set_checkin(Some(Checkin::default())).await;
+    tokio::time::sleep(Duration::from_secs(2 * 60)).await;
+    assert_eq!(transport.checkins(), 2);
+
+    // Refreshes at twelve and fourteen minutes
+    tokio::time::sleep(Duration::from_secs(4 * 60)).await;
+    assert_eq!(transport.checkins(), 4);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn expired_checkins_wait_at_least_a_minute() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new()
+                .valid_until("2001-01-01T00:00:00Z".parse().unwrap())
+                .build(),
+        ))
+        .await;
+
+    let (recorder, worker) = start(&transport).await;
+
+    tokio::time::sleep(Duration::from_secs(59)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(transport.checkins(), 2);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_new_valid_until_is_not_a_change() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(checkin_valid_for(Duration::from_secs(60))))
+        .await;
+
+    let (recorder, worker) = start(&transport).await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    transport
+        .set_checkin(Some(checkin_valid_for(Duration::from_secs(120))))
+        .await;
+    let summary = recorder.refresh_configuration().await.unwrap();
+    assert!(!summary.changed);
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    // Only the initial check-in
+    assert_eq!(
+        transport.events_named("$feature_flags_changed").await.len(),
+        1
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn triggered_refreshes_use_the_cache_until_the_checkin_expires() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(checkin_valid_for(Duration::from_secs(10 * 60))))
+        .await;
+
+    let (recorder, worker) = start(&transport).await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    // Neither changes who the check-in is for
+    recorder
+        .set_person_properties(crate::recorder::IdentifyProperties::default())
+        .await;
+    recorder.alias("another-name").await;
+    assert_eq!(transport.checkins(), 1);
+
+    // Explicit refreshes still reach the server
+    recorder.refresh_configuration().await.unwrap();
+    assert_eq!(transport.checkins(), 2);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn session_changes_bypass_the_cache() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(checkin_valid_for(Duration::from_secs(10 * 60))))
+        .await;
+
+    let (recorder, worker) = start(&transport).await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(transport.checkins(), 1);
+
+    recorder.identify("alice".to_string().into()).await;
+    assert_eq!(transport.checkins(), 2);
+
+    recorder.add_group("org", "acme").await;
+    assert_eq!(transport.checkins(), 3);
+
+    recorder.reset().await;
+    assert_eq!(transport.checkins(), 4);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
@@ -0,0 +1,46 @@
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn child_recorders_prefix_event_names() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    let child = recorder.child_recorder("prefix");
+    let grandchild = child.child_recorder("nested");
+
+    recorder.record("foo", None).await;
+    child.record("foo", None).await;
+    grandchild.record("foo", None).await;
+    child
+        .record_batch(vec![("bar".into(), None), ("baz".into(), None)])
+        .await;
+    child.set_fact("shared", true.into()).await;
+    recorder.record("after_fact", None).await;
+
+    drop((recorder, child, grandchild));
+    worker.await.unwrap();
+
+    let names: Vec<String> = transport
+        .events()
+        .await
+        .iter()
+        .map(|event| event["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "foo",
+            "prefix.foo",
+            "prefix.nested.foo",
+            "prefix.bar",
+            "prefix.baz",
+            "after_fact"
+        ]
+    );
+
+    let after_fact = transport.events_named("after_fact").await;
+    assert_eq!(after_fact[0]["properties"]["shared"], true);
+}
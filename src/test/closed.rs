@@ -0,0 +1,50 @@
+#[test]
+fn closed_after_the_worker_shuts_down() {
+    super::init_tracing();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let (recorder, worker) = runtime.block_on(async {
+        crate::Builder::new()
+            .build_with(
+                crate::transport::Transports::none(),
+                crate::system_snapshot::Generic::default(),
+                crate::storage::Generic::default(),
+            )
+            .await
+    });
+
+    assert!(!recorder.is_closed());
+    assert_eq!(recorder.outgoing_len(), 0);
+    assert!(recorder.outgoing_capacity() > 0);
+
+    // Dropping the runtime drops the Worker's tasks, as if they had panicked or been aborted.
+    drop(worker);
+    drop(runtime);
+
+    assert!(recorder.is_closed());
+}
+
+#[tokio::test]
+async fn outgoing_len_counts_queued_messages() {
+    super::init_tracing();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let (config_tx, _config_rx) = tokio::sync::mpsc::channel(10);
+    let recorder = crate::Recorder::new(tx, config_tx, 5);
+
+    recorder.record("one", None).await;
+    recorder.record("two", None).await;
+
+    assert_eq!(recorder.outgoing_len(), 2);
+    assert_eq!(recorder.outgoing_capacity(), 8);
+
+    rx.recv().await.unwrap();
+
+    assert_eq!(recorder.outgoing_len(), 1);
+    assert!(!recorder.is_closed());
+
+    drop(rx);
+
+    assert!(recorder.is_closed());
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+use crate::{CoalesceOptions, EventNameFilter, Map};
+
+#[tokio::test]
+async fn hammering_collapses_identical_events() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .coalesce_events(Some(CoalesceOptions::new(Duration::from_secs(60)))),
+        )
+        .await;
+
+    let props = Map::from_iter([("url".to_string(), "https://example.com".into())]);
+    let other_props = Map::from_iter([("url".to_string(), "https://example.org".into())]);
+
+    for _ in 0..4000 {
+        recorder.record("fetch_failed", Some(props.clone())).await;
+    }
+    for _ in 0..10 {
+        recorder
+            .record("fetch_failed", Some(other_props.clone()))
+            .await;
+    }
+    recorder.record("started", None).await;
+    recorder.identify("alice".to_string().into()).await;
+    recorder.alias("alice2").await;
+    recorder.alias("alice2").await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let fetch_failed = transport.events_named("fetch_failed").await;
+    assert_eq!(fetch_failed.len(), 2);
+
+    let counts: Vec<_> = fetch_failed
+        .iter()
+        .map(|event| {
+            (
+                event["properties"]["url"].as_str().unwrap().to_string(),
+                event["properties"]["$count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert!(counts.contains(&("https://example.com".to_string(), 4000)));
+    assert!(counts.contains(&("https://example.org".to_string(), 10)));
+
+    for event in &fetch_failed {
+        assert!(event["properties"]["$first_timestamp"].is_string());
+        assert!(event["properties"]["$last_timestamp"].is_string());
+    }
+
+    let started = transport.events_named("started").await;
+    assert_eq!(started.len(), 1);
+    assert!(started[0]["properties"].get("$count").is_none());
+
+    assert_eq!(transport.events_named("$identify").await.len(), 1);
+    assert_eq!(transport.events_named("$create_alias").await.len(), 2);
+}
+
+#[tokio::test]
+async fn denied_events_are_not_coalesced() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new().coalesce_events(Some(
+                CoalesceOptions::new(Duration::from_secs(60))
+                    .event_names(EventNameFilter::Deny(["heartbeat".to_string()].into())),
+            )),
+        )
+        .await;
+
+    for _ in 0..100 {
+        recorder.record("heartbeat", None).await;
+        recorder.record("fetch_failed", None).await;
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert_eq!(transport.events_named("heartbeat").await.len(), 100);
+    assert_eq!(transport.events_named("fetch_failed").await.len(), 1);
+}
+
+#[tokio::test]
+async fn window_expiry_starts_a_new_event() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .coalesce_events(Some(CoalesceOptions::new(Duration::from_millis(50)))),
+        )
+        .await;
+
+    for _ in 0..5 {
+        recorder.record("fetch_failed", None).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for _ in 0..3 {
+        recorder.record("fetch_failed", None).await;
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let counts: Vec<_> = transport
+        .events_named("fetch_failed")
+        .await
+        .iter()
+        .map(|event| event["properties"]["$count"].as_u64().unwrap())
+        .collect();
+    assert_eq!(counts, vec![5, 3]);
+}
@@ -0,0 +1,120 @@
+use crate::storage::{JsonFile, Storage, StoredProperties};
+use crate::test::recording_transport::RecordingTransport;
+
+/// JsonFile storage which loaded before another process first stored, like two processes started together.
+struct LoadedBeforeTheOtherProcess(JsonFile);
+
+impl Storage for LoadedBeforeTheOtherProcess {
+    type Error = <JsonFile as Storage>::Error;
+
+    async fn load(&self) -> Result<Option<StoredProperties>, Self::Error> {
+        Ok(None)
+    }
+
+    async fn store(&mut self, properties: StoredProperties) -> Result<(), Self::Error> {
+        self.0.store(properties).await
+    }
+
+    async fn store_merged(
+        &mut self,
+        properties: StoredProperties,
+    ) -> Result<StoredProperties, Self::Error> {
+        self.0.store_merged(properties).await
+    }
+}
+
+async fn record_once<P: Storage>(storage: P) -> serde_json::Value {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            storage,
+        )
+        .await;
+    let worker = tokio::spawn(worker.wait());
+
+    recorder.record("event", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    transport.events_named("event").await.remove(0)
+}
+
+#[tokio::test]
+async fn processes_racing_to_store_converge_on_one_identity() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let location = dir.path().join("storage.json");
+
+    let first = record_once(JsonFile::try_from_path(&location).unwrap()).await;
+    let second = record_once(LoadedBeforeTheOtherProcess(
+        JsonFile::try_from_path(&location).unwrap(),
+    ))
+    .await;
+
+    for property in ["$anon_distinct_id", "$device_id"] {
+        assert_eq!(
+            first["properties"][property], second["properties"][property],
+            "{property}"
+        );
+    }
+    assert_eq!(first["distinct_id"], second["distinct_id"]);
+
+    let stored = JsonFile::try_from_path(&location)
+        .unwrap()
+        .load()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        first["properties"]["$anon_distinct_id"],
+        stored.anonymous_distinct_id.to_string()
+    );
+}
+
+#[tokio::test]
+async fn a_reset_isnt_undone_by_another_process_storing() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let location = dir.path().join("storage.json");
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            JsonFile::try_from_path(&location).unwrap(),
+        )
+        .await;
+    let worker = tokio::spawn(worker.wait());
+
+    recorder.identify("alice".to_string().into()).await;
+    recorder.record("before", None).await;
+    assert_eq!(recorder.get_distinct_id().await, "alice");
+
+    // Another process stores alice's identity again
+    let mut other = JsonFile::try_from_path(&location).unwrap();
+    let mut properties = other.load().await.unwrap().unwrap();
+    properties.groups.insert("org", "other");
+    other.store(properties).await.unwrap();
+
+    recorder.reset().await;
+    recorder.record("after", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let before = &transport.events_named("before").await[0];
+    let after = &transport.events_named("after").await[0];
+    assert_eq!(before["distinct_id"], "alice");
+    assert_ne!(after["distinct_id"], "alice");
+    assert_ne!(
+        before["properties"]["$anon_distinct_id"],
+        after["properties"]["$anon_distinct_id"]
+    );
+}
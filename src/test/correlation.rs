@@ -0,0 +1,91 @@
+use crate::Correlation;
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn builder_correlation_is_used() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let correlation = Correlation {
+        session_id: Some("builder-session".into()),
+        groups: std::collections::HashMap::from_iter([(
+            "ci".to_string(),
+            Some("builder-ci".to_string()),
+        )]),
+        properties: crate::Map::from_iter([("correlation_source".into(), "builder".into())]),
+        ..Correlation::default()
+    };
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().with_correlation(correlation))
+        .await;
+
+    recorder.record("tick", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("tick").await;
+    assert_eq!(events.len(), 1);
+
+    let properties = &events[0]["properties"];
+    assert_eq!(properties["$session_id"], "builder-session");
+    assert_eq!(properties["$groups"]["ci"], "builder-ci");
+    assert_eq!(properties["correlation_source"], "builder");
+}
+
+#[tokio::test]
+async fn builder_correlation_takes_precedence_over_the_import() {
+    super::init_tracing();
+
+    let imported = Correlation::import_with(|name| {
+        (name == "DETSYS_CORRELATION").then(|| {
+            serde_json::json!({
+                "distinct_id": "imported-distinct",
+                "$session_id": "imported-session",
+                "$device_id": "imported-device",
+                "$groups": { "ci": "imported-ci", "org": "imported-org" },
+                "correlation_source": "import",
+            })
+            .to_string()
+            .into()
+        })
+    });
+    assert_eq!(imported.session_id.as_deref(), Some("imported-session"));
+
+    let supplied = Correlation::default()
+        .with_session_id("builder-session")
+        .with_group("ci", "builder-ci")
+        .with_property("correlation_source", "builder");
+
+    let correlation =
+        Correlation::supplied_or_imported(Some(supplied.clone()), || imported.clone());
+    assert_eq!(
+        correlation,
+        supplied.merge(imported, crate::MergeStrategy::SelfPriority)
+    );
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().with_correlation(correlation))
+        .await;
+
+    recorder.record("tick", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("tick").await;
+    let properties = &events[0]["properties"];
+
+    // Set by the builder
+    assert_eq!(properties["$session_id"], "builder-session");
+    assert_eq!(properties["$groups"]["ci"], "builder-ci");
+    assert_eq!(properties["correlation_source"], "builder");
+
+    // Only imported
+    assert_eq!(events[0]["distinct_id"], "imported-distinct");
+    assert_eq!(properties["$device_id"], "imported-device");
+    assert_eq!(properties["$groups"]["org"], "imported-org");
+}
@@ -0,0 +1,64 @@
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+#[tokio::test]
+async fn defaults_are_served_until_a_checkin_replaces_them() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .default_feature("greeting", "hello", Some("hi".into()))
+                .default_feature("fallback", true, None),
+        )
+        .await;
+
+    let feature = recorder
+        .get_feature::<serde_json::Value>("greeting")
+        .await
+        .unwrap();
+    assert_eq!(feature.variant, "hello");
+    assert_eq!(feature.payload, Some("hi".into()));
+
+    let mut changes = recorder.subscribe_to_feature_changes().await.unwrap();
+    let checkin = CheckinFixture::new().variant("greeting", "howdy").build();
+    transport.set_checkin(Some(checkin)).await;
+
+    recorder.trigger_configuration_refresh().await;
+    changes.recv().await.unwrap();
+
+    assert_eq!(
+        recorder.get_feature_variant::<String>("greeting").await,
+        Some("howdy".to_string())
+    );
+    assert_eq!(
+        recorder.get_feature_variant::<bool>("fallback").await,
+        Some(true)
+    );
+    assert_eq!(recorder.get_feature_variant::<bool>("unknown").await, None);
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let called: Vec<_> = transport
+        .events_named("$feature_flag_called")
+        .await
+        .into_iter()
+        .map(|event| {
+            (
+                event["properties"]["$feature_flag_response"].clone(),
+                event["properties"]["$default"].clone(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        called,
+        vec![
+            ("hello".into(), true.into()),
+            ("howdy".into(), serde_json::Value::Null),
+            (true.into(), true.into()),
+        ]
+    );
+}
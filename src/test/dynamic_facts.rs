@@ -0,0 +1,56 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn dynamic_facts_are_computed_per_event() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let builder_counter = Arc::new(AtomicU64::new(0));
+    let recorder_counter = Arc::new(AtomicU64::new(100));
+
+    let builder = crate::Builder::new()
+        .dynamic_fact("builder_counter", {
+            let counter = builder_counter.clone();
+            move || counter.fetch_add(1, Ordering::SeqCst).into()
+        })
+        .dynamic_fact("panics", || panic!("deliberate panic in a dynamic fact"));
+
+    let (recorder, worker) = transport.spawn_with(builder).await;
+
+    recorder
+        .set_dynamic_fact("recorder_counter", {
+            let counter = recorder_counter.clone();
+            move || counter.fetch_add(1, Ordering::SeqCst).into()
+        })
+        .await;
+
+    for _ in 0..5 {
+        recorder.record("tick", None).await;
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("tick").await;
+    assert_eq!(events.len(), 5);
+
+    let builder_values: Vec<u64> = events
+        .iter()
+        .map(|event| event["properties"]["builder_counter"].as_u64().unwrap())
+        .collect();
+    assert_eq!(builder_values, vec![0, 1, 2, 3, 4]);
+
+    let recorder_values: Vec<u64> = events
+        .iter()
+        .map(|event| event["properties"]["recorder_counter"].as_u64().unwrap())
+        .collect();
+    assert_eq!(recorder_values, vec![100, 101, 102, 103, 104]);
+
+    for event in &events {
+        assert!(event["properties"]["panics"].is_null());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+#[tokio::test]
+async fn exposures_are_recorded_for_served_experiments() {
+    super::init_tracing();
+
+    let checkin = CheckinFixture::new()
+        .feature(
+            "checkout-button",
+            serde_json::json!({ "variant": "green", "experiment_id": "exp-2024-checkout" }),
+        )
+        .build();
+
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(checkin)).await;
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+    recorder.wait_for_checkin(None).await.unwrap();
+
+    assert_eq!(
+        recorder
+            .get_experiment_variant::<String>("checkout-button")
+            .await,
+        Some("green".to_string())
+    );
+    assert_eq!(
+        recorder.get_experiment_variant::<String>("unknown").await,
+        None
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let called = transport.events_named("$feature_flag_called").await;
+    assert_eq!(called.len(), 1);
+    assert_eq!(called[0]["properties"]["$feature_flag"], "checkout-button");
+
+    let exposures = transport.events_named("$experiment_exposure").await;
+    assert_eq!(exposures.len(), 1);
+    assert_eq!(exposures[0]["properties"]["experiment"], "checkout-button");
+    assert_eq!(exposures[0]["properties"]["variant"], "green");
+    assert_eq!(
+        exposures[0]["properties"]["$experiment_id"],
+        "exp-2024-checkout"
+    );
+}
+
+#[tokio::test]
+async fn default_variants_are_not_exposures() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().default_feature("checkout-button", "blue", None))
+        .await;
+
+    assert_eq!(
+        recorder
+            .get_experiment_variant::<String>("checkout-button")
+            .await,
+        Some("blue".to_string())
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let called = transport.events_named("$feature_flag_called").await;
+    assert_eq!(called.len(), 1);
+    assert_eq!(called[0]["properties"]["$default"], true);
+
+    assert!(
+        transport
+            .events_named("$experiment_exposure")
+            .await
+            .is_empty()
+    );
+}
@@ -0,0 +1,55 @@
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+#[tokio::test]
+async fn experiment_ids_are_recorded_on_feature_flag_called() {
+    super::init_tracing();
+
+    let checkin = CheckinFixture::new()
+        .feature(
+            "checkout-button",
+            serde_json::json!({ "variant": "green", "experiment_id": "exp-2024-checkout" }),
+        )
+        .variant("dark-mode", true)
+        .build();
+
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(checkin)).await;
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+    recorder.wait_for_checkin(None).await.unwrap();
+
+    let feature = recorder
+        .get_feature::<serde_json::Value>("checkout-button")
+        .await
+        .unwrap();
+    assert_eq!(feature.experiment_id.as_deref(), Some("exp-2024-checkout"));
+
+    let feature = recorder
+        .get_feature::<serde_json::Value>("dark-mode")
+        .await
+        .unwrap();
+    assert_eq!(feature.experiment_id, None);
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let called: Vec<_> = transport
+        .events_named("$feature_flag_called")
+        .await
+        .into_iter()
+        .map(|event| {
+            (
+                event["properties"]["$feature_flag"].clone(),
+                event["properties"]["$experiment_id"].clone(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        called,
+        vec![
+            ("checkout-button".into(), "exp-2024-checkout".into()),
+            ("dark-mode".into(), serde_json::Value::Null),
+        ]
+    );
+}
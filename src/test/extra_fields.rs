@@ -0,0 +1,41 @@
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn generic_extra_fields_are_merged_in_order() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let snapshotter = crate::system_snapshot::Generic::default()
+        .with_fields(crate::Map::from_iter([
+            ("app_mode".into(), "batch".into()),
+            ("app_region".into(), "eu".into()),
+        ]))
+        .with_extra(|fields| {
+            fields.insert("app_region".into(), "us".into());
+            fields.insert("app_workers".into(), 4.into());
+        });
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            snapshotter,
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    let worker = tokio::spawn(worker.wait());
+
+    recorder.record("tick", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("tick").await;
+    assert_eq!(events.len(), 1);
+
+    let properties = &events[0]["properties"];
+    assert_eq!(properties["app_mode"], "batch");
+    assert_eq!(properties["app_region"], "us");
+    assert_eq!(properties["app_workers"], 4);
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::configuration_proxy::{CheckinStatus, ConfigurationProxySignal};
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+#[tokio::test]
+async fn lagging_behind_still_counts_as_checked_in() {
+    super::init_tracing();
+
+    let (tx, _rx) = tokio::sync::mpsc::channel(10);
+    let (config_tx, mut config_rx) = tokio::sync::mpsc::channel(10);
+    let recorder = crate::Recorder::new(tx, config_tx, 5);
+
+    // Stands in for the configuration proxy, with several changes published before the subscriber reads any
+    let proxy = tokio::spawn(async move {
+        let notifier = tokio::sync::broadcast::Sender::new(1);
+
+        while let Some(signal) = config_rx.recv().await {
+            match signal {
+                ConfigurationProxySignal::Subscribe(reply) => {
+                    reply.send(notifier.subscribe()).unwrap();
+                }
+                ConfigurationProxySignal::QueryIfCheckedIn(reply) => {
+                    notifier.send(()).unwrap();
+                    notifier.send(()).unwrap();
+                    notifier.send(()).unwrap();
+                    reply.send(CheckinStatus::NotYet).unwrap();
+                }
+                other => panic!("Unexpected signal: {other:?}"),
+            }
+        }
+    });
+
+    recorder
+        .wait_for_checkin(Some(Duration::from_secs(5)))
+        .await
+        .unwrap();
+
+    drop(recorder);
+    proxy.await.unwrap();
+}
+
+#[tokio::test]
+async fn subscribers_see_several_changes() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new()
+                .variant("some-feature", "first")
+                .build(),
+        ))
+        .await;
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    recorder
+        .wait_for_checkin(Some(Duration::from_secs(5)))
+        .await
+        .unwrap();
+
+    let mut subscription = recorder.subscribe_to_feature_changes().await.unwrap();
+
+    for variant in ["second", "third", "fourth"] {
+        transport
+            .set_checkin(Some(
+                CheckinFixture::new()
+                    .variant("some-feature", variant)
+                    .build(),
+            ))
+            .await;
+        assert!(recorder.refresh_configuration().await.unwrap().changed);
+    }
+
+    for _ in 0..3 {
+        subscription.recv().await.unwrap();
+    }
+    assert!(subscription.is_empty());
+
+    drop(recorder);
+    worker.await.unwrap();
+}
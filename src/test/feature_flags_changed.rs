@@ -0,0 +1,67 @@
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+#[tokio::test]
+async fn flag_changes_are_recorded() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new()
+                .variant("flipped", true)
+                .variant("retired", "a")
+                .variant("stable", "on")
+                .build(),
+        ))
+        .await;
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    recorder.wait_for_checkin(None).await.unwrap();
+
+    let mut changes = recorder.subscribe_to_feature_changes().await.unwrap();
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new()
+                .variant("flipped", false)
+                .variant("launched", true)
+                .variant("stable", "on")
+                .build(),
+        ))
+        .await;
+    recorder.trigger_configuration_refresh().await;
+    changes.recv().await.unwrap();
+
+    // An unchanged check-in isn't recorded
+    recorder.trigger_configuration_refresh().await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("$feature_flags_changed").await;
+    let properties: Vec<_> = events
+        .iter()
+        .map(|event| {
+            let mut properties = event["properties"].as_object().unwrap().clone();
+            properties.retain(|key, _| {
+                ["initial", "added", "removed", "modified", "server_options"]
+                    .contains(&key.as_str())
+            });
+            serde_json::Value::from(properties)
+        })
+        .collect();
+
+    assert_eq!(
+        properties,
+        vec![
+            serde_json::json!({ "initial": true }),
+            serde_json::json!({
+                "initial": false,
+                "added": ["launched"],
+                "removed": ["retired"],
+                "modified": ["flipped"],
+                "server_options": [],
+            }),
+        ]
+    );
+}
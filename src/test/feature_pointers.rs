@@ -0,0 +1,175 @@
+use crate::test::recording_transport::RecordingTransport;
+
+/// A Builder serving `pointers` as features whose payloads name other features.
+fn builder(pointers: &[(&str, serde_json::Value)]) -> crate::Builder {
+    pointers
+        .iter()
+        .fold(crate::Builder::new(), |builder, (key, payload)| {
+            builder.default_feature(*key, true, Some(payload.clone()))
+        })
+}
+
+#[tokio::test]
+async fn two_hop_chains_are_resolved() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = transport
+        .spawn_with(builder(&[
+            ("a", "b".into()),
+            ("b", "c".into()),
+            ("c", serde_json::json!({ "answer": 42 })),
+        ]))
+        .await;
+
+    let resolved = recorder
+        .get_feature_resolved::<serde_json::Value>("a")
+        .await
+        .unwrap();
+    assert_eq!(resolved.chain, vec!["a", "b", "c"]);
+    assert_eq!(
+        resolved.feature.payload,
+        Some(serde_json::json!({ "answer": 42 }))
+    );
+
+    assert_eq!(
+        recorder
+            .get_feature_ptr_payload::<serde_json::Value>("b")
+            .await,
+        Some(serde_json::json!({ "answer": 42 }))
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    // Only the resolved feature is called, never the pointers to it
+    let called: Vec<_> = transport
+        .events_named("$feature_flag_called")
+        .await
+        .into_iter()
+        .map(|event| {
+            (
+                event["properties"]["$feature_flag"].clone(),
+                event["properties"]["$feature_flag_pointer_chain"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        called,
+        vec![
+            ("c".into(), serde_json::json!(["a", "b", "c"])),
+            ("c".into(), serde_json::json!(["b", "c"])),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn strings_which_are_not_features_end_the_chain() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = transport
+        .spawn_with(builder(&[
+            ("a", "b".into()),
+            ("b", "just a string".into()),
+            ("lonely", "nobody".into()),
+        ]))
+        .await;
+
+    let resolved = recorder.get_feature_resolved::<String>("a").await.unwrap();
+    assert_eq!(resolved.chain, vec!["a", "b"]);
+    assert_eq!(resolved.feature.payload, Some("just a string".to_string()));
+
+    assert!(
+        recorder
+            .get_feature_resolved::<String>("lonely")
+            .await
+            .is_none()
+    );
+    assert!(
+        recorder
+            .get_feature_resolved::<String>("missing")
+            .await
+            .is_none()
+    );
+    assert!(recorder.get_feature_resolved::<u64>("a").await.is_none());
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    // Unresolved and mistyped features aren't called
+    let called: Vec<_> = transport
+        .events_named("$feature_flag_called")
+        .await
+        .into_iter()
+        .map(|event| event["properties"]["$feature_flag"].clone())
+        .collect();
+    assert_eq!(called, vec![serde_json::json!("b")]);
+}
+
+#[tokio::test]
+async fn cycles_are_not_resolved() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = transport
+        .spawn_with(builder(&[
+            ("a", "b".into()),
+            ("b", "a".into()),
+            ("self", "self".into()),
+        ]))
+        .await;
+
+    assert!(recorder.get_feature_resolved::<String>("a").await.is_none());
+    assert!(recorder.get_feature_ptr::<String>("self").await.is_none());
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert!(
+        transport
+            .events_named("$feature_flag_called")
+            .await
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn chains_deeper_than_the_limit_are_not_resolved() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = transport
+        .spawn_with(
+            builder(&[
+                ("a", "b".into()),
+                ("b", "c".into()),
+                ("c", "d".into()),
+                ("d", 4.into()),
+            ])
+            .feature_pointer_depth(Some(2)),
+        )
+        .await;
+
+    assert_eq!(
+        recorder
+            .get_feature_resolved::<u64>("b")
+            .await
+            .unwrap()
+            .chain,
+        vec!["b", "c", "d"]
+    );
+    assert!(recorder.get_feature_resolved::<u64>("a").await.is_none());
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    // Only the chain within the limit is called
+    let chains: Vec<_> = transport
+        .events_named("$feature_flag_called")
+        .await
+        .into_iter()
+        .map(|event| event["properties"]["$feature_flag_pointer_chain"].clone())
+        .collect();
+    assert_eq!(chains, vec![serde_json::json!(["b", "c", "d"])]);
+}
@@ -0,0 +1,76 @@
+use crate::VariantMatch;
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Experiment {
+    A,
+    B,
+    Control,
+}
+
+#[tokio::test]
+async fn variants_match_enum_arms() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let checkin = CheckinFixture::new()
+        .variant("experiment", "b")
+        .variant("control-experiment", "control")
+        .variant("surprise-experiment", "d")
+        .build();
+    transport.set_checkin(Some(checkin)).await;
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    recorder.wait_for_checkin(None).await.unwrap();
+
+    assert_eq!(
+        recorder
+            .get_feature_variant_enum::<Experiment>("experiment")
+            .await,
+        Some(Experiment::B)
+    );
+    assert_eq!(
+        recorder
+            .match_feature_variant::<Experiment>("control-experiment")
+            .await,
+        VariantMatch::Matched(Experiment::Control)
+    );
+    assert_eq!(
+        recorder
+            .match_feature_variant::<Experiment>("surprise-experiment")
+            .await,
+        VariantMatch::Unknown("d".into())
+    );
+    assert_eq!(
+        recorder
+            .get_feature_variant_enum::<Experiment>("missing-experiment")
+            .await,
+        None
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let called: Vec<_> = transport
+        .events_named("$feature_flag_called")
+        .await
+        .into_iter()
+        .map(|event| {
+            (
+                event["properties"]["$feature_flag"].clone(),
+                event["properties"]["$feature_flag_variant_matched"].clone(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        called,
+        vec![
+            ("experiment".into(), "b".into()),
+            ("control-experiment".into(), "control".into()),
+            ("surprise-experiment".into(), serde_json::Value::Null),
+        ]
+    );
+}
@@ -0,0 +1,64 @@
+use crate::test::recording_transport::RecordingTransport;
+use crate::{EventFilter, Map, PrefixFilter};
+
+struct DropNoisy;
+
+impl EventFilter for DropNoisy {
+    fn should_include(&self, _event_name: &str, properties: Option<&Map>) -> bool {
+        properties.and_then(|p| p.get("noisy")) != Some(&true.into())
+    }
+}
+
+#[tokio::test]
+async fn filtered_events_never_reach_the_submitter() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().with_event_filter(PrefixFilter(vec!["debug.".into()])))
+        .await;
+
+    for _ in 0..10 {
+        recorder.record("debug.cache_hit", None).await;
+        recorder.record("build_started", None).await;
+    }
+    recorder.identify("debug.user".to_string().into()).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert!(transport.events_named("debug.cache_hit").await.is_empty());
+    assert_eq!(transport.events_named("build_started").await.len(), 10);
+    assert_eq!(transport.events_named("$identify").await.len(), 1);
+}
+
+#[tokio::test]
+async fn filters_can_inspect_properties() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().with_event_filter(DropNoisy))
+        .await;
+
+    recorder
+        .record(
+            "event",
+            Some(Map::from_iter([("noisy".into(), true.into())])),
+        )
+        .await;
+    recorder
+        .record(
+            "event",
+            Some(Map::from_iter([("noisy".into(), false.into())])),
+        )
+        .await;
+    recorder.record("event", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert_eq!(transport.events_named("event").await.len(), 2);
+}
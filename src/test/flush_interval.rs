@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+
+async fn build(
+    transport: &RecordingTransport,
+    interval: Duration,
+    jitter: Option<Duration>,
+) -> (crate::Recorder, tokio::task::JoinHandle<()>) {
+    transport
+        .spawn_with(
+            crate::Builder::new()
+                .flush_interval(Some(interval))
+                .flush_interval_jitter(jitter),
+        )
+        .await
+}
+
+#[tokio::test(start_paused = true)]
+async fn custom_flush_interval_is_used() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = build(&transport, Duration::from_secs(1), None).await;
+
+    // The Submitter flushes once on startup, then every second
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    recorder.record("tick", None).await;
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(transport.events_named("tick").await.len(), 1);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn jitter_only_delays_flushes() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let (recorder, worker) = build(
+        &transport,
+        Duration::from_secs(1),
+        Some(Duration::from_secs(1)),
+    )
+    .await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    recorder.record("tick", None).await;
+
+    tokio::time::sleep(Duration::from_millis(800)).await;
+    assert!(transport.events_named("tick").await.is_empty());
+
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(transport.events_named("tick").await.len(), 1);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
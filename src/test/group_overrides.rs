@@ -0,0 +1,60 @@
+use crate::test::recording_transport::RecordingTransport;
+use crate::{Groups, Map};
+
+#[tokio::test]
+async fn overrides_apply_to_a_single_event() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().groups(Some(Groups::from_iter([
+            ("project".to_string(), "nix-installer".to_string()),
+            ("github_organization".to_string(), "default-org".to_string()),
+        ]))))
+        .await;
+
+    for i in 0..3 {
+        recorder
+            .record(
+                "normal",
+                Some(Map::from_iter([("i".to_string(), i.into())])),
+            )
+            .await;
+        recorder
+            .record_with_groups(
+                "overridden",
+                Some(Map::from_iter([("i".to_string(), i.into())])),
+                Groups::from_iter([
+                    ("github_organization".to_string(), format!("org-{i}")),
+                    ("extra".to_string(), "yes".to_string()),
+                ]),
+            )
+            .await;
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events().await;
+    assert_eq!(events.len(), 6);
+
+    for event in events {
+        let groups = &event["properties"]["$groups"];
+        let i = event["properties"]["i"].as_u64().unwrap();
+
+        assert_eq!(groups["project"], "nix-installer");
+
+        match event["name"].as_str().unwrap() {
+            "normal" => {
+                assert_eq!(groups["github_organization"], "default-org");
+                assert!(groups.get("extra").is_none());
+            }
+            "overridden" => {
+                assert_eq!(groups["github_organization"], format!("org-{i}"));
+                assert_eq!(groups["extra"], "yes");
+            }
+            other => panic!("Unexpected event {other}"),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use crate::test::http_server;
+use crate::test::recording_transport::RecordingTransport;
+use crate::transport::{Transport, Transports};
+
+async fn http_transport(addr: std::net::SocketAddr) -> Transports {
+    Transports::try_new(
+        Some(format!("http://{addr}")),
+        Duration::from_secs(3),
+        None,
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn http_health_checks_send_head_requests() {
+    super::init_tracing();
+
+    let (addr, mut requests) = http_server::serve().await;
+
+    http_transport(addr).await.health_check().await.unwrap();
+
+    let head = requests.recv().await.unwrap();
+    assert!(head.starts_with("HEAD /check-in "), "{head}");
+}
+
+#[tokio::test]
+async fn unreachable_servers_fail_health_checks() {
+    super::init_tracing();
+
+    let addr = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    };
+
+    assert!(http_transport(addr).await.health_check().await.is_err());
+}
+
+#[tokio::test]
+async fn health_checks_default_to_checking_in() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    assert!(transport.health_check().await.is_err());
+
+    transport
+        .set_checkin(Some(crate::checkin::Checkin::default()))
+        .await;
+    transport.health_check().await.unwrap();
+    assert_eq!(transport.checkins(), 2);
+}
+
+#[tokio::test]
+async fn startup_verification_checks_the_transport_first() {
+    super::init_tracing();
+
+    let (addr, mut requests) = http_server::serve().await;
+
+    let (recorder, worker) = crate::Builder::new()
+        .verify_transport_on_startup(true)
+        .build_with(
+            http_transport(addr).await,
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    let head = requests.recv().await.unwrap();
+    assert!(head.starts_with("HEAD "), "{head}");
+
+    drop(recorder);
+    worker.wait().await;
+}
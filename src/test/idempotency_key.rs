@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use crate::CoalesceOptions;
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn idempotency_keys_are_serialized_per_event() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .coalesce_events(Some(CoalesceOptions::new(Duration::from_secs(60)))),
+        )
+        .await;
+
+    recorder
+        .record_with_idempotency_key("job_finished", None, "job-1")
+        .await;
+    recorder
+        .record_with_idempotency_key("job_finished", None, "job-2")
+        .await;
+    recorder.record("unkeyed", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("job_finished").await;
+    let keys: Vec<&str> = events
+        .iter()
+        .map(|event| event["properties"]["$idempotency_key"].as_str().unwrap())
+        .collect();
+    assert_eq!(keys, ["job-1", "job-2"]);
+
+    let unkeyed = transport.events_named("unkeyed").await;
+    assert_eq!(unkeyed.len(), 1);
+    assert!(unkeyed[0]["properties"].get("$idempotency_key").is_none());
+}
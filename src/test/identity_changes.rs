@@ -0,0 +1,81 @@
+use crate::DistinctIdChange;
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn identify_and_reset_notify_subscribers() {
+    super::init_tracing();
+
+    let (recorder, worker) = RecordingTransport::new()
+        .spawn_with(crate::Builder::new())
+        .await;
+
+    let mut changes = recorder.subscribe_to_identity_changes().await.unwrap();
+
+    recorder.identify("alice".to_string().into()).await;
+    assert_eq!(
+        changes.recv().await.unwrap(),
+        DistinctIdChange {
+            from: None,
+            to: "alice".into(),
+        }
+    );
+
+    // Identifying as the same person again isn't a change
+    recorder.identify("alice".to_string().into()).await;
+    recorder.get_distinct_id().await;
+    assert!(changes.try_recv().is_err());
+
+    recorder.identify("bob".to_string().into()).await;
+    assert_eq!(
+        changes.recv().await.unwrap(),
+        DistinctIdChange {
+            from: Some("alice".into()),
+            to: "bob".into(),
+        }
+    );
+
+    recorder.reset().await;
+    let anonymous = recorder.get_distinct_id().await;
+    assert_eq!(
+        changes.recv().await.unwrap(),
+        DistinctIdChange {
+            from: Some("bob".into()),
+            to: anonymous,
+        }
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test]
+async fn imports_notify_subscribers() {
+    super::init_tracing();
+
+    let build = || async {
+        RecordingTransport::new()
+            .spawn_with(crate::Builder::new())
+            .await
+    };
+
+    let (source, source_worker) = build().await;
+    source.identify("carol".to_string().into()).await;
+    let exported = source.export_identity().await;
+
+    let (recorder, worker) = build().await;
+    let mut changes = recorder.subscribe_to_identity_changes().await.unwrap();
+
+    recorder.import_identity(&exported).await.unwrap();
+    assert_eq!(
+        changes.recv().await.unwrap(),
+        DistinctIdChange {
+            from: None,
+            to: "carol".into(),
+        }
+    );
+
+    drop(source);
+    drop(recorder);
+    source_worker.await.unwrap();
+    worker.await.unwrap();
+}
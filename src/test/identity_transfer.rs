@@ -0,0 +1,89 @@
+use crate::test::recording_transport::RecordingTransport;
+use crate::{DistinctId, Recorder, Worker};
+
+async fn worker_with(
+    transport: RecordingTransport,
+    storage: crate::storage::Shared,
+) -> (Recorder, tokio::task::JoinHandle<()>) {
+    let (recorder, worker): (Recorder, Worker) = crate::Builder::new()
+        .build_with(
+            transport,
+            crate::system_snapshot::Generic::default(),
+            storage,
+        )
+        .await;
+
+    (recorder, tokio::spawn(worker.wait()))
+}
+
+#[tokio::test]
+async fn identities_carry_across_machines() {
+    super::init_tracing();
+
+    let old_storage = crate::storage::Shared::new();
+    let (recorder, worker) = worker_with(RecordingTransport::new(), old_storage.clone()).await;
+    recorder
+        .identify(DistinctId::from("alice".to_string()))
+        .await;
+    recorder.add_group("org", "acme").await;
+    let exported = recorder.export_identity().await;
+    drop(recorder);
+    worker.await.unwrap();
+
+    let old = old_storage.handle().get().unwrap();
+
+    let transport = RecordingTransport::new();
+    let new_storage = crate::storage::Shared::new();
+    let (recorder, worker) = worker_with(transport.clone(), new_storage.clone()).await;
+    assert_ne!(new_storage.handle().get().unwrap().device_id, old.device_id);
+
+    recorder.import_identity(&exported).await.unwrap();
+    assert_eq!(recorder.get_distinct_id().await, "alice");
+    recorder.record("after_import", None).await;
+    drop(recorder);
+    worker.await.unwrap();
+
+    let new = new_storage.handle().get().unwrap();
+    assert_eq!(new.device_id, old.device_id);
+    assert_eq!(new.anonymous_distinct_id, old.anonymous_distinct_id);
+    assert_eq!(new.distinct_id, old.distinct_id);
+    assert_eq!(new.groups, old.groups);
+
+    let imported = transport.events_named("$identity_imported").await;
+    assert_eq!(imported.len(), 1);
+
+    let events = transport.events_named("after_import").await;
+    assert_eq!(events[0]["distinct_id"], "alice");
+    assert_eq!(
+        events[0]["properties"]["$device_id"],
+        old.device_id.to_string()
+    );
+}
+
+#[tokio::test]
+async fn malformed_imports_leave_the_identity_alone() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    let storage = crate::storage::Shared::new();
+    let (recorder, worker) = worker_with(transport.clone(), storage.clone()).await;
+    recorder
+        .identify(DistinctId::from("alice".to_string()))
+        .await;
+    let before = storage.handle().get().unwrap();
+
+    assert!(recorder.import_identity("{\"version\": 1}").await.is_err());
+    assert!(recorder.import_identity("").await.is_err());
+
+    assert_eq!(recorder.get_distinct_id().await, "alice");
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert_eq!(storage.handle().get().unwrap(), before);
+    assert!(
+        transport
+            .events_named("$identity_imported")
+            .await
+            .is_empty()
+    );
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+
+async fn wait_for_attempts(transport: &RecordingTransport, count: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while transport.attempts().await.len() < count {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+fn insert_ids(events: &[serde_json::Value]) -> Vec<String> {
+    events
+        .iter()
+        .map(|event| {
+            event["properties"]["$insert_id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn insert_ids_are_unique_and_sequenced() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    for _ in 0..50 {
+        recorder.record("event", None).await;
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("event").await;
+    assert_eq!(events.len(), 50);
+
+    let ids = insert_ids(&events);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 50);
+
+    for event in &events {
+        assert_eq!(event["properties"]["$insert_id"], event["uuid"]);
+
+        let uuid: uuid::Uuid = event["uuid"].as_str().unwrap().parse().unwrap();
+        assert_eq!(uuid.get_version(), Some(uuid::Version::SortRand));
+    }
+
+    let seqs: Vec<u64> = events
+        .iter()
+        .map(|event| event["properties"]["$seq"].as_u64().unwrap())
+        .collect();
+    assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]), "{seqs:?}");
+}
+
+#[tokio::test]
+async fn retried_batches_keep_their_insert_ids() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport.set_fail_submissions(true);
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    for _ in 0..5 {
+        recorder.record("event", None).await;
+    }
+    recorder.flush_now().await;
+    wait_for_attempts(&transport, 1).await;
+
+    transport.set_fail_submissions(false);
+    recorder.flush_now().await;
+    wait_for_attempts(&transport, 2).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let attempts = transport.attempts().await;
+    assert_eq!(insert_ids(&attempts[0]), insert_ids(&attempts[1]));
+    assert_eq!(
+        insert_ids(&attempts[1]),
+        insert_ids(&transport.events().await)
+    );
+}
+
+#[tokio::test]
+async fn each_process_counts_from_zero_with_its_own_instance() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    for _ in 0..2 {
+        let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+        for _ in 0..3 {
+            recorder.record("event", None).await;
+        }
+
+        drop(recorder);
+        worker.await.unwrap();
+    }
+
+    let events = transport.events_named("event").await;
+    assert_eq!(events.len(), 6);
+
+    let mut by_instance: std::collections::HashMap<String, Vec<u64>> = Default::default();
+    for event in &events {
+        by_instance
+            .entry(
+                event["properties"]["$seq_instance"]
+                    .as_str()
+                    .unwrap()
+                    .into(),
+            )
+            .or_default()
+            .push(event["properties"]["$seq"].as_u64().unwrap());
+    }
+
+    // Both count from zero, and the instance tells them apart
+    assert_eq!(by_instance.len(), 2);
+    for seqs in by_instance.values() {
+        assert_eq!(seqs, &vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use crate::checkin::Checkin;
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+fn checkin(telemetry_enabled: bool) -> Checkin {
+    CheckinFixture::new()
+        .server_option("compression_algorithms", serde_json::json!([]))
+        .server_option("telemetry_enabled", telemetry_enabled)
+        .build()
+}
+
+#[tokio::test]
+async fn server_pause_silences_the_client_until_lifted() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(checkin(false))).await;
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    recorder.wait_for_checkin(None).await.unwrap();
+
+    for _ in 0..3 {
+        recorder.record("while_paused", None).await;
+    }
+    recorder.flush_now().await;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while worker.dropped_event_count() < 3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let mut changes = recorder.subscribe_to_feature_changes().await.unwrap();
+    transport.set_checkin(Some(checkin(true))).await;
+    recorder.trigger_configuration_refresh().await;
+    changes.recv().await.unwrap();
+
+    recorder.record("after_pause", None).await;
+
+    drop(recorder);
+    worker.wait().await;
+
+    assert!(transport.events_named("while_paused").await.is_empty());
+    assert_eq!(transport.events_named("after_pause").await.len(), 1);
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn worker_exposes_submitter_metrics() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    assert_eq!(worker.submitted_event_count(), 0);
+    assert_eq!(worker.failed_batch_count(), 0);
+
+    transport.set_fail_submissions(true);
+    for _ in 0..3 {
+        recorder.record("event", None).await;
+    }
+    recorder.flush_now().await;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while worker.failed_batch_count() < 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(worker.submitted_event_count(), 0);
+    assert_eq!(worker.pending_event_count(), 3);
+
+    transport.set_fail_submissions(false);
+    recorder.flush_now().await;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while worker.submitted_event_count() < 3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(worker.failed_batch_count(), 1);
+    assert_eq!(worker.pending_event_count(), 0);
+
+    drop(recorder);
+    worker.wait().await;
+}
+
+#[tokio::test]
+async fn drop_oldest_limits_pending_events_to_the_queue_size() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport.set_fail_submissions(true);
+
+    let (recorder, worker) = crate::Builder::new()
+        .overflow_strategy(crate::SubmitterOverflowStrategy::DropOldest)
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    for i in 0..1005 {
+        recorder
+            .record(
+                "event",
+                Some(crate::Map::from_iter([("i".into(), i.into())])),
+            )
+            .await;
+    }
+    recorder.flush_now().await;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while worker.dropped_event_count() < 5 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(worker.dropped_event_count(), 5);
+    assert_eq!(worker.pending_event_count(), 1000);
+
+    transport.set_fail_submissions(false);
+    recorder.flush_now().await;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while worker.submitted_event_count() < 1000 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let first = transport.events_named("event").await[0]["properties"]["i"].clone();
+    assert_eq!(first, 5);
+
+    drop(recorder);
+    worker.wait().await;
+}
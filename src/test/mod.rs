@@ -1,6 +1,59 @@
+mod async_snapshotter;
 mod basic;
+mod batch;
+mod builder_macro;
+mod cached_checkin;
+mod checkin_backoff;
+mod checkin_coalescing;
+mod checkin_status;
+mod checkin_valid_until;
+mod child_recorder;
+mod closed;
+mod coalesce;
+mod concurrent_storage;
+mod correlation;
+mod default_features;
+mod dynamic_facts;
+mod experiment_exposure;
+mod experiment_id;
+mod extra_fields;
+mod feature_change_lag;
+mod feature_flags_changed;
+mod feature_pointers;
+mod feature_variant_enum;
+mod filter;
+mod flush_interval;
+mod group_overrides;
+mod health_check;
+pub(crate) mod http_server;
+mod idempotency_key;
+mod identity_changes;
+mod identity_transfer;
+mod insert_id;
+mod kill_switch;
+mod metrics;
+mod person_property;
+mod priority_event;
+mod process_stats;
+pub(crate) mod recording_transport;
+mod refresh_interval;
+mod refresh_jitter;
+mod server_batching;
+mod session_properties;
+mod set_once;
+mod shared_storage;
+mod shutdown;
 mod slow_transport;
+mod srv_transport;
+mod storage_path;
+mod telemetry_env;
+mod timed_event;
 mod timeout;
+mod trace_context;
+mod transformer;
+mod transport_errors;
+mod udp_transport;
+mod unix_transport;
 
 use once_cell::sync::Lazy;
 use tracing_subscriber::fmt;
@@ -0,0 +1,53 @@
+use serde_json::json;
+
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn single_properties_are_set() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    recorder.set_person_property("plan", "pro").await;
+    recorder.set_person_property_once("first_seen", 2024).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("$set").await;
+    assert_eq!(events.len(), 2);
+
+    assert_eq!(events[0]["properties"]["$set"], json!({ "plan": "pro" }));
+    assert_eq!(events[0]["properties"]["$set_once"], json!({}));
+
+    assert_eq!(events[1]["properties"]["$set"], json!({}));
+    assert_eq!(
+        events[1]["properties"]["$set_once"],
+        json!({ "first_seen": 2024 })
+    );
+}
+
+#[tokio::test]
+async fn unset_properties_are_identified() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    recorder.unset_person_property("plan").await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("$identify").await;
+    assert_eq!(events.len(), 1);
+
+    let properties = &events[0]["properties"];
+    assert_eq!(properties["$unset"], json!(["plan"]));
+    assert_eq!(properties["$set"], json!({}));
+    assert_eq!(properties["$set_once"], json!({}));
+    assert!(events[0]["distinct_id"].is_string(), "{:#}", events[0]);
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test(start_paused = true)]
+async fn priority_events_skip_the_flush_interval() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    // Let the Submitter's first flush pass
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    recorder.record("routine", None).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(transport.events().await.is_empty());
+
+    // Well within the 30 second flush interval, the priority event goes out with the routine one
+    recorder.record_priority("crash", None).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let attempts = transport.attempts().await;
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(
+        attempts[0]
+            .iter()
+            .map(|event| event["name"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        ["routine", "crash"]
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+}
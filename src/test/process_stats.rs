@@ -0,0 +1,43 @@
+use crate::test::recording_transport::RecordingTransport;
+
+async fn record_tick(capture: bool) -> serde_json::Value {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().capture_process_stats(capture))
+        .await;
+
+    recorder.record("tick", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let mut events = transport.events_named("tick").await;
+    assert_eq!(events.len(), 1);
+
+    events.remove(0)["properties"].take()
+}
+
+#[tokio::test]
+async fn process_stats_are_added_when_enabled() {
+    super::init_tracing();
+
+    let properties = record_tick(true).await;
+
+    assert!(properties["process_rss_bytes"].as_u64().unwrap() > 0);
+    assert!(properties["process_cpu_seconds"].as_f64().unwrap() >= 0.0);
+
+    #[cfg(target_os = "linux")]
+    assert!(properties["open_fd_count"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn process_stats_are_off_by_default() {
+    super::init_tracing();
+
+    let properties = record_tick(false).await;
+
+    for key in ["process_rss_bytes", "process_cpu_seconds", "open_fd_count"] {
+        assert!(properties.get(key).is_none(), "{key} was reported");
+    }
+}
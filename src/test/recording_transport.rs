@@ -0,0 +1,177 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use tokio::sync::Mutex;
+
+use crate::{checkin::Checkin, transport::Transport};
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("No checkin configured")]
+    NoCheckin,
+
+    #[error("Simulated error")]
+    Simulated,
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+/// A transport which keeps every submitted event in memory for later inspection.
+#[derive(Clone, Default)]
+pub(crate) struct RecordingTransport {
+    checkin_val: Arc<Mutex<Option<Checkin>>>,
+    events: Arc<Mutex<Vec<serde_json::Value>>>,
+    attempts: Arc<Mutex<Vec<Vec<serde_json::Value>>>>,
+    fail_submissions: Arc<AtomicBool>,
+    checkins: Arc<AtomicUsize>,
+    checkin_times: Arc<Mutex<Vec<tokio::time::Instant>>>,
+}
+
+impl RecordingTransport {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) async fn set_checkin(&self, checkin: Option<Checkin>) {
+        *self.checkin_val.lock().await = checkin;
+    }
+
+    /// How many times the client has checked in.
+    pub(crate) fn checkins(&self) -> usize {
+        self.checkins.load(Ordering::SeqCst)
+    }
+
+    /// When each check-in happened.
+    pub(crate) async fn checkin_times(&self) -> Vec<tokio::time::Instant> {
+        self.checkin_times.lock().await.clone()
+    }
+
+    pub(crate) fn set_fail_submissions(&self, fail: bool) {
+        self.fail_submissions.store(fail, Ordering::SeqCst);
+    }
+
+    pub(crate) async fn events(&self) -> Vec<serde_json::Value> {
+        self.events.lock().await.clone()
+    }
+
+    /// The events of every submission attempt, including failed attempts.
+    pub(crate) async fn attempts(&self) -> Vec<Vec<serde_json::Value>> {
+        self.attempts.lock().await.clone()
+    }
+
+    pub(crate) async fn events_named(&self, name: &str) -> Vec<serde_json::Value> {
+        self.events()
+            .await
+            .into_iter()
+            .filter(|event| event["name"] == name)
+            .collect()
+    }
+
+    /// Build `builder` on this transport with the generic snapshotter and in-memory storage, and run its worker.
+    pub(crate) async fn spawn_with(
+        &self,
+        mut builder: crate::Builder,
+    ) -> (crate::Recorder, tokio::task::JoinHandle<()>) {
+        let (recorder, worker) = builder
+            .build_with(
+                self.clone(),
+                crate::system_snapshot::Generic::default(),
+                crate::storage::Generic::default(),
+            )
+            .await;
+
+        (recorder, tokio::spawn(worker.wait()))
+    }
+}
+
+/// Builds a check-in the way the server would send it.
+#[derive(Default)]
+pub(crate) struct CheckinFixture {
+    options: serde_json::Map<String, serde_json::Value>,
+    server_options: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CheckinFixture {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a feature with just a variant.
+    pub(crate) fn variant(self, name: &str, variant: impl Into<serde_json::Value>) -> Self {
+        self.feature(name, serde_json::json!({ "variant": variant.into() }))
+    }
+
+    /// Add a feature given as the server's JSON for it.
+    pub(crate) fn feature(mut self, name: &str, feature: serde_json::Value) -> Self {
+        self.options.insert(name.into(), feature);
+        self
+    }
+
+    pub(crate) fn server_option(mut self, name: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.server_options.insert(name.into(), value.into());
+        self
+    }
+
+    pub(crate) fn valid_until(self, valid_until: chrono::DateTime<chrono::Utc>) -> Self {
+        self.server_option("valid_until", valid_until.to_rfc3339())
+    }
+
+    /// Expire the check-in `valid_for` from now.
+    pub(crate) fn valid_for(self, valid_for: std::time::Duration) -> Self {
+        self.valid_until(chrono::Utc::now() + valid_for)
+    }
+
+    pub(crate) fn build(self) -> Checkin {
+        let mut checkin = serde_json::json!({ "options": self.options });
+
+        if !self.server_options.is_empty() {
+            let mut server_options = self.server_options;
+            server_options
+                .entry("compression_algorithms")
+                .or_insert_with(|| serde_json::json!(["zstd"]));
+            checkin["server_options"] = server_options.into();
+        }
+
+        serde_json::from_value(checkin).unwrap()
+    }
+}
+
+impl Transport for RecordingTransport {
+    type Error = Error;
+
+    async fn checkin(
+        &self,
+        _session_properties: crate::Map,
+    ) -> Result<crate::checkin::Checkin, Self::Error> {
+        self.checkins.fetch_add(1, Ordering::SeqCst);
+        self.checkin_times
+            .lock()
+            .await
+            .push(tokio::time::Instant::now());
+
+        (*self.checkin_val.lock().await)
+            .clone()
+            .ok_or(Error::NoCheckin)
+    }
+
+    async fn submit(&mut self, batch: crate::submitter::Batch<'_>) -> Result<(), Self::Error> {
+        let serde_json::Value::Object(mut batch) = serde_json::to_value(&batch)? else {
+            unreachable!("Batch always serializes to an object");
+        };
+
+        let Some(serde_json::Value::Array(events)) = batch.remove("batch") else {
+            unreachable!("Batch always has a batch array");
+        };
+
+        self.attempts.lock().await.push(events.clone());
+
+        if self.fail_submissions.load(Ordering::SeqCst) {
+            return Err(Error::Simulated);
+        }
+
+        self.events.lock().await.extend(events);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use crate::checkin::Checkin;
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+fn checkin_with_refresh_interval(seconds: u64) -> Checkin {
+    CheckinFixture::new()
+        .server_option("refresh_interval_seconds", seconds)
+        .build()
+}
+
+#[tokio::test(start_paused = true)]
+async fn refresh_interval_follows_the_server() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(checkin_with_refresh_interval(60)))
+        .await;
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().configuration_refresh_jitter(Some(0.0)))
+        .await;
+
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    let initial = transport.checkins();
+
+    // Ticks at 60s, 120s, and 180s
+    tokio::time::sleep(Duration::from_secs(180)).await;
+    assert_eq!(transport.checkins(), initial + 3);
+
+    // The tick at 240s picks up the new interval, so the next is at 840s
+    transport
+        .set_checkin(Some(checkin_with_refresh_interval(600)))
+        .await;
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert_eq!(transport.checkins(), initial + 4);
+
+    tokio::time::sleep(Duration::from_secs(540)).await;
+    assert_eq!(transport.checkins(), initial + 4);
+
+    tokio::time::sleep(Duration::from_secs(120)).await;
+    assert_eq!(transport.checkins(), initial + 5);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+/// How many times the client checks in over `elapsed`, after the initial check-in.
+async fn checkins_over(interval: Option<Duration>, elapsed: Duration) -> usize {
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(Checkin::default())).await;
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_jitter(Some(0.0))
+                .configuration_refresh_interval(interval),
+        )
+        .await;
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let initial = transport.checkins();
+
+    tokio::time::sleep(elapsed).await;
+    let checkins = transport.checkins() - initial;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    checkins
+}
+
+#[tokio::test(start_paused = true)]
+async fn builder_sets_the_baseline_interval() {
+    super::init_tracing();
+
+    let three_hours = Duration::from_secs(3 * 60 * 60);
+
+    assert_eq!(
+        checkins_over(Some(Duration::from_secs(15 * 60)), three_hours).await,
+        12
+    );
+    assert_eq!(
+        checkins_over(Some(Duration::from_secs(60 * 60)), three_hours).await,
+        3
+    );
+    assert_eq!(checkins_over(None, three_hours).await, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn builder_interval_has_a_minimum() {
+    super::init_tracing();
+
+    assert_eq!(
+        checkins_over(Some(Duration::from_secs(1)), Duration::from_secs(10 * 60)).await,
+        10
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn explicit_checkins_restart_the_interval() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(Checkin::default())).await;
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_jitter(Some(0.0))
+                .configuration_refresh_interval(Some(Duration::from_secs(10 * 60))),
+        )
+        .await;
+
+    tokio::time::sleep(Duration::from_secs(5 * 60)).await;
+    let initial = transport.checkins();
+
+    // The next tick moves from 10 to 15 minutes
+    recorder.trigger_configuration_refresh().await;
+    assert_eq!(transport.checkins(), initial + 1);
+
+    tokio::time::sleep(Duration::from_secs(9 * 60)).await;
+    assert_eq!(transport.checkins(), initial + 1);
+
+    tokio::time::sleep(Duration::from_secs(2 * 60)).await;
+    assert_eq!(transport.checkins(), initial + 2);
+
+    drop(recorder);
+    worker.await.unwrap();
+}
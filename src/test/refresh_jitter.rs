@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::checkin::Checkin;
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test(start_paused = true)]
+async fn refreshes_are_spread_within_the_jitter_band() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(Checkin::default())).await;
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_interval(Some(Duration::from_secs(600)))
+                .configuration_refresh_jitter(Some(0.2)),
+        )
+        .await;
+
+    tokio::time::sleep(Duration::from_secs(6 * 60 * 60)).await;
+
+    let times = transport.checkin_times().await;
+    assert!(times.len() > 20, "{} check-ins", times.len());
+
+    // The first refresh is also offset by up to 20% of the interval
+    let first = times[1] - times[0];
+    assert!(first >= Duration::from_secs(480), "{first:?}");
+    assert!(first <= Duration::from_secs(840), "{first:?}");
+
+    let gaps: Vec<Duration> = times[1..].windows(2).map(|w| w[1] - w[0]).collect();
+    for gap in &gaps {
+        assert!(*gap >= Duration::from_secs(480), "{gap:?}");
+        assert!(*gap <= Duration::from_secs(720), "{gap:?}");
+    }
+    assert!(gaps.iter().any(|gap| *gap != gaps[0]));
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn no_jitter_keeps_the_interval() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport.set_checkin(Some(Checkin::default())).await;
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .configuration_refresh_interval(Some(Duration::from_secs(600)))
+                .configuration_refresh_jitter(Some(0.0)),
+        )
+        .await;
+
+    tokio::time::sleep(Duration::from_secs(60 * 60 + 1)).await;
+
+    let times = transport.checkin_times().await;
+    assert_eq!(times.len(), 7);
+    for gap in times.windows(2).map(|w| w[1] - w[0]) {
+        assert_eq!(gap, Duration::from_secs(600));
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+}
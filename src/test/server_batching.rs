@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::{CheckinFixture, RecordingTransport};
+
+async fn record(recorder: &crate::Recorder, name: &str, count: usize) {
+    for i in 0..count {
+        recorder
+            .record(name, Some(crate::Map::from_iter([("i".into(), i.into())])))
+            .await;
+    }
+}
+
+/// The sizes of the submitted batches which carried events named `name`.
+async fn batch_sizes(transport: &RecordingTransport, name: &str) -> Vec<usize> {
+    transport
+        .attempts()
+        .await
+        .into_iter()
+        .filter(|batch| batch.iter().any(|event| event["name"] == name))
+        .map(|batch| batch.len())
+        .collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn submitter_follows_the_server() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new()
+                .server_option("flush_interval_seconds", 10)
+                .server_option("max_batch_size", 2)
+                .server_option("max_pending_events", 4)
+                .build(),
+        ))
+        .await;
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().max_batch_size(Some(3)))
+        .await;
+
+    recorder.wait_for_checkin(None).await.unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // Only the newest four are kept, and they go out within ten seconds, two at a time
+    record(&recorder, "first", 6).await;
+    tokio::time::sleep(Duration::from_secs(11)).await;
+
+    let first = transport.events_named("first").await;
+    assert_eq!(
+        first
+            .iter()
+            .map(|event| event["properties"]["i"].as_u64().unwrap())
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4, 5]
+    );
+    assert!(
+        batch_sizes(&transport, "first")
+            .await
+            .iter()
+            .all(|size| *size <= 2)
+    );
+
+    // The second check-in lifts the pending limit, flushes every ten minutes,
+    // and asks for larger batches than the Builder allows
+    transport
+        .set_checkin(Some(
+            CheckinFixture::new()
+                .server_option("flush_interval_seconds", 600)
+                .server_option("max_batch_size", 50)
+                .build(),
+        ))
+        .await;
+    recorder.trigger_configuration_refresh().await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    record(&recorder, "second", 7).await;
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert!(transport.events_named("second").await.is_empty());
+
+    tokio::time::sleep(Duration::from_secs(600)).await;
+    assert_eq!(transport.events_named("second").await.len(), 7);
+    assert!(
+        batch_sizes(&transport, "second")
+            .await
+            .iter()
+            .all(|size| *size <= 3)
+    );
+
+    drop(recorder);
+    worker.await.unwrap();
+}
@@ -0,0 +1,45 @@
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn session_id_matches_emitted_events() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    let session_id = recorder.get_session_id().await.unwrap();
+    recorder.record("event", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("event").await;
+    assert_eq!(events[0]["properties"]["$session_id"], session_id.as_str());
+}
+
+#[tokio::test]
+async fn distinct_id_follows_identify() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    let anonymous = recorder.get_distinct_id().await;
+    assert!(!anonymous.is_empty());
+
+    recorder.record("before", None).await;
+    recorder.identify("alice".to_string().into()).await;
+    assert_eq!(recorder.get_distinct_id().await, "alice");
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let before = transport.events_named("before").await;
+    assert_eq!(before[0]["distinct_id"], anonymous.as_str());
+    assert_eq!(
+        before[0]["properties"]["$anon_distinct_id"],
+        anonymous.as_str()
+    );
+}
@@ -0,0 +1,107 @@
+use crate::test::recording_transport::RecordingTransport;
+use crate::{DistinctId, IdentifyProperties, PersonProperties};
+
+fn set_once(keys: &[&str]) -> IdentifyProperties {
+    let mut set_once = PersonProperties::new();
+    for key in keys {
+        set_once.insert(*key, "value");
+    }
+
+    IdentifyProperties {
+        set_once,
+        ..Default::default()
+    }
+}
+
+fn sent_set_once_keys(event: &serde_json::Value) -> Vec<String> {
+    let mut keys: Vec<String> = event["properties"]["$set_once"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    keys.sort();
+    keys
+}
+
+async fn identify_all(
+    storage: crate::storage::Shared,
+    calls: Vec<Option<IdentifyProperties>>,
+) -> Vec<Vec<String>> {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            storage,
+        )
+        .await;
+
+    let worker = tokio::spawn(worker.wait());
+
+    for call in calls {
+        match call {
+            Some(properties) => {
+                recorder
+                    .identify_with_properties(DistinctId::from("alice".to_string()), properties)
+                    .await
+            }
+            None => recorder.reset().await,
+        }
+    }
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    transport
+        .events_named("$identify")
+        .await
+        .iter()
+        .map(sent_set_once_keys)
+        .collect()
+}
+
+#[tokio::test]
+async fn set_once_properties_are_only_sent_once() {
+    super::init_tracing();
+
+    let storage = crate::storage::Shared::new();
+
+    let sent = identify_all(
+        storage.clone(),
+        vec![
+            Some(set_once(&["first_seen", "plan"])),
+            Some(set_once(&["first_seen", "referrer"])),
+        ],
+    )
+    .await;
+    assert_eq!(sent, vec![vec!["first_seen", "plan"], vec!["referrer"]]);
+
+    let stored = storage.handle().get().unwrap();
+    assert_eq!(stored.sent_set_once, ["first_seen", "plan", "referrer"]);
+
+    // A later run with the same storage remembers what was sent
+    let sent = identify_all(
+        storage.clone(),
+        vec![Some(set_once(&["first_seen", "plan"]))],
+    )
+    .await;
+    assert_eq!(sent, vec![Vec::<String>::new()]);
+}
+
+#[tokio::test]
+async fn reset_forgets_sent_set_once_properties() {
+    super::init_tracing();
+
+    let sent = identify_all(
+        crate::storage::Shared::new(),
+        vec![
+            Some(set_once(&["first_seen"])),
+            None,
+            Some(set_once(&["first_seen"])),
+        ],
+    )
+    .await;
+    assert_eq!(sent, vec![vec!["first_seen"], vec!["first_seen"]]);
+}
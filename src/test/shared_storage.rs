@@ -0,0 +1,75 @@
+use crate::storage::{Shared, StoredProperties};
+use crate::test::recording_transport::RecordingTransport;
+
+async fn run(storage: Shared, f: impl AsyncFnOnce(&crate::Recorder)) -> RecordingTransport {
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            storage,
+        )
+        .await;
+
+    let worker = tokio::spawn(worker.wait());
+
+    f(&recorder).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    transport
+}
+
+#[tokio::test]
+async fn seeded_identity_is_used() {
+    super::init_tracing();
+
+    let seeded = StoredProperties {
+        device_id: "seeded-device".to_string().into(),
+        ..Default::default()
+    };
+
+    let transport = run(Shared::seeded(seeded.clone()), async |recorder| {
+        recorder.record("event", None).await;
+    })
+    .await;
+
+    let events = transport.events_named("event").await;
+    assert_eq!(
+        events[0]["properties"]["$anon_distinct_id"],
+        seeded.anonymous_distinct_id.to_string()
+    );
+    assert_eq!(events[0]["properties"]["$device_id"], "seeded-device");
+}
+
+#[tokio::test]
+async fn stored_state_is_visible_through_the_handle() {
+    super::init_tracing();
+
+    let storage = Shared::new();
+    let handle = storage.handle();
+    assert_eq!(handle.get(), None);
+
+    run(storage.clone(), async |recorder| {
+        recorder.identify("alice".to_string().into()).await;
+    })
+    .await;
+
+    let stored = handle.get().unwrap();
+    assert_eq!(stored.distinct_id, Some("alice".to_string().into()));
+
+    // A second worker sharing the storage picks up the persisted identity
+    let transport = run(storage, async |recorder| {
+        recorder.record("event", None).await;
+    })
+    .await;
+
+    let events = transport.events_named("event").await;
+    assert_eq!(events[0]["distinct_id"], "alice");
+    assert_eq!(
+        events[0]["properties"]["$anon_distinct_id"],
+        stored.anonymous_distinct_id.to_string()
+    );
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+
+#[tokio::test]
+async fn shutdown_stops_the_worker_while_recorders_remain() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport.clone(),
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    let shutdown = worker.shutdown_handle();
+
+    recorder.record("before", None).await;
+    shutdown.shutdown();
+
+    tokio::time::timeout(Duration::from_secs(5), worker.wait())
+        .await
+        .expect("the worker should stop after shutdown");
+
+    assert_eq!(transport.events_named("before").await.len(), 1);
+    assert!(recorder.is_closed());
+
+    // The recorder is still usable, but nothing happens
+    recorder.record("after", None).await;
+    assert!(recorder.get_feature::<bool>("anything").await.is_none());
+    assert!(transport.events_named("after").await.is_empty());
+}
+
+#[tokio::test]
+async fn shutdown_is_idempotent() {
+    super::init_tracing();
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            RecordingTransport::new(),
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    let shutdown = worker.shutdown_handle();
+    shutdown.shutdown();
+    shutdown.clone().shutdown();
+
+    tokio::time::timeout(Duration::from_secs(5), worker.wait())
+        .await
+        .expect("the worker should stop after shutdown");
+
+    drop(recorder);
+}
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+
+use detsys_srv::SrvClient;
+use detsys_srv::resolver::SrvResolver;
+use tokio::net::TcpListener;
+use url::Url;
+
+use crate::transport::Transport;
+use crate::transport::srv_http::{SrvHttpTransport, SrvHttpTransportError};
+
+struct Record {
+    target: String,
+    port: u16,
+}
+
+impl detsys_srv::SrvRecord for Record {
+    type Target = str;
+
+    fn target(&self) -> &str {
+        &self.target
+    }
+
+    fn port(&self) -> u16 {
+        self.port
+    }
+
+    fn priority(&self) -> u16 {
+        0
+    }
+
+    fn weight(&self) -> u16 {
+        0
+    }
+}
+
+/// Resolves every SRV record to the same local ports, or fails if there are none.
+struct FixedResolver(Vec<u16>);
+
+#[async_trait::async_trait]
+impl SrvResolver for FixedResolver {
+    type Record = Record;
+    type Error = std::io::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        _srv: &str,
+    ) -> Result<(Vec<Record>, Instant), std::io::Error> {
+        if self.0.is_empty() {
+            return Err(std::io::Error::other("no such record"));
+        }
+
+        let records = self
+            .0
+            .iter()
+            .map(|&port| Record {
+                target: String::from("127.0.0.1"),
+                port,
+            })
+            .collect();
+
+        Ok((records, Instant::now() + Duration::from_secs(60)))
+    }
+}
+
+/// A local port which nothing is listening on.
+async fn closed_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+async fn transport(resolved: Vec<u16>) -> SrvHttpTransport<FixedResolver> {
+    let fallback = Url::parse(&format!("http://127.0.0.1:{}", closed_port().await)).unwrap();
+    let srv = SrvClient::new_with_resolver(
+        "_detsys_ids._tcp.example.com.",
+        fallback,
+        None,
+        FixedResolver(resolved),
+    );
+
+    SrvHttpTransport::with_client(srv, Duration::from_secs(3), None, None).unwrap()
+}
+
+#[tokio::test]
+async fn unreachable_hosts_are_reported_together() {
+    super::init_tracing();
+
+    let ports = vec![closed_port().await, closed_port().await];
+    let transport = transport(ports.clone()).await;
+
+    let err = transport.checkin(crate::Map::new()).await.unwrap_err();
+    let SrvHttpTransportError::AllHostsUnreachable(attempted) = &err else {
+        panic!("expected AllHostsUnreachable, got {err:?}");
+    };
+
+    // Both resolved hosts, and then the fallback.
+    assert_eq!(attempted.len(), 3);
+    for (url, port) in attempted.iter().zip(ports) {
+        assert_eq!(url.port(), Some(port));
+    }
+}
+
+#[tokio::test]
+async fn an_unreachable_fallback_alone_is_not_reported_as_all_hosts_unreachable() {
+    super::init_tracing();
+
+    let transport = transport(vec![]).await;
+
+    let err = transport.checkin(crate::Map::new()).await.unwrap_err();
+    assert!(
+        matches!(&err, SrvHttpTransportError::Reqwest(e) if e.is_connect()),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,77 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use crate::storage::DefaultStorageChain;
+use crate::test::recording_transport::RecordingTransport;
+
+async fn record_once(builder: crate::Builder) {
+    let (recorder, worker) = builder.enable_reporting(false).build_or_default().await;
+    let worker = tokio::spawn(worker.wait());
+
+    recorder.record("hello", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+}
+
+fn assert_stored_at(location: &Path) {
+    let contents: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(location).unwrap()).unwrap();
+    assert!(contents["body"]["anonymous_distinct_id"].is_string());
+
+    let mode = std::fs::metadata(location.parent().unwrap())
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[tokio::test]
+async fn storage_lands_at_the_configured_path() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let configured = dir.path().join("configured/nested/storage.json");
+
+    record_once(crate::Builder::new().storage_path(Some(configured.clone()))).await;
+    assert_stored_at(&configured);
+}
+
+#[tokio::test]
+async fn the_environment_overrides_the_configured_path() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let configured = dir.path().join("configured/nested/storage.json");
+    let ambient = dir.path().join("ambient/storage.json");
+
+    let storage = DefaultStorageChain::from_env(Some(configured.clone()), |name| {
+        (name == "DETSYS_IDS_STORAGE_PATH").then(|| ambient.clone().into())
+    })
+    .await;
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            RecordingTransport::new(),
+            crate::system_snapshot::Generic::default(),
+            storage,
+        )
+        .await;
+    let worker = tokio::spawn(worker.wait());
+
+    recorder.record("hello", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert_stored_at(&ambient);
+    assert!(!configured.exists());
+
+    // Relative paths are ignored
+    let storage = DefaultStorageChain::from_env(Some(configured.clone()), |name| {
+        (name == "DETSYS_IDS_STORAGE_PATH").then(|| "relative/storage.json".into())
+    })
+    .await;
+    assert!(matches!(storage, DefaultStorageChain::JsonFile(_)));
+    assert!(configured.parent().unwrap().is_dir());
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::telemetry_enabled;
+
+fn enabled(env: &[(&str, Option<&str>)], env_vars: &[&str]) -> bool {
+    let env: HashMap<&str, &str> = env
+        .iter()
+        .filter_map(|(name, value)| Some((*name, (*value)?)))
+        .collect();
+
+    telemetry_enabled(|name| env.get(name).map(OsString::from), env_vars)
+}
+
+#[test]
+fn a_single_variable() {
+    const VAR: &str = "DETSYS_IDS_TELEMETRY";
+
+    for (value, expected) in [
+        (None, true),
+        (Some("disabled"), false),
+        (Some("enabled"), true),
+        (Some(""), true),
+        (Some("DISABLED"), true),
+    ] {
+        assert_eq!(enabled(&[(VAR, value)], &[VAR]), expected, "{value:?}");
+    }
+}
+
+#[test]
+fn a_primary_and_fallback_variable() {
+    const PRIMARY: &str = "MY_TOOL_TELEMETRY";
+    const FALLBACK: &str = "DETSYS_IDS_TELEMETRY";
+
+    for (primary, fallback, expected) in [
+        (None, None, true),
+        (None, Some("enabled"), true),
+        (None, Some("disabled"), false),
+        (Some("enabled"), None, true),
+        (Some("enabled"), Some("enabled"), true),
+        (Some("enabled"), Some("disabled"), true),
+        (Some("disabled"), None, false),
+        (Some("disabled"), Some("enabled"), false),
+        (Some("disabled"), Some("disabled"), false),
+    ] {
+        assert_eq!(
+            enabled(
+                &[(PRIMARY, primary), (FALLBACK, fallback)],
+                &[PRIMARY, FALLBACK]
+            ),
+            expected,
+            "{primary:?} {fallback:?}"
+        );
+    }
+}
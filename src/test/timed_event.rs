@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use crate::test::recording_transport::RecordingTransport;
+
+fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+#[tokio::test]
+async fn timed_events_record_their_duration() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport.spawn_with(crate::Builder::new()).await;
+
+    let handle = recorder.start_timed_event("download");
+    assert_send_static(&handle);
+
+    let cancelled = recorder.start_timed_event("abandoned");
+
+    let finisher = {
+        let recorder = recorder.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            handle
+                .finish(
+                    &recorder,
+                    Some(crate::Map::from_iter([("bytes".into(), 1024.into())])),
+                )
+                .await;
+        })
+    };
+    finisher.await.unwrap();
+    cancelled.cancel();
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("download").await;
+    assert_eq!(events.len(), 1);
+
+    let duration_ms = events[0]["properties"]["duration_ms"].as_u64().unwrap();
+    assert!((100..5_000).contains(&duration_ms), "{duration_ms}");
+    assert_eq!(events[0]["properties"]["bytes"], 1024);
+
+    assert!(transport.events_named("abandoned").await.is_empty());
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use tracing::Instrument;
+
+use crate::test::http_server;
+use crate::transport::{Transport, Transports};
+
+async fn http_transport() -> (Transports, tokio::sync::mpsc::UnboundedReceiver<String>) {
+    let (addr, requests) = http_server::serve().await;
+
+    let transport = Transports::try_new(
+        Some(format!("http://{addr}")),
+        Duration::from_secs(3),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    (transport, requests)
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+#[tokio::test]
+async fn no_trace_context_without_the_feature() {
+    super::init_tracing();
+
+    let (mut transport, mut requests) = http_transport().await;
+
+    transport
+        .submit(crate::submitter::Batch::new(&[]))
+        .instrument(tracing::info_span!("mock span"))
+        .await
+        .unwrap();
+
+    let head = requests.recv().await.unwrap();
+    assert_eq!(http_server::header(&head, "traceparent"), None);
+    assert_eq!(http_server::header(&head, "tracestate"), None);
+}
+
+#[cfg(feature = "opentelemetry")]
+fn is_lower_hex(s: &str, len: usize) -> bool {
+    s.len() == len
+        && s.chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+#[cfg(feature = "opentelemetry")]
+#[tokio::test(flavor = "current_thread")]
+async fn submission_carries_w3c_trace_context() {
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let provider = SdkTracerProvider::builder().build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let (mut transport, mut requests) = http_transport().await;
+
+    let span = tracing::info_span!("mock span");
+    let trace_id = span.context().span().span_context().trace_id().to_string();
+
+    transport
+        .submit(crate::submitter::Batch::new(&[]))
+        .instrument(span)
+        .await
+        .unwrap();
+
+    let head = requests.recv().await.unwrap();
+
+    let traceparent = http_server::header(&head, "traceparent").expect("traceparent header");
+    let parts: Vec<&str> = traceparent.split('-').collect();
+    assert_eq!(parts.len(), 4, "{traceparent}");
+    assert_eq!(parts[0], "00");
+    assert!(is_lower_hex(parts[1], 32), "{traceparent}");
+    assert!(is_lower_hex(parts[2], 16), "{traceparent}");
+    assert!(is_lower_hex(parts[3], 2), "{traceparent}");
+    assert_eq!(parts[1], trace_id);
+
+    assert_eq!(http_server::header(&head, "tracestate"), Some(""));
+}
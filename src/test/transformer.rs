@@ -0,0 +1,78 @@
+use crate::test::recording_transport::RecordingTransport;
+use crate::{EventFilter, EventTransformer, Map, PiiScrubber};
+
+struct Rename;
+
+impl EventTransformer for Rename {
+    fn transform(&self, event_name: String, properties: Option<Map>) -> (String, Option<Map>) {
+        let mut properties = properties.unwrap_or_default();
+        properties.insert("original_name".into(), event_name.clone().into());
+
+        (format!("renamed.{event_name}"), Some(properties))
+    }
+}
+
+struct DropRenamed;
+
+impl EventFilter for DropRenamed {
+    fn should_include(&self, event_name: &str, _properties: Option<&Map>) -> bool {
+        !event_name.starts_with("renamed.")
+    }
+}
+
+#[tokio::test]
+async fn transformed_events_contain_scrubbed_properties() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(crate::Builder::new().with_event_transformer(PiiScrubber::default()))
+        .await;
+
+    recorder
+        .record(
+            "signup",
+            Some(Map::from_iter([
+                ("email".into(), "hi@example.com".into()),
+                ("plan".into(), "pro".into()),
+            ])),
+        )
+        .await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    let events = transport.events_named("signup").await;
+    assert_eq!(events.len(), 1);
+
+    let properties = &events[0]["properties"];
+    assert_eq!(properties["email"], "[REDACTED]");
+    assert_eq!(properties["plan"], "pro");
+}
+
+#[tokio::test]
+async fn filters_see_the_untransformed_event() {
+    super::init_tracing();
+
+    let transport = RecordingTransport::new();
+
+    let (recorder, worker) = transport
+        .spawn_with(
+            crate::Builder::new()
+                .with_event_transformer(Rename)
+                .with_event_filter(DropRenamed),
+        )
+        .await;
+
+    recorder.record("build", None).await;
+
+    drop(recorder);
+    worker.await.unwrap();
+
+    assert!(transport.events_named("build").await.is_empty());
+
+    let events = transport.events_named("renamed.build").await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["properties"]["original_name"], "build");
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use tokio::net::TcpListener;
+
+use crate::transport::{Transport, Transports, TransportsError};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+async fn transport(endpoint: String) -> Result<Transports, TransportsError> {
+    Transports::try_new(Some(endpoint), TIMEOUT, None, None).await
+}
+
+#[tokio::test]
+async fn unresponsive_http_servers_time_out() {
+    super::init_tracing();
+
+    // Accept connections and hold them open without ever answering.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+
+    let transport = transport(format!("http://{addr}")).await.unwrap();
+
+    let err = transport.checkin(crate::Map::new()).await.unwrap_err();
+    assert!(
+        matches!(err, TransportsError::Timeout(t) if t == TIMEOUT),
+        "{err:?}"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unresponsive_unix_agents_time_out() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("agent.sock");
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+
+    let transport = transport(format!("unix://{}", path.display()))
+        .await
+        .unwrap();
+
+    let err = transport.checkin(crate::Map::new()).await.unwrap_err();
+    assert!(
+        matches!(err, TransportsError::Timeout(t) if t == TIMEOUT),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn unresolvable_udp_hosts_are_dns_failures() {
+    super::init_tracing();
+
+    let Err(err) = transport(String::from("udp://does-not-exist.invalid:8125")).await else {
+        panic!("resolving an .invalid host succeeded");
+    };
+    assert!(
+        matches!(&err, TransportsError::DnsFailure(description) if description.contains("does-not-exist.invalid")),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn unresolvable_http_hosts_are_dns_failures() {
+    super::init_tracing();
+
+    let transport = transport(String::from("http://does-not-exist.invalid"))
+        .await
+        .unwrap();
+
+    let err = transport.checkin(crate::Map::new()).await.unwrap_err();
+    assert!(
+        matches!(&err, TransportsError::DnsFailure(description) if description.contains("does-not-exist.invalid")),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn refused_http_connections_arent_dns_failures() {
+    super::init_tracing();
+
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let transport = transport(format!("http://{addr}")).await.unwrap();
+
+    let err = transport.checkin(crate::Map::new()).await.unwrap_err();
+    assert!(!matches!(err, TransportsError::DnsFailure(_)), "{err:?}");
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+
+use crate::transport::udp::MAX_DATAGRAM_SIZE;
+use crate::transport::{Transport, Transports};
+
+async fn listen() -> (UdpSocket, Transports) {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let transport = Transports::try_new(
+        Some(format!("udp://{}", socket.local_addr().unwrap())),
+        Duration::from_secs(3),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    (socket, transport)
+}
+
+async fn receive(socket: &UdpSocket) -> Vec<u8> {
+    let mut buf = vec![0; 1 << 17];
+    let len = tokio::time::timeout(Duration::from_secs(5), socket.recv(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    buf.truncate(len);
+    buf
+}
+
+#[tokio::test]
+async fn events_are_sent_as_datagrams() {
+    super::init_tracing();
+
+    let (socket, transport) = listen().await;
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport,
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    recorder
+        .record(
+            "udp-event",
+            Some(crate::Map::from_iter([("hello".into(), "world".into())])),
+        )
+        .await;
+
+    drop(recorder);
+    worker.wait().await;
+
+    let batch: serde_json::Value = serde_json::from_slice(&receive(&socket).await).unwrap();
+    let event = batch["batch"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|event| event["name"] == "udp-event")
+        .expect("the recorded event is in the datagram");
+    assert_eq!(event["properties"]["hello"], "world");
+    assert!(batch["sent_at"].is_string());
+}
+
+/// Receive datagrams until none arrive for a while, returning the events they hold.
+async fn receive_events(socket: &UdpSocket) -> Vec<serde_json::Value> {
+    let mut events = vec![];
+    let mut buf = vec![0; 1 << 17];
+
+    while let Ok(len) =
+        tokio::time::timeout(Duration::from_millis(500), socket.recv(&mut buf)).await
+    {
+        let batch: serde_json::Value = serde_json::from_slice(&buf[..len.unwrap()]).unwrap();
+        events.extend(batch["batch"].as_array().unwrap().iter().cloned());
+    }
+
+    events
+}
+
+async fn record_padded(transport: Transports, events: &[(&str, usize)]) {
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport,
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    for &(name, padding) in events {
+        recorder
+            .record(
+                name,
+                Some(crate::Map::from_iter([(
+                    "padding".into(),
+                    "x".repeat(padding).into(),
+                )])),
+            )
+            .await;
+    }
+
+    drop(recorder);
+    worker.wait().await;
+}
+
+#[tokio::test]
+async fn oversized_batches_are_split_across_datagrams() {
+    super::init_tracing();
+
+    let (socket, transport) = listen().await;
+
+    let padding = MAX_DATAGRAM_SIZE / 3;
+    record_padded(transport, &[("big-event", padding); 4]).await;
+
+    let events = receive_events(&socket).await;
+    let big = events
+        .iter()
+        .filter(|event| event["name"] == "big-event")
+        .collect::<Vec<_>>();
+    assert_eq!(big.len(), 4);
+    assert!(
+        big.iter()
+            .all(|event| event["properties"]["padding"].as_str().unwrap().len() == padding)
+    );
+}
+
+#[tokio::test]
+async fn events_too_large_for_a_datagram_are_dropped() {
+    super::init_tracing();
+
+    let (socket, transport) = listen().await;
+
+    record_padded(
+        transport,
+        &[("huge-event", MAX_DATAGRAM_SIZE), ("small-event", 10)],
+    )
+    .await;
+
+    let events = receive_events(&socket).await;
+    assert!(events.iter().any(|event| event["name"] == "small-event"));
+    assert!(!events.iter().any(|event| event["name"] == "huge-event"));
+}
+
+#[tokio::test]
+async fn checkins_are_empty() {
+    super::init_tracing();
+
+    let (_socket, transport) = listen().await;
+
+    assert_eq!(
+        transport.checkin(crate::Map::new()).await.unwrap(),
+        crate::checkin::Checkin::default()
+    );
+}
+
+#[tokio::test]
+async fn udp_endpoints_need_a_port() {
+    assert!(matches!(
+        Transports::try_new(
+            Some("udp://localhost".into()),
+            Duration::from_secs(3),
+            None,
+            None
+        )
+        .await,
+        Err(crate::transport::TransportsError::MissingHostOrPort)
+    ));
+}
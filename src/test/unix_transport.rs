@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use crate::transport::{Transport, Transports};
+
+/// A local agent which answers check-ins with `checkin` and reports every frame it reads.
+fn serve(
+    listener: UnixListener,
+    checkin: serde_json::Value,
+) -> UnboundedReceiver<serde_json::Value> {
+    let (tx, rx) = unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            let checkin = checkin.clone();
+
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let frame: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+                    if frame["type"] == "checkin" {
+                        let mut reply = serde_json::to_vec(&checkin).unwrap();
+                        reply.push(b'\n');
+                        write.write_all(&reply).await.unwrap();
+                    }
+
+                    let _ = tx.send(frame);
+                }
+            });
+        }
+    });
+
+    rx
+}
+
+async fn transport(path: &std::path::Path) -> Transports {
+    Transports::try_new(
+        Some(format!("unix://{}", path.display())),
+        Duration::from_secs(3),
+        None,
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn checkins_and_batches_round_trip() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("agent.sock");
+    let mut frames = serve(
+        UnixListener::bind(&path).unwrap(),
+        serde_json::json!({ "options": { "from-the-agent": { "variant": "yes" } } }),
+    );
+
+    let (recorder, worker) = crate::Builder::new()
+        .build_with(
+            transport(&path).await,
+            crate::system_snapshot::Generic::default(),
+            crate::storage::Generic::default(),
+        )
+        .await;
+
+    recorder.wait_for_checkin(None).await.unwrap();
+    assert_eq!(
+        recorder
+            .get_feature_variant::<String>("from-the-agent")
+            .await,
+        Some("yes".to_string())
+    );
+
+    recorder.record("over-the-socket", None).await;
+
+    drop(recorder);
+    worker.wait().await;
+
+    let checkin = frames.recv().await.unwrap();
+    assert_eq!(checkin["type"], "checkin");
+    assert!(checkin["session_properties"].is_object());
+
+    // The agent may still be reading, so wait for the event to arrive
+    let frame = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let frame = frames.recv().await.unwrap();
+            assert_eq!(frame["type"], "batch");
+
+            if frame["batch"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|event| event["name"] == "over-the-socket")
+            {
+                return frame;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert!(frame["sent_at"].is_string());
+}
+
+#[tokio::test]
+async fn missing_agents_fail() {
+    super::init_tracing();
+
+    let dir = tempfile::tempdir().unwrap();
+    let transport = transport(&dir.path().join("nobody-home.sock")).await;
+
+    assert!(transport.checkin(crate::Map::new()).await.is_err());
+}
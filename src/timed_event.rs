@@ -0,0 +1,37 @@
+use tokio::time::Instant;
+
+use crate::{Map, Recorder};
+
+/// An event which records how long it took, created by [`Recorder::start_timed_event`].
+///
+/// Dropping the handle without calling [`TimedEventHandle::finish`] records nothing.
+#[derive(Debug)]
+#[must_use = "a timed event is only recorded when it is finished"]
+pub struct TimedEventHandle {
+    event_name: String,
+    started: Instant,
+}
+
+impl TimedEventHandle {
+    pub(crate) fn new(event_name: String) -> Self {
+        Self {
+            event_name,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record the event with a `duration_ms` property holding the milliseconds elapsed since it was started.
+    pub async fn finish(self, recorder: &Recorder, extra_properties: Option<Map>) {
+        let duration_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+        let mut properties = extra_properties.unwrap_or_default();
+        properties.insert("duration_ms".into(), duration_ms.into());
+
+        recorder.record(self.event_name, Some(properties)).await;
+    }
+
+    /// Discard the event without recording it.
+    pub fn cancel(self) {
+        tracing::trace!(event_name = self.event_name, "Cancelled a timed event");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::Map;
+
+/// Rewrite a recorded event before it is collated.
+///
+/// Transformers run in the Collator after any [`crate::EventFilter`], for every event recorded with [`crate::Recorder::record`] and friends.
+/// Identity events like `$identify` and `$create_alias` are never transformed.
+pub trait EventTransformer: Send + Sync + 'static {
+    fn transform(&self, event_name: String, properties: Option<Map>) -> (String, Option<Map>);
+}
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Redact the values of properties whose key looks like personally identifiable information.
+///
+/// A key matches a term if the term's words appear in it consecutively, case-insensitively.
+/// Words are separated by punctuation like `_`, `-`, and `.`, or by camelCase, so `UserName` and `user-name` match `user_name`, but `hostname` doesn't match `name`.
+/// Nested objects are scrubbed too.
+#[derive(Clone, Debug)]
+pub struct PiiScrubber {
+    terms: Vec<Vec<String>>,
+}
+
+impl PiiScrubber {
+    pub fn new(terms: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            terms: terms
+                .into_iter()
+                .map(|term| words(&term.into()))
+                .filter(|words| !words.is_empty())
+                .collect(),
+        }
+    }
+
+    fn is_pii(&self, key: &str) -> bool {
+        let key = words(key);
+        self.terms.iter().any(|term| {
+            key.windows(term.len())
+                .any(|window| window == term.as_slice())
+        })
+    }
+
+    fn scrub(&self, properties: &mut Map) {
+        for (key, value) in properties.iter_mut() {
+            if self.is_pii(key) {
+                *value = REDACTED.into();
+            } else if let serde_json::Value::Object(nested) = value {
+                self.scrub(nested);
+            }
+        }
+    }
+}
+
+impl Default for PiiScrubber {
+    fn default() -> Self {
+        Self::new([
+            "email",
+            "ip",
+            "username",
+            "user_name",
+            "first_name",
+            "last_name",
+            "full_name",
+            "display_name",
+        ])
+    }
+}
+
+/// Split a key into lowercase words at punctuation and camelCase boundaries.
+fn words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        // A new word starts at `aB`, `1B`, or the `B` of `ABc`
+        let starts_word = c.is_uppercase()
+            && i > 0
+            && (!chars[i - 1].is_uppercase()
+                || chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+        if starts_word && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.extend(c.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+impl EventTransformer for PiiScrubber {
+    fn transform(&self, event_name: String, properties: Option<Map>) -> (String, Option<Map>) {
+        let properties = properties.map(|mut properties| {
+            self.scrub(&mut properties);
+            properties
+        });
+
+        (event_name, properties)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EventTransformer, PiiScrubber};
+    use crate::Map;
+
+    #[test]
+    fn scrubs_pii_keys() {
+        let properties: Map = serde_json::from_value(serde_json::json!({
+            "email": "hi@example.com",
+            "UserName": "Alice",
+            "client_ip": "127.0.0.1",
+            "count": 3,
+            "nested": { "contact_email": "hi@example.com", "ok": true },
+        }))
+        .unwrap();
+
+        let (name, properties) = PiiScrubber::default().transform("ev".into(), Some(properties));
+
+        assert_eq!(name, "ev");
+        assert_eq!(
+            serde_json::Value::Object(properties.unwrap()),
+            serde_json::json!({
+                "email": "[REDACTED]",
+                "UserName": "[REDACTED]",
+                "client_ip": "[REDACTED]",
+                "count": 3,
+                "nested": { "contact_email": "[REDACTED]", "ok": true },
+            })
+        );
+    }
+
+    #[test]
+    fn keys_merely_containing_a_term_are_kept() {
+        let keys = [
+            "zip",
+            "description",
+            "pipeline",
+            "recipient",
+            "file_name",
+            "event_name",
+            "hostname",
+            "ship_date",
+            "emailer_count",
+        ];
+        let properties = Map::from_iter(keys.map(|key| (key.to_string(), "kept".into())));
+
+        let (_, scrubbed) = PiiScrubber::default().transform("ev".into(), Some(properties.clone()));
+
+        assert_eq!(scrubbed, Some(properties));
+    }
+
+    #[test]
+    fn terms_match_across_key_styles() {
+        let keys = [
+            "user_name",
+            "user-name",
+            "user.name",
+            "userName",
+            "UserName",
+            "USER_NAME",
+            "clientIP",
+            "IPAddress",
+            "ip_address",
+            "primary_email",
+        ];
+        let properties = Map::from_iter(keys.map(|key| (key.to_string(), "secret".into())));
+
+        let (_, scrubbed) = PiiScrubber::default().transform("ev".into(), Some(properties));
+
+        for (key, value) in scrubbed.unwrap() {
+            assert_eq!(value, "[REDACTED]", "{key}");
+        }
+    }
+
+    #[test]
+    fn custom_terms() {
+        let properties = Map::from_iter([
+            ("token".to_string(), "secret".into()),
+            ("email".to_string(), "hi@example.com".into()),
+        ]);
+
+        let (_, properties) = PiiScrubber::new(["TOKEN"]).transform("ev".into(), Some(properties));
+        let properties = properties.unwrap();
+
+        assert_eq!(properties["token"], "[REDACTED]");
+        assert_eq!(properties["email"], "hi@example.com");
+    }
+
+    #[test]
+    fn no_properties() {
+        assert_eq!(
+            PiiScrubber::default().transform("ev".into(), None),
+            ("ev".to_string(), None)
+        );
+    }
+}
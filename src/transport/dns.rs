@@ -0,0 +1,27 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Resolves hosts with the system resolver, like reqwest's default resolver does.
+///
+/// Its failures are a [`DnsError`] in the request error's source chain, so they can be told apart from other connection failures.
+#[derive(Debug)]
+pub(crate) struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+
+            match tokio::net::lookup_host((host.clone(), 0)).await {
+                Ok(addrs) => Ok(Box::new(addrs) as Addrs),
+                Err(source) => Err(DnsError { host, source }.into()),
+            }
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Failure resolving '{host}': {source}")]
+pub(crate) struct DnsError {
+    host: String,
+    source: std::io::Error,
+}
@@ -19,7 +19,8 @@ impl ReqwestTransport {
         certificates: Option<Certificate>,
         proxy: Option<Url>,
     ) -> Result<Self, ReqwestTransportError> {
-        let mut builder = reqwest::ClientBuilder::new();
+        let mut builder = reqwest::ClientBuilder::new()
+            .dns_resolver(std::sync::Arc::new(super::dns::SystemResolver));
 
         if let Some(cert) = certificates {
             builder = builder.add_root_certificate(cert);
@@ -35,6 +36,10 @@ impl ReqwestTransport {
             timeout,
         })
     }
+
+    pub(crate) fn timeout(&self) -> std::time::Duration {
+        self.timeout
+    }
 }
 
 impl Transport for ReqwestTransport {
@@ -45,13 +50,10 @@ impl Transport for ReqwestTransport {
         let mut url = self.host.clone();
         url.set_path("/events/batch");
 
-        let resp = self
-            .client
-            .post(url)
-            .timeout(self.timeout)
-            .json(&batch)
-            .send()
-            .await?;
+        let resp =
+            super::trace_context::inject(self.client.post(url).timeout(self.timeout).json(&batch))
+                .send()
+                .await?;
 
         if resp.status().is_success() {
             return Ok(());
@@ -68,13 +70,14 @@ impl Transport for ReqwestTransport {
         let mut url = self.host.clone();
         url.set_path("/check-in");
 
-        let res = self
-            .client
-            .post(url.clone())
-            .json(&session_properties)
-            .timeout(self.timeout)
-            .send()
-            .await;
+        let res = super::trace_context::inject(
+            self.client
+                .post(url.clone())
+                .json(&session_properties)
+                .timeout(self.timeout),
+        )
+        .send()
+        .await;
 
         match res {
             Ok(resp) => Ok(resp.json().await?),
@@ -84,6 +87,21 @@ impl Transport for ReqwestTransport {
             }
         }
     }
+
+    /// Send a `HEAD` request, treating any response short of a server error as healthy.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        let mut url = self.host.clone();
+        url.set_path("/check-in");
+
+        let resp = self.client.head(url).timeout(self.timeout).send().await?;
+
+        if resp.status().is_server_error() {
+            return Err(Self::Error::Response(Box::new(resp)));
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -4,13 +4,21 @@ use file::FileTransport;
 use http::ReqwestTransport;
 use reqwest::Certificate;
 use srv_http::SrvHttpTransport;
+use udp::UdpTransport;
+#[cfg(unix)]
+use unix::UnixSocketTransport;
 use url::Url;
 
 use crate::{Map, submitter::Batch};
 
+mod dns;
 mod file;
 mod http;
-mod srv_http;
+pub(crate) mod srv_http;
+mod trace_context;
+pub(crate) mod udp;
+#[cfg(unix)]
+mod unix;
 
 pub(crate) const APPLICATION_JSON: &str = "application/json";
 pub(crate) trait Transport: Send + Sync + Clone + 'static {
@@ -22,6 +30,12 @@ pub(crate) trait Transport: Send + Sync + Clone + 'static {
     ) -> impl Future<Output = Result<crate::checkin::Checkin, Self::Error>> + Send;
 
     fn submit(&mut self, batch: Batch<'_>) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Check that the backend is reachable, without submitting anything.
+    /// Defaults to a check-in with no session properties.
+    fn health_check(&self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { self.checkin(Map::new()).await.map(|_| ()) }
+    }
 }
 
 pub(crate) fn default_transport_backend() -> (String, Url, Option<Vec<url::Host>>) {
@@ -41,6 +55,9 @@ pub(crate) enum Transports {
     File(FileTransport),
     Http(ReqwestTransport),
     SrvHttp(SrvHttpTransport),
+    Udp(UdpTransport),
+    #[cfg(unix)]
+    Unix(UnixSocketTransport),
 }
 
 impl Transports {
@@ -54,6 +71,17 @@ impl Transports {
         timeout: Duration,
         certificates: Option<Certificate>,
         proxy: Option<Url>,
+    ) -> Result<Self, TransportsError> {
+        Self::build(opt_value, timeout, certificates, proxy)
+            .await
+            .map_err(|e| e.classify(Some(timeout)))
+    }
+
+    async fn build(
+        opt_value: Option<String>,
+        timeout: Duration,
+        certificates: Option<Certificate>,
+        proxy: Option<Url>,
     ) -> Result<Self, TransportsError> {
         let Some(value) = opt_value else {
             let (record, fallback, allowed_suffixes) = default_transport_backend();
@@ -90,9 +118,32 @@ impl Transports {
                 )
                 .await?,
             )),
+            "udp" => {
+                let (Some(host), Some(port)) = (url.host_str(), url.port()) else {
+                    return Err(TransportsError::MissingHostOrPort);
+                };
+
+                Ok(Transports::Udp(UdpTransport::new(host, port).await?))
+            }
+            #[cfg(unix)]
+            "unix" => Ok(Transports::Unix(UnixSocketTransport::new(
+                url.path(),
+                timeout,
+            ))),
             _ => Err(TransportsError::UnknownUrlScheme),
         }
     }
+
+    /// How long a request may take, for transports which give up.
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Http(t) => Some(t.timeout()),
+            Self::SrvHttp(t) => Some(t.timeout()),
+            #[cfg(unix)]
+            Self::Unix(t) => Some(t.timeout()),
+            Self::None | Self::File(_) | Self::Udp(_) => None,
+        }
+    }
 }
 
 impl Transport for Transports {
@@ -103,25 +154,51 @@ impl Transport for Transports {
         &self,
         session_properties: Map,
     ) -> Result<crate::checkin::Checkin, Self::Error> {
-        match self {
+        let result = match self {
             Self::None => Ok(crate::checkin::Checkin {
                 options: std::collections::HashMap::new(),
                 ..Default::default()
             }),
-            Self::File(t) => Ok(t.checkin(session_properties).await?),
-            Self::Http(t) => Ok(t.checkin(session_properties).await?),
-            Self::SrvHttp(t) => Ok(t.checkin(session_properties).await?),
-        }
+            Self::File(t) => t.checkin(session_properties).await.map_err(Into::into),
+            Self::Http(t) => t.checkin(session_properties).await.map_err(Into::into),
+            Self::SrvHttp(t) => t.checkin(session_properties).await.map_err(Into::into),
+            Self::Udp(t) => t.checkin(session_properties).await.map_err(Into::into),
+            #[cfg(unix)]
+            Self::Unix(t) => t.checkin(session_properties).await.map_err(Into::into),
+        };
+
+        result.map_err(|e: TransportsError| e.classify(self.timeout()))
     }
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
     async fn submit(&mut self, batch: Batch<'_>) -> Result<(), Self::Error> {
-        match self {
+        let timeout = self.timeout();
+        let result = match self {
             Self::None => Ok(()),
-            Self::File(t) => Ok(t.submit(batch).await?),
-            Self::Http(t) => Ok(t.submit(batch).await?),
-            Self::SrvHttp(t) => Ok(t.submit(batch).await?),
-        }
+            Self::File(t) => t.submit(batch).await.map_err(Into::into),
+            Self::Http(t) => t.submit(batch).await.map_err(Into::into),
+            Self::SrvHttp(t) => t.submit(batch).await.map_err(Into::into),
+            Self::Udp(t) => t.submit(batch).await.map_err(Into::into),
+            #[cfg(unix)]
+            Self::Unix(t) => t.submit(batch).await.map_err(Into::into),
+        };
+
+        result.map_err(|e: TransportsError| e.classify(timeout))
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        let result = match self {
+            Self::None => Ok(()),
+            Self::File(t) => t.health_check().await.map_err(Into::into),
+            Self::Http(t) => t.health_check().await.map_err(Into::into),
+            Self::SrvHttp(t) => t.health_check().await.map_err(Into::into),
+            Self::Udp(t) => t.health_check().await.map_err(Into::into),
+            #[cfg(unix)]
+            Self::Unix(t) => t.health_check().await.map_err(Into::into),
+        };
+
+        result.map_err(|e: TransportsError| e.classify(self.timeout()))
     }
 }
 
@@ -136,9 +213,19 @@ pub enum TransportsError {
     #[error(transparent)]
     SrvHttpError(#[from] srv_http::SrvHttpTransportError),
 
-    #[error("Only http, https, and file URL schemes are supported.")]
+    #[error(transparent)]
+    UdpError(#[from] udp::UdpTransportError),
+
+    #[cfg(unix)]
+    #[error(transparent)]
+    UnixError(#[from] unix::UnixSocketTransportError),
+
+    #[error("Only http, https, file, udp, and unix URL schemes are supported.")]
     UnknownUrlScheme,
 
+    #[error("UDP endpoints need a host and a port, like `udp://localhost:8125`.")]
+    MissingHostOrPort,
+
     #[error(transparent)]
     Parse(#[from] url::ParseError),
 
@@ -147,4 +234,70 @@ pub enum TransportsError {
 
     #[error("Unknown certificate format, `der` and `pem` supported")]
     UnknownCertFormat,
+
+    /// The backend didn't respond within the transport's timeout.
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// A host name couldn't be resolved. Carries a description of the failure.
+    #[error("DNS resolution failed: {0}")]
+    DnsFailure(String),
+}
+
+impl TransportsError {
+    /// Lift timeouts and DNS failures out of the transport-specific errors, so callers can match on them.
+    fn classify(self, timeout: Option<Duration>) -> Self {
+        if let Some(timeout) = timeout
+            && self.is_timeout()
+        {
+            return Self::Timeout(timeout);
+        }
+
+        match self.dns_failure() {
+            Some(description) => Self::DnsFailure(description),
+            None => self,
+        }
+    }
+
+    fn is_timeout(&self) -> bool {
+        match self {
+            Self::HttpError(http::ReqwestTransportError::Reqwest(e))
+            | Self::SrvHttpError(srv_http::SrvHttpTransportError::Reqwest(e)) => e.is_timeout(),
+            #[cfg(unix)]
+            Self::UnixError(unix::UnixSocketTransportError::Timeout) => true,
+            _ => false,
+        }
+    }
+
+    fn dns_failure(&self) -> Option<String> {
+        match self {
+            Self::HttpError(http::ReqwestTransportError::Reqwest(e))
+            | Self::SrvHttpError(srv_http::SrvHttpTransportError::Reqwest(e)) => {
+                reqwest_dns_failure(e)
+            }
+            Self::SrvHttpError(srv_http::SrvHttpTransportError::SrvError(
+                detsys_srv::Error::Lookup(e),
+            )) => Some(format!("SRV lookup failed: {e}")),
+            Self::UdpError(
+                e @ (udp::UdpTransportError::Resolve(..) | udp::UdpTransportError::NoAddress(_)),
+            ) => Some(e.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// The failure to resolve the request's host, if that is why the request failed.
+///
+/// reqwest doesn't distinguish DNS failures from other connection failures, but its clients resolve with [`dns::SystemResolver`], whose error is in the source chain.
+fn reqwest_dns_failure(e: &reqwest::Error) -> Option<String> {
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        if let Some(dns) = err.downcast_ref::<dns::DnsError>() {
+            return Some(dns.to_string());
+        }
+
+        source = err.source();
+    }
+
+    None
 }
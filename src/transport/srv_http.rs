@@ -1,6 +1,8 @@
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use detsys_srv::SrvClient;
+use detsys_srv::resolver::SrvResolver;
 use reqwest::Certificate;
 use reqwest::Url;
 use tracing::Instrument;
@@ -19,11 +21,23 @@ type Resolver = hickory_resolver::TokioResolver;
 //     >,
 // >;
 
-#[derive(Clone)]
-pub(crate) struct SrvHttpTransport {
-    srv: Arc<SrvClient<Resolver>>,
+pub(crate) struct SrvHttpTransport<R = Resolver> {
+    srv: Arc<SrvClient<R>>,
     server_options: Arc<tokio::sync::RwLock<crate::checkin::ServerOptions>>,
     reqwest: reqwest::Client,
+    timeout: std::time::Duration,
+}
+
+// Derived, this would require the resolver to be Clone too.
+impl<R> Clone for SrvHttpTransport<R> {
+    fn clone(&self) -> Self {
+        Self {
+            srv: self.srv.clone(),
+            server_options: self.server_options.clone(),
+            reqwest: self.reqwest.clone(),
+            timeout: self.timeout,
+        }
+    }
 }
 impl SrvHttpTransport {
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(err(level = tracing::Level::TRACE)))]
@@ -49,7 +63,20 @@ impl SrvHttpTransport {
         let srv =
             SrvClient::<Resolver>::new_with_resolver(&record, fallback, allowed_suffixes, resolver);
 
-        let mut builder = reqwest::ClientBuilder::new().timeout(timeout);
+        Self::with_client(srv, timeout, certificates, proxy)
+    }
+}
+
+impl<R: SrvResolver> SrvHttpTransport<R> {
+    pub(crate) fn with_client(
+        srv: SrvClient<R>,
+        timeout: std::time::Duration,
+        certificates: Option<Certificate>,
+        proxy: Option<Url>,
+    ) -> Result<Self, SrvHttpTransportError> {
+        let mut builder = reqwest::ClientBuilder::new()
+            .timeout(timeout)
+            .dns_resolver(std::sync::Arc::new(super::dns::SystemResolver));
 
         if let Some(cert) = certificates {
             builder = builder.add_root_certificate(cert);
@@ -59,17 +86,72 @@ impl SrvHttpTransport {
             builder = builder.proxy(reqwest::Proxy::all(proxy.clone())?);
         }
 
-        Ok(SrvHttpTransport {
+        Ok(Self {
             srv: Arc::new(srv),
             reqwest: builder.build()?,
+            timeout,
             server_options: Arc::new(tokio::sync::RwLock::new(
                 crate::checkin::ServerOptions::default(),
             )),
         })
     }
+
+    pub(crate) fn timeout(&self) -> std::time::Duration {
+        self.timeout
+    }
+
+    /// Try `func` against each host the SRV record resolves to, then the fallback.
+    ///
+    /// Fails with `AllHostsUnreachable` if the record resolved to at least one host, and no host accepted a connection.
+    async fn execute<T, Fut>(
+        &self,
+        mut func: impl FnMut(Url) -> Fut,
+    ) -> Result<T, SrvHttpTransportError>
+    where
+        Fut: Future<Output = Result<T, SrvHttpTransportError>>,
+    {
+        let attempts: Arc<Mutex<Vec<(Url, bool)>>> = Default::default();
+
+        let result = self
+            .srv
+            .execute(|url| {
+                let attempt = func(url.clone());
+                let attempts = attempts.clone();
+
+                async move {
+                    let result = attempt.await;
+                    let unreachable = matches!(&result, Err(e) if e.is_unreachable());
+                    attempts
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push((url, unreachable));
+                    result
+                }
+            })
+            .await;
+
+        let Err(e) = result else {
+            return result;
+        };
+
+        let attempts = std::mem::take(&mut *attempts.lock().unwrap_or_else(|e| e.into_inner()));
+
+        // The fallback is always tried last, so any earlier attempt came from the SRV record.
+        if attempts.len() > 1 && attempts.iter().all(|(_, unreachable)| *unreachable) {
+            return Err(SrvHttpTransportError::AllHostsUnreachable(
+                attempts.into_iter().map(|(url, _)| url).collect(),
+            ));
+        }
+
+        Err(e)
+    }
 }
 
-impl Transport for SrvHttpTransport {
+impl<R> Transport for SrvHttpTransport<R>
+where
+    R: SrvResolver + 'static,
+    R::Error: Send,
+{
     type Error = SrvHttpTransportError;
 
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
@@ -79,7 +161,6 @@ impl Transport for SrvHttpTransport {
         let server_opts = self.server_options.clone();
 
         let resp = self
-            .srv
             .execute(move |mut url| {
                 let payload: Vec<u8> = payload.as_bytes().into();
                 let reqwest = reqwest.clone();
@@ -110,7 +191,6 @@ impl Transport for SrvHttpTransport {
         let server_opts = self.server_options.clone();
 
         let resp = self
-            .srv
             .execute(move |mut url| {
                 let payload: Vec<u8> = payload.as_bytes().into();
                 let reqwest = reqwest.clone();
@@ -134,6 +214,37 @@ impl Transport for SrvHttpTransport {
 
         Ok(checkin)
     }
+
+    /// Send a `HEAD` request, treating any response short of a server error as healthy.
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        let reqwest = self.reqwest.clone();
+
+        let resp = self
+            .execute(move |mut url| {
+                let reqwest = reqwest.clone();
+
+                url.set_path("check-in");
+
+                let span = tracing::trace_span!("health check attempt", %url);
+
+                async move {
+                    let resp = reqwest.head(url).send().await?;
+
+                    if resp.status().is_server_error() {
+                        return Err(SrvHttpTransportError::Response(Box::new(resp)));
+                    }
+
+                    Ok(resp)
+                }
+                .instrument(span)
+            })
+            .await?;
+
+        tracing::trace!(status = %resp.status(), "Health check succeeded");
+
+        Ok(())
+    }
 }
 
 #[tracing::instrument(skip(reqwest, payload, server_opts))]
@@ -143,13 +254,19 @@ async fn perform_request(
     payload: Vec<u8>,
     server_opts: Arc<tokio::sync::RwLock<ServerOptions>>,
 ) -> Result<reqwest::Response, SrvHttpTransportError> {
-    let algos = server_opts.read().await.compression_algorithms.into_iter();
+    let compression_algorithms = server_opts.read().await.compression_algorithms;
+    tracing::trace!(
+        preferred = ?compression_algorithms.preferred_algorithm(),
+        compressed = compression_algorithms.has_any_compression(),
+        "Selecting compression algorithm"
+    );
+
+    let algos = compression_algorithms.into_iter();
 
     for compression_algo in algos {
         let span = tracing::debug_span!("requesting", ?compression_algo);
 
-        let mut req = reqwest
-            .post(url.clone())
+        let mut req = super::trace_context::inject(reqwest.post(url.clone()))
             .header(
                 http::header::CONTENT_TYPE,
                 crate::transport::APPLICATION_JSON,
@@ -206,4 +323,14 @@ pub enum SrvHttpTransportError {
 
     #[error("The server has rejected all of our compression modes")]
     NoCompressionMode,
+
+    /// The SRV record resolved, but none of its hosts, nor the fallback, accepted a connection.
+    #[error("None of the hosts for the SRV record could be reached: {}", .0.iter().map(Url::as_str).collect::<Vec<_>>().join(", "))]
+    AllHostsUnreachable(Vec<Url>),
+}
+
+impl SrvHttpTransportError {
+    fn is_unreachable(&self) -> bool {
+        matches!(self, Self::Reqwest(e) if e.is_connect())
+    }
 }
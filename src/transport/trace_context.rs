@@ -0,0 +1,23 @@
+//! Propagate the OpenTelemetry context of the current `tracing` span as W3C `traceparent` / `tracestate` headers.
+
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn inject(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = tracing::Span::current().context();
+    let mut headers = http::HeaderMap::new();
+
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(
+            &context,
+            &mut opentelemetry_http::HeaderInjector(&mut headers),
+        );
+    });
+
+    request.headers(headers)
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+pub(crate) fn inject(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    request
+}
@@ -0,0 +1,116 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::net::UdpSocket;
+
+use crate::Map;
+use crate::submitter::Batch;
+
+use super::Transport;
+
+/// The largest payload a single UDP datagram can carry over IPv4, or by default on macOS, whose `net.inet.udp.maxdgram` is lower.
+pub(crate) const MAX_DATAGRAM_SIZE: usize = if cfg!(target_os = "macos") {
+    9_216
+} else {
+    65_507
+};
+
+/// Sends each batch as one datagram, without waiting for or expecting a reply.
+/// Batches too large for one datagram are split across several, and events too large on their own are dropped.
+#[derive(Clone)]
+pub(crate) struct UdpTransport {
+    target: SocketAddr,
+    socket: Arc<UdpSocket>,
+}
+impl UdpTransport {
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(err))]
+    pub(crate) async fn new(host: &str, port: u16) -> Result<Self, UdpTransportError> {
+        let target = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| UdpTransportError::Resolve(host.to_string(), e))?
+            .next()
+            .ok_or_else(|| UdpTransportError::NoAddress(host.to_string()))?;
+
+        let local: SocketAddr = if target.is_ipv4() {
+            (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)
+            .await
+            .map_err(UdpTransportError::Bind)?;
+
+        Ok(UdpTransport {
+            target,
+            socket: Arc::new(socket),
+        })
+    }
+}
+
+impl Transport for UdpTransport {
+    type Error = UdpTransportError;
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
+    async fn submit(&mut self, batch: Batch<'_>) -> Result<(), Self::Error> {
+        for payload in datagrams(&batch)? {
+            self.socket
+                .send_to(&payload, self.target)
+                .await
+                .map_err(|e| UdpTransportError::Send(self.target, e))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
+    async fn checkin(
+        &self,
+        _session_properties: Map,
+    ) -> Result<crate::checkin::Checkin, Self::Error> {
+        Ok(crate::checkin::Checkin::default())
+    }
+}
+
+/// Serialize `batch` into datagrams no larger than [`MAX_DATAGRAM_SIZE`], halving it until each part fits.
+fn datagrams(batch: &Batch<'_>) -> Result<Vec<Vec<u8>>, serde_json::Error> {
+    let payload = serde_json::to_vec(batch)?;
+
+    if payload.len() <= MAX_DATAGRAM_SIZE {
+        return Ok(vec![payload]);
+    }
+
+    if let [event] = batch.events() {
+        tracing::warn!(
+            event = event.name(),
+            size = payload.len(),
+            max = MAX_DATAGRAM_SIZE,
+            "IDS event is too large for a UDP datagram, dropping it"
+        );
+        return Ok(vec![]);
+    }
+
+    let mut payloads = vec![];
+    for half in batch.split(batch.len().div_ceil(2)) {
+        payloads.extend(datagrams(&half)?);
+    }
+
+    Ok(payloads)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UdpTransportError {
+    #[error("Failure resolving '{0}': {1}")]
+    Resolve(String, std::io::Error),
+
+    #[error("No addresses found for '{0}'")]
+    NoAddress(String),
+
+    #[error("Failure binding a UDP socket: {0}")]
+    Bind(std::io::Error),
+
+    #[error("Failure sending to '{0}': {1}")]
+    Send(SocketAddr, std::io::Error),
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
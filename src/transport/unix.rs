@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufStream};
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+
+use crate::Map;
+use crate::submitter::Batch;
+
+use super::Transport;
+
+/// One line of the protocol spoken with the local agent.
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Frame<'a> {
+    Batch(Batch<'a>),
+    Checkin { session_properties: Map },
+}
+
+/// Sends newline-delimited JSON frames to a local agent listening on a Unix domain socket.
+///
+/// The connection is opened on first use, and reopened after any failure.
+#[derive(Clone)]
+pub(crate) struct UnixSocketTransport {
+    path: PathBuf,
+    timeout: Duration,
+    connection: Arc<Mutex<Option<BufStream<UnixStream>>>>,
+}
+impl UnixSocketTransport {
+    pub(crate) fn new(path: impl Into<PathBuf>, timeout: Duration) -> Self {
+        UnixSocketTransport {
+            path: path.into(),
+            timeout,
+            connection: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Write a frame, and read back a reply line if `reply` is set.
+    async fn exchange(
+        &self,
+        frame: &Frame<'_>,
+        reply: bool,
+    ) -> Result<Option<String>, UnixSocketTransportError> {
+        let mut line = serde_json::to_vec(frame)?;
+        line.push(b'\n');
+
+        let mut connection = self.connection.lock().await;
+
+        let result = tokio::time::timeout(self.timeout, async {
+            let stream = match &mut *connection {
+                Some(stream) => stream,
+                None => connection.insert(BufStream::new(
+                    UnixStream::connect(&self.path)
+                        .await
+                        .map_err(|e| UnixSocketTransportError::Connect(self.path.clone(), e))?,
+                )),
+            };
+
+            stream.write_all(&line).await?;
+            stream.flush().await?;
+
+            if !reply {
+                return Ok(None);
+            }
+
+            let mut response = String::new();
+            if stream.read_line(&mut response).await? == 0 {
+                return Err(UnixSocketTransportError::Closed);
+            }
+
+            Ok(Some(response))
+        })
+        .await
+        .unwrap_or(Err(UnixSocketTransportError::Timeout));
+
+        if result.is_err() {
+            // Start over with a fresh connection, rather than risk reading a stale reply
+            *connection = None;
+        }
+
+        result
+    }
+}
+
+impl Transport for UnixSocketTransport {
+    type Error = UnixSocketTransportError;
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
+    async fn submit(&mut self, batch: Batch<'_>) -> Result<(), Self::Error> {
+        self.exchange(&Frame::Batch(batch), false).await?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all, ret(level = tracing::Level::TRACE)))]
+    async fn checkin(
+        &self,
+        session_properties: Map,
+    ) -> Result<crate::checkin::Checkin, Self::Error> {
+        let response = self
+            .exchange(&Frame::Checkin { session_properties }, true)
+            .await?
+            .unwrap_or_default();
+
+        Ok(serde_json::from_str(&response)?)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UnixSocketTransportError {
+    #[error("Failure connecting to '{0}': {1}")]
+    Connect(PathBuf, std::io::Error),
+
+    #[error("Failure talking to the IDS agent: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("The IDS agent closed the connection before replying")]
+    Closed,
+
+    #[error("Timed out talking to the IDS agent")]
+    Timeout,
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
@@ -0,0 +1,75 @@
+/// How a multivariate feature flag's variant matched the variants a caller knows about, from [`crate::Recorder::match_feature_variant`].
+///
+/// `T` is usually an enum with `#[serde(rename_all = "...")]`, so each server-side variant name deserializes to one arm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariantMatch<T> {
+    /// The variant is one of `T`'s.
+    Matched(T),
+    /// The flag is set to a variant `T` doesn't know about.
+    Unknown(serde_json::Value),
+    /// The flag isn't set.
+    Missing,
+}
+
+impl<T: serde::de::DeserializeOwned> VariantMatch<T> {
+    pub(crate) fn from_variant(key: &str, variant: Option<&serde_json::Value>) -> Self {
+        let Some(variant) = variant else {
+            return VariantMatch::Missing;
+        };
+
+        match serde_json::from_value(variant.clone()) {
+            Ok(matched) => VariantMatch::Matched(matched),
+            Err(e) => {
+                tracing::debug!(%e, key, %variant, "Feature flag has an unknown variant");
+                VariantMatch::Unknown(variant.clone())
+            }
+        }
+    }
+}
+
+impl<T> VariantMatch<T> {
+    /// The matched variant, or None if the flag is unset or its variant is unknown.
+    pub fn matched(self) -> Option<T> {
+        match self {
+            VariantMatch::Matched(matched) => Some(matched),
+            VariantMatch::Unknown(_) | VariantMatch::Missing => None,
+        }
+    }
+
+    /// The matched variant, or `default` if the flag is unset or its variant is unknown.
+    pub fn unwrap_or(self, default: T) -> T {
+        self.matched().unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VariantMatch;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Greeting {
+        Hello,
+        GoodDay,
+    }
+
+    #[test]
+    fn matches_known_variants() {
+        assert_eq!(
+            VariantMatch::from_variant("greeting", Some(&"good-day".into())),
+            VariantMatch::Matched(Greeting::GoodDay)
+        );
+        assert_eq!(
+            VariantMatch::<Greeting>::from_variant("greeting", Some(&"howdy".into())),
+            VariantMatch::Unknown("howdy".into())
+        );
+        assert_eq!(
+            VariantMatch::<Greeting>::from_variant("greeting", None),
+            VariantMatch::Missing
+        );
+        assert_eq!(
+            VariantMatch::<Greeting>::Missing.unwrap_or(Greeting::Hello),
+            Greeting::Hello
+        );
+    }
+}
@@ -1,78 +1,148 @@
-use tokio::sync::mpsc::channel;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc::{WeakSender, channel};
 use tokio::task::JoinHandle;
 use tracing::Instrument;
 
-use crate::collator::{Collator, SnapshotError};
-use crate::configuration_proxy::{ConfigurationProxy, ConfigurationProxyError};
-use crate::ds_correlation::Correlation;
-use crate::identity::AnonymousDistinctId;
+use crate::Recorder;
+use crate::checkin::{DEFAULT_REFRESH_INTERVAL, Feature};
+use crate::collator::{CollatedSignal, Collator, CollatorOptions, SnapshotError};
+use crate::configuration_proxy::{
+    ConfigurationProxy, ConfigurationProxyError, DEFAULT_REFRESH_JITTER, Jitter,
+};
+use crate::recorder::DEFAULT_FEATURE_POINTER_DEPTH;
 use crate::storage::Storage;
-use crate::submitter::Submitter;
+use crate::submitter::{
+    DEFAULT_FLUSH_INTERVAL, PauseSwitch, Submitter, SubmitterMetrics, SubmitterOverflowStrategy,
+};
 use crate::system_snapshot::SystemSnapshotter;
 use crate::transport::Transport;
-use crate::{DeviceId, DistinctId, Groups, Map, Recorder};
 
 pub struct Worker {
+    shutdown: ShutdownHandle,
     collator_task: JoinHandle<Result<(), SnapshotError>>,
     submitter_task: JoinHandle<()>,
     configuration_task: JoinHandle<Result<(), ConfigurationProxyError>>,
+    submitter_metrics: SubmitterMetrics,
+    to_submitter: WeakSender<CollatedSignal>,
+}
+
+/// The Builder's settings for the Worker and the tasks it spawns.
+pub(crate) struct WorkerOptions {
+    pub(crate) collator: CollatorOptions,
+    pub(crate) max_batch_size: Option<usize>,
+    pub(crate) max_pending_events: Option<usize>,
+    pub(crate) flush_interval: Option<Duration>,
+    pub(crate) flush_interval_jitter: Option<Duration>,
+    pub(crate) configuration_refresh_interval: Option<Duration>,
+    pub(crate) configuration_refresh_jitter: Option<f64>,
+    pub(crate) default_features: Option<HashMap<String, Feature<serde_json::Value>>>,
+    pub(crate) verify_transport_on_startup: bool,
+    pub(crate) feature_pointer_depth: Option<usize>,
 }
 
 impl Worker {
-    #[cfg_attr(
-        feature = "tracing-instrument",
-        tracing::instrument(skip(
-            anonymous_distinct_id,
-            distinct_id,
-            device_id,
-            facts,
-            groups,
-            system_snapshotter,
-            storage,
-            transport
-        ))
-    )]
-    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip_all))]
     pub(crate) async fn new<F: SystemSnapshotter, P: Storage, T: Transport + Sync + 'static>(
-        anonymous_distinct_id: Option<AnonymousDistinctId>,
-        distinct_id: Option<DistinctId>,
-        device_id: Option<DeviceId>,
-        facts: Option<Map>,
-        groups: Option<Groups>,
+        options: WorkerOptions,
         system_snapshotter: F,
         storage: P,
         transport: T,
     ) -> (Recorder, Worker) {
+        let WorkerOptions {
+            collator: collator_options,
+            max_batch_size,
+            max_pending_events,
+            flush_interval,
+            flush_interval_jitter,
+            configuration_refresh_interval,
+            configuration_refresh_jitter,
+            default_features,
+            verify_transport_on_startup,
+            feature_pointer_depth,
+        } = options;
+
         // Message flow:
         //
         // Recorder --> Configuration --\
         //          `----> Collator -------> Submitter
 
+        if verify_transport_on_startup && let Err(e) = transport.health_check().await {
+            tracing::warn!(%e, "The IDS transport failed its health check");
+        }
+
         let (to_configuration_proxy, configuration_proxy_rx) = channel(1000);
         let (to_collator, collator_rx) = channel(1000);
         let (to_submitter, submitter_rx) = channel(1000);
 
-        let recorder = Recorder::new(to_collator.clone(), to_configuration_proxy);
-        let mut configuration =
-            ConfigurationProxy::new(transport.clone(), configuration_proxy_rx, to_collator);
+        // DropOldest makes room among the Submitter's pending events, so they need a limit even if the Builder didn't set one
+        let max_pending_events = match collator_options.overflow_strategy {
+            SubmitterOverflowStrategy::DropOldest => {
+                max_pending_events.or(Some(to_submitter.max_capacity()))
+            }
+            SubmitterOverflowStrategy::Block | SubmitterOverflowStrategy::DropNewest => {
+                max_pending_events
+            }
+        };
+
+        let to_submitter_weak = to_submitter.downgrade();
+        let submitter_metrics = SubmitterMetrics::default();
+        let pause = PauseSwitch::default();
+        let (batching_tx, batching_rx) = tokio::sync::watch::channel(Default::default());
+        let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+
+        let recorder = Recorder::new(
+            to_collator.clone(),
+            to_configuration_proxy,
+            feature_pointer_depth.unwrap_or(DEFAULT_FEATURE_POINTER_DEPTH),
+        );
+        let mut configuration = ConfigurationProxy::new(
+            transport.clone(),
+            configuration_proxy_rx,
+            to_collator,
+            pause.clone(),
+            batching_tx,
+            configuration_refresh_interval.unwrap_or(DEFAULT_REFRESH_INTERVAL),
+            Jitter::new(configuration_refresh_jitter.unwrap_or(DEFAULT_REFRESH_JITTER)),
+            shutdown_rx.clone(),
+        );
         let collator = Collator::new(
+            collator_options,
             system_snapshotter,
             storage,
             collator_rx,
             to_submitter,
-            anonymous_distinct_id,
-            distinct_id,
-            device_id,
-            facts.unwrap_or_default(),
-            groups.unwrap_or_default(),
-            Correlation::import(),
+            submitter_metrics.clone(),
+            shutdown_rx,
         )
         .await;
-        let submitter = Submitter::new(transport, submitter_rx);
+        let submitter = Submitter::new(
+            transport,
+            submitter_rx,
+            max_batch_size,
+            max_pending_events,
+            flush_interval.unwrap_or(DEFAULT_FLUSH_INTERVAL),
+            flush_interval_jitter.unwrap_or_default(),
+            submitter_metrics.clone(),
+            pause,
+            batching_rx,
+        );
 
         configuration
-            .bootstrap_checkin(collator.get_checkin().cloned())
+            .bootstrap_checkin(
+                collator.get_checkin().cloned(),
+                collator.get_checkin_fetched_at(),
+            )
             .await;
+        configuration.bootstrap_defaults(
+            default_features
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, feature)| (key, Arc::new(feature)))
+                .collect(),
+        );
 
         let span = tracing::debug_span!("spawned worker");
 
@@ -81,14 +151,50 @@ impl Worker {
         let submitter_task = tokio::spawn(submitter.execute().instrument(span));
 
         let worker = Self {
+            shutdown: ShutdownHandle {
+                signal: Arc::new(shutdown_tx),
+            },
             collator_task,
             configuration_task,
             submitter_task,
+            submitter_metrics,
+            to_submitter: to_submitter_weak,
         };
 
         (recorder, worker)
     }
 
+    /// A handle for stopping the worker while Recorders are still around.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// The number of events successfully submitted over the Transport.
+    pub fn submitted_event_count(&self) -> u64 {
+        self.submitter_metrics.submitted_event_count()
+    }
+
+    /// The number of batches the Transport failed to submit.
+    pub fn failed_batch_count(&self) -> u64 {
+        self.submitter_metrics.failed_batch_count()
+    }
+
+    /// The number of events discarded because the server paused sending events, because too many were waiting to be submitted, or by the overflow strategy.
+    pub fn dropped_event_count(&self) -> u64 {
+        self.submitter_metrics.dropped_event_count()
+    }
+
+    /// The number of events waiting to be submitted, including events not yet received by the Submitter.
+    pub fn pending_event_count(&self) -> usize {
+        let queued = self
+            .to_submitter
+            .upgrade()
+            .map(|tx| tx.max_capacity() - tx.capacity())
+            .unwrap_or(0);
+
+        self.submitter_metrics.buffered_event_count() + queued
+    }
+
     #[cfg_attr(feature = "tracing-instrument", tracing::instrument(skip(self)))]
     pub async fn wait(self) {
         // Note these three tasks have to shut down in this order.
@@ -96,7 +202,7 @@ impl Worker {
         // They are also all tokio::spawn'd, so they are all executing in the background, without needing to be awaited.
         //
         // The Submitter won't shut down if the Collator is still running.
-        // The ConfigurationProxy and Collator tasks won't shut down if any Recorders are still out there.
+        // The ConfigurationProxy and Collator tasks won't shut down if any Recorders are still out there, unless the ShutdownHandle is used.
         //
         // I'm liking keeping these shut down in this explicit order so we
         // don't accidentally create a more complicated situation where these
@@ -114,3 +220,18 @@ impl Worker {
         }
     }
 }
+
+/// Stops a Worker's tasks, even while Recorders are still around.
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle {
+    signal: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Stop checking in, submit the events already recorded, and let `Worker::wait` return.
+    ///
+    /// Recorder calls made afterwards are logged and otherwise do nothing.
+    pub fn shutdown(&self) {
+        self.signal.send_replace(true);
+    }
+}